@@ -0,0 +1,170 @@
+#ifndef STATE_GRAPH_H
+#define STATE_GRAPH_H
+
+/* Generated from src/ffi.rs by cbindgen (see cbindgen.toml) -- do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum {
+  SG_ALGORITHM_NAIVE = 0,
+  SG_ALGORITHM_SIMPLE = 1,
+  SG_ALGORITHM_TARJAN = 2,
+  SG_ALGORITHM_JUMP = 3,
+} SgAlgorithm;
+
+typedef enum {
+  SG_PROBABILITY_ZERO = 0,
+  SG_PROBABILITY_POSITIVE = 1,
+  SG_PROBABILITY_ONE = 2,
+  SG_PROBABILITY_UNKNOWN = 3,
+} SgProbability;
+
+typedef enum {
+  SG_RESULT_OK = 0,
+  SG_RESULT_ALREADY_CLOSED = 1,
+  SG_RESULT_SELF_LOOP = 2,
+  SG_RESULT_ALREADY_LIVE = 3,
+  SG_RESULT_CONTRADICTORY_NOT_REACHABLE = 4,
+  SG_RESULT_NULL_HANDLE = 5,
+  SG_RESULT_UNIVERSAL_UNSUPPORTED = 6,
+  SG_RESULT_NOT_BUCHI = 7,
+  SG_RESULT_MERGE_UNSUPPORTED = 8,
+  SG_RESULT_CONTRADICTORY_MERGE = 9,
+  SG_RESULT_SUBSUMED_UNSUPPORTED = 10,
+  SG_RESULT_CONTRADICTORY_SUBSUMED = 11,
+  SG_RESULT_INTERNAL_ERROR = 12,
+} SgResult;
+
+typedef enum {
+  SG_STATUS_LIVE = 0,
+  SG_STATUS_DEAD = 1,
+  SG_STATUS_UNKNOWN = 2,
+  SG_STATUS_OPEN = 3,
+  SG_STATUS_UNSEEN = 4,
+} SgStatus;
+
+typedef struct SgStateGraph SgStateGraph;
+
+// Create a new, empty state graph, using the given SgAlgorithm.
+// Returns null if algorithm is out of range. Free with sg_free.
+SgStateGraph *sg_new(uint32_t algorithm);
+
+// Create a new, empty state graph in Büchi mode, using the given
+// SgAlgorithm. Returns null if algorithm is out of range or doesn't support
+// Büchi mode (Jump). Free with sg_free.
+SgStateGraph *sg_new_buchi(uint32_t algorithm);
+
+// Create a new, empty state graph in Markov mode, using the given
+// SgAlgorithm. Returns null if algorithm is out of range. Free with sg_free.
+SgStateGraph *sg_new_markov(uint32_t algorithm);
+
+// Free a state graph. Does nothing if graph is null.
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+void sg_free(SgStateGraph *graph);
+
+// Add a transition from an open state v1 to any state v2.
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_add_transition(SgStateGraph *graph, size_t v1, size_t v2);
+
+// Add a transition from an open state v1 to any state v2, labeled with
+// label (e.g. a symbol ID).
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_add_labeled_transition(SgStateGraph *graph, size_t v1, size_t v2, size_t label);
+
+// Mark an open state as closed.
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_mark_closed(SgStateGraph *graph, size_t v);
+
+// Mark an open state as live.
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_mark_live(SgStateGraph *graph, size_t v);
+
+// Mark a state as live for a target class (target 0 is the default class,
+// as in sg_mark_live). The state must be open for the class.
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_mark_live_for(SgStateGraph *graph, size_t v, size_t target);
+
+// Indicate that v2 is not reachable from v1.
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_not_reachable(SgStateGraph *graph, size_t v1, size_t v2);
+
+// Declare two states equal: from then on they are one state. Not
+// supported in Büchi or Markov mode, or for universal states.
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_merge(SgStateGraph *graph, size_t v1, size_t v2);
+
+// Indicate the language of v1 is included in that of v2: if v1 is live
+// then so is v2, and if v2 is dead then so is v1. Not supported in Markov
+// mode.
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_subsumed(SgStateGraph *graph, size_t v1, size_t v2);
+
+// Mark a state (open or not) as initial.
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_mark_initial(SgStateGraph *graph, size_t v);
+
+// Mark an open state as universal: live only if all of its successors are.
+// Only supported by the Naive and Simple algorithms.
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_mark_universal(SgStateGraph *graph, size_t v);
+
+// Mark an open state as accepting (in Büchi mode only).
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgResult sg_mark_accepting(SgStateGraph *graph, size_t v);
+
+// Get the status of a state (SG_STATUS_UNSEEN if it has not been seen).
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgStatus sg_get_status(const SgStateGraph *graph, size_t v);
+
+// Get the status of a state for a target class (SG_STATUS_UNSEEN if it has
+// not been seen).
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgStatus sg_get_status_for(const SgStateGraph *graph, size_t v, size_t target);
+
+// Whether a state is reachable from an initial state (false if graph is
+// null).
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+bool sg_is_reachable_from_initial(const SgStateGraph *graph, size_t v);
+
+// In Markov mode, the probability that a state reaches a state marked live
+// (SG_PROBABILITY_UNKNOWN if it is not known yet, or graph is null).
+//
+// # Safety
+// graph must be null or a handle from sg_new that has not been freed.
+SgProbability sg_get_probability(const SgStateGraph *graph, size_t v);
+
+#endif /* STATE_GRAPH_H */
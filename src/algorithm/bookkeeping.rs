@@ -0,0 +1,330 @@
+/*
+    What the implementations of the StateGraph trait have in common: how the
+    bookkeeping in status_log follows the graph, for distances to live,
+    universal states, Markov mode, Subsumed hints, merges, initial states
+    and target classes.
+
+    Each algorithm gives access to its graph and to the parts of the
+    bookkeeping it owns (parts and parts_mut), and implements the few hooks
+    where it differs:
+    - set_status: how the status is stored (and what else changes with it)
+    - iter_succs: forward edges, including those the algorithm hasn't added
+      to the graph yet
+    - mark_subsumed_dead: how a state found Dead through a Subsumed hint is
+      marked, and what to check next
+*/
+
+use crate::graph::DiGraph;
+use crate::interface::{StateGraph, StateID, Status, Transaction};
+use crate::status_log::{
+    Classes, Kinds, Markov, Reachability, StatusLog, Subsumption,
+};
+use std::fmt::Debug;
+use std::mem;
+
+pub(super) struct Parts<'a, V, L> {
+    pub graph: &'a DiGraph<V, L>,
+    pub log: &'a StatusLog<V>,
+    pub reach: &'a Reachability<V>,
+    pub kinds: &'a Kinds<V>,
+    pub classes: &'a Classes<V>,
+    pub markov: &'a Markov<V>,
+    pub subsumed: &'a Subsumption<V>,
+}
+
+pub(super) struct PartsMut<'a, V, L> {
+    pub graph: &'a DiGraph<V, L>,
+    pub log: &'a mut StatusLog<V>,
+    pub reach: &'a mut Reachability<V>,
+    pub kinds: &'a Kinds<V>,
+    pub classes: &'a mut Classes<V>,
+    pub markov: &'a mut Markov<V>,
+    pub subsumed: &'a mut Subsumption<V>,
+}
+
+pub(super) trait Bookkeeping<V: StateID>: StateGraph<V> {
+    type Label: Clone + Debug + PartialEq;
+
+    /* Accessors and hooks */
+    fn parts(&self) -> Parts<'_, V, Self::Label>;
+    fn parts_mut(&mut self) -> PartsMut<'_, V, Self::Label>;
+    fn set_status(&mut self, v: V, status: Status);
+    fn iter_succs(&self, v: V) -> impl Iterator<Item = V> + '_;
+    fn mark_subsumed_dead(&mut self, v: V) {
+        self.set_status(v, Status::Dead);
+    }
+
+    /*
+        Live states
+    */
+    fn calculate_new_live_states(&mut self, v: V, dist: usize) {
+        // Lower the distance to live of v to dist, updating distances
+        // backwards from v (BFS), and mark the states that now have a
+        // distance Live. Repeat from the universal states that are now Live
+        // because all of their successors are.
+        let mut todo = vec![(v, dist)];
+        while let Some((v, dist)) = todo.pop() {
+            let PartsMut { graph, log, kinds, .. } = self.parts_mut();
+            let new_live = log.lower_distance(
+                v,
+                dist,
+                |u| graph.iter_bck_edges_unmerged(u),
+                |u| kinds.is_universal(u),
+            );
+            for &u in &new_live {
+                debug_assert!(!self.is_dead(u));
+                self.set_status(u, Status::Live);
+            }
+            self.calculate_almost_sure(&new_live);
+            self.calculate_subsumed_live(&new_live);
+            if !self.parts().kinds.has_universal() {
+                continue;
+            }
+            for u in new_live {
+                let preds: Vec<V> =
+                    self.parts().graph.iter_bck_edges_unmerged(u).collect();
+                for p in preds {
+                    if let Some(dist) = self.universal_live_distance(p) {
+                        todo.push((p, dist));
+                    }
+                }
+            }
+        }
+    }
+    fn universal_live_distance(&self, v: V) -> Option<usize> {
+        // If v is a closed universal state that isn't Live yet, but all of
+        // its successors are, return its distance to live
+        let p = self.parts();
+        if !p.kinds.is_universal(v) || !self.is_unknown(v) {
+            return None;
+        }
+        let mut min_dist = None;
+        for w in p.graph.iter_fwd_edges_unmerged(v) {
+            let dist = p.log.distance(w)?;
+            min_dist = Some(min_dist.map_or(dist, |min: usize| min.min(dist)));
+        }
+        Some(min_dist.map_or(0, |min| min + 1))
+    }
+    fn calculate_universal_live(&mut self, v: V) {
+        // Check if a newly closed universal state is Live
+        if let Some(dist) = self.universal_live_distance(v) {
+            if dist == 0 {
+                // No successors: Live by itself, as if marked live
+                self.parts_mut().log.mark_live(v);
+            }
+            self.calculate_new_live_states(v, dist);
+        }
+    }
+    fn calculate_accepting_self_loop(&mut self, v: V) {
+        // In Büchi mode, a newly closed accepting state with a self-loop is
+        // a cycle of closed states through an accepting state by itself:
+        // mark it live, as the accepting states on longer cycles are
+        let p = self.parts();
+        if p.kinds.is_accepting(v)
+            && p.kinds.has_self_loop(v)
+            && self.is_unknown(v)
+        {
+            self.parts_mut().log.mark_live(v);
+            self.calculate_new_live_states(v, 0);
+        }
+    }
+    fn calculate_almost_sure(&mut self, vs: &[V]) {
+        // In Markov mode, mark almost surely live the closed Live states
+        // backward from vs whose successors are all almost surely live (or
+        // marked live), allowing cycles: from such a cycle, a state marked
+        // live is eventually reached with probability 1.
+        let p = self.parts();
+        if !p.markov.is_markov() {
+            return;
+        }
+        let (log, markov) = (p.log, p.markov);
+        let candidate =
+            |u| !markov.is_sure(u, log) && log.is_closed(u) && self.is_live(u);
+        let sure = p.graph.sure_search_bck_unmerged(
+            vs.iter().copied(),
+            candidate,
+            |u| markov.is_sure(u, log),
+        );
+        let markov = self.parts_mut().markov;
+        for w in sure {
+            markov.mark_almost_sure(w);
+        }
+    }
+
+    /*
+        Subsumed hints
+    */
+    fn add_subsumed(&mut self, v1: V, v2: V) {
+        // The language of v1 is included in that of v2 (see
+        // subsumed_unchecked); both are seen
+        self.parts_mut().subsumed.subsume(v1, v2);
+        if self.parts().log.distance(v1).is_some() {
+            self.calculate_subsumed_live(&[v1]);
+        }
+        self.calculate_subsumed_dead(&[v1]);
+    }
+    fn calculate_subsumed_live(&mut self, vs: &[V]) {
+        // States subsuming a newly Live state are Live, as if marked live
+        // (see subsumed_unchecked)
+        for &v in vs {
+            let p = self.parts();
+            let supersets: Vec<V> = p.subsumed.iter_supersets(v).collect();
+            for w in supersets {
+                if self.parts().log.distance(w).is_none() {
+                    self.parts_mut().log.mark_live(w);
+                    self.calculate_new_live_states(w, 0);
+                }
+            }
+        }
+    }
+    fn is_subsumed_by_dead(&self, v: V) -> bool {
+        // Whether v (or a state merged with it) is subsumed by a Dead state
+        let p = self.parts();
+        p.graph
+            .iter_aliases(v)
+            .any(|u| p.subsumed.iter_supersets(u).any(|w| self.is_dead(w)))
+    }
+    fn calculate_doomed(&mut self, v: V) {
+        // Mark v and the states reachable from it as doomed
+        let mut subsumed = mem::take(self.parts_mut().subsumed);
+        let graph = self.parts().graph;
+        subsumed.doom(v, |u| self.iter_succs(u), |u| graph.iter_aliases(u));
+        *self.parts_mut().subsumed = subsumed;
+    }
+    fn calculate_subsumed_dead(&mut self, vs: &[V]) {
+        // Closed states subsumed by a Dead state are Dead, and so are the
+        // closed states reachable from those (doomed; see
+        // subsumed_unchecked): check vs and the states queued since
+        // (mark_subsumed_dead checks the states that may be Dead in turn)
+        if !self.parts().subsumed.has_subsumed() {
+            return;
+        }
+        for &v in vs {
+            self.parts_mut().subsumed.push_queue(v);
+        }
+        while let Some(v) = self.parts_mut().subsumed.pop_queue() {
+            if self.is_unknown(v)
+                && (self.parts().subsumed.is_doomed(v)
+                    || self.is_subsumed_by_dead(v))
+            {
+                self.mark_subsumed_dead(v);
+                self.calculate_doomed(v);
+            }
+        }
+    }
+
+    /*
+        Initial states, merges and target classes
+    */
+    fn calculate_reachable_from_initial(&mut self, v: V) {
+        // Mark v and the states reachable from it as reachable from an
+        // initial state
+        let mut reach = mem::take(self.parts_mut().reach);
+        let graph = self.parts().graph;
+        reach.reach(v, |u| self.iter_succs(u), |u| graph.iter_aliases(u));
+        *self.parts_mut().reach = reach;
+    }
+    fn calculate_merged(&mut self, v1: V, v2: V) {
+        // Once the merge of v1 and v2 is recorded as transitions both ways
+        // between them (in the unmerged graph), update everything that
+        // follows transitions, as in add_labeled_transition_unchecked, and
+        // close both if either is closed
+        for (u, w) in [(v1, v2), (v2, v1)] {
+            if let Some(dist) = self.parts().log.distance(w) {
+                self.calculate_new_live_states(u, dist + 1);
+            }
+            let PartsMut { graph, classes, .. } = self.parts_mut();
+            classes
+                .add_transition_for(u, w, |x| graph.iter_bck_edges_unmerged(x));
+            if self.parts().reach.is_reached(u) {
+                self.calculate_reachable_from_initial(w);
+            }
+            if self.parts().subsumed.is_doomed(u) {
+                self.calculate_doomed(w);
+            }
+        }
+        let log = self.parts().log;
+        if log.is_closed(v1) || log.is_closed(v2) {
+            self.mark_closed_merged(v1);
+            self.mark_closed_merged(v2);
+            // (The algorithm may not have added the transitions yet)
+            self.calculate_closed_for_classes(v1, &[(v1, v2), (v2, v1)]);
+        }
+    }
+    fn mark_closed_merged(&mut self, v: V) {
+        // Mark v closed, along with every state merged with it: they are one
+        // state (see merge_unchecked)
+        let PartsMut { graph, log, .. } = self.parts_mut();
+        if graph.is_seen(v) {
+            for u in graph.iter_aliases(v) {
+                log.mark_closed(u);
+            }
+        }
+        log.mark_closed(v);
+    }
+    fn calculate_closed_for_classes(&mut self, v: V, extra: &[(V, V)]) {
+        // Find the states that only reach closed states now that the seen
+        // state v is closed: they are Dead for the other classes (see
+        // status_for). extra are transitions not in iter_succs yet.
+        let mut classes = mem::take(self.parts_mut().classes);
+        let Parts { graph, log, .. } = self.parts();
+        let succs = |u| {
+            let extra =
+                extra.iter().filter(move |&&(x, _)| graph.is_same_vertex(x, u));
+            self.iter_succs(u).chain(extra.map(|&(_, w)| w))
+        };
+        classes.close(
+            v,
+            |u| log.is_closed(u),
+            succs,
+            |u| graph.iter_bck_edges(u),
+            |u| graph.iter_aliases(u),
+        );
+        *self.parts_mut().classes = classes;
+    }
+    fn status_for(&self, v: V, class: usize) -> Option<Status> {
+        // See get_status_for. O(1): a state is Live for the class once it
+        // can reach a state marked live for it, and Dead once it is closed
+        // and can only reach closed states, whatever its status for the
+        // default class.
+        let status = self.get_status(v)?;
+        if class == 0 {
+            return Some(status);
+        }
+        let p = self.parts();
+        Some(p.classes.status_for(v, class, p.log.is_closed(v)))
+    }
+
+    /*
+        Batches
+    */
+    fn split_batch<'t>(
+        &mut self,
+        ts: &'t [Transaction<V>],
+    ) -> &'t [Transaction<V>] {
+        // A merge or Subsumed hint is checked against the Dead states found
+        // so far, so the batch ends before each one: process everything up
+        // to the last of them, and return the rest of the batch
+        let mut ts = ts;
+        while let Some(i) = ts.iter().position(|t| {
+            matches!(t, Transaction::Merge(_, _) | Transaction::Subsumed(_, _))
+        }) {
+            self.process_batch(&ts[..i]);
+            self.process(ts[i]);
+            ts = &ts[i + 1..];
+        }
+        ts
+    }
+}
+
+pub(super) fn merge_status(s1: Status, s2: Status) -> Status {
+    // Status of two merged states, once calculate_merged has made them
+    // both Live or neither, and closed both if either was (so they are only
+    // different if one is Open and the other is Unknown)
+    if s1 == s2 {
+        s1
+    } else {
+        debug_assert!(s1 == Status::Open || s2 == Status::Open);
+        Status::Unknown
+    }
+}
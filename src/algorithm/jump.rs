@@ -4,33 +4,60 @@
     of states ahead at once.
 */
 
-use crate::debug_counter::DebugCounter;
-use crate::graph::DiGraph;
-use crate::interface::{StateGraph, Status};
+use super::bookkeeping::{Bookkeeping, Parts, PartsMut};
+use crate::graph::{DiGraph, REACHABLE_SEARCH_MAX};
+use crate::interface::{
+    Kind, StateGraph, StateID, Status, StatusChange, Transaction,
+};
+use crate::search::DepthFirstSearch;
+use crate::status_log::{
+    Classes, Kinds, Markov, Reachability, StatusLog, Subsumption,
+};
 use std::collections::{HashSet, LinkedList};
 use std::iter;
 
 // Maximum amount of work when merging not-reachable sets
 const NOT_REACHABLE_MAX: usize = 10;
 
-#[derive(Debug, Default, PartialEq)]
-struct Node {
+#[derive(Clone, Debug)]
+pub(super) struct Node<V> {
     // Jump list: nonempty for closed vertices.
     // First is a real edge, and the ith is approximately 2^i edges forward.
-    jumps: Vec<usize>,
+    jumps: Vec<V>,
 
-    // Reserve list: forward edges not added to graph.
-    reserve: LinkedList<usize>,
+    // Reserve list: forward edges not added to graph, with their original
+    // source (an alias of this node once merged).
+    reserve: LinkedList<(V, V)>,
 
     // Not reachable set: nodes known to be not reachable from this node
-    not_reachable: HashSet<usize>,
+    not_reachable: HashSet<V>,
 
     // Categorized status, same as in other algorithms
     status: Status,
 }
-fn merge_nodes(mut n1: Node, mut n2: Node) -> Node {
+impl<V> Default for Node<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            jumps: Default::default(),
+            reserve: Default::default(),
+            not_reachable: Default::default(),
+            status: Default::default(),
+        }
+    }
+}
+impl<V: StateID> PartialEq for Node<V> {
+    // Can't derive automatically because HashSet<V> needs V: Eq + Hash
+    fn eq(&self, other: &Self) -> bool {
+        self.jumps == other.jumps
+            && self.reserve == other.reserve
+            && self.not_reachable == other.not_reachable
+            && self.status == other.status
+    }
+}
+fn merge_nodes<V: StateID>(mut n1: Node<V>, mut n2: Node<V>) -> Node<V> {
     // Note: result will be Status::Open!
-    let mut result: Node = Default::default();
+    let mut result: Node<V> = Default::default();
     debug_assert!(n1.status == Status::Unknown || n1.status == Status::Open);
     debug_assert!(n2.status == Status::Unknown || n2.status == Status::Open);
     debug_assert_eq!(result.status, Status::Open);
@@ -46,68 +73,93 @@ fn merge_nodes(mut n1: Node, mut n2: Node) -> Node {
         // canonical vertex; so, when querying the set,
         // it is better to query original vertex names rather than
         // trying to call get_canon_vertex first
-        println!("Copying not reachable: {}", v);
+        // println!("Copying not reachable: {:?}", v);
         result.not_reachable.insert(v);
     }
     result
 }
+fn merge_decided_nodes<V: StateID>(n1: Node<V>, n2: Node<V>) -> Node<V> {
+    // Both Live or both Dead: neither has jumps or reserve edges left
+    debug_assert_eq!(n1.status, n2.status);
+    debug_assert!(n1.status == Status::Live || n1.status == Status::Dead);
+    debug_assert!(n1.jumps.is_empty() && n2.jumps.is_empty());
+    let status = n1.status;
+    let n1 = Node { status: Status::Open, ..n1 };
+    let n2 = Node { status: Status::Open, ..n2 };
+    Node { status, ..merge_nodes(n1, n2) }
+}
 
-#[derive(Debug, Default)]
-pub struct JumpStateGraph {
-    graph: DiGraph<usize, Node>,
-    additional_time: DebugCounter,
+#[derive(Debug)]
+pub struct JumpStateGraph<V = usize> {
+    graph: DiGraph<V, Node<V>>,
+    // Status changes not yet drained, and states marked live
+    log: StatusLog<V>,
+    // The rest of the bookkeeping, by concern (see status_log)
+    reach: Reachability<V>,
+    kinds: Kinds<V>,
+    classes: Classes<V>,
+    markov: Markov<V>,
+    subsumed: Subsumption<V>,
+    // While processing a batch: closed states whose jumps were cleared
+    // by a dead state, still to be re-initialized
+    batch: Option<Vec<V>>,
 }
-impl JumpStateGraph {
+impl<V> Default for JumpStateGraph<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        let graph = DiGraph::new_indexed(|node: &Node<V>| node.status as usize);
+        Self {
+            graph,
+            log: Default::default(),
+            reach: Default::default(),
+            kinds: Default::default(),
+            classes: Default::default(),
+            markov: Default::default(),
+            subsumed: Default::default(),
+            batch: None,
+        }
+    }
+}
+impl<V: StateID> JumpStateGraph<V> {
     /* Node label manipulation */
-    fn get_node(&self, v: usize) -> &Node {
+    fn get_node(&self, v: V) -> &Node<V> {
         debug_assert!(self.is_seen(v));
         self.graph.get_label(v).unwrap()
     }
-    fn get_node_mut(&mut self, v: usize) -> &mut Node {
+    fn get_node_mut(&mut self, v: V) -> &mut Node<V> {
         debug_assert!(self.is_seen(v));
         self.graph.get_label_mut(v).unwrap()
     }
-    // Status getters / setters
-    fn set_status(&mut self, v: usize, status: Status) {
-        // println!("  Set status: {} {:?}", v, status);
-        debug_assert!(self.is_seen(v));
-        self.get_node_mut(v).status = status;
-        // Mark live in particular deletes jumps and reserve edges.
-        if status == Status::Live {
-            self.get_node_mut(v).jumps.clear();
-            self.get_node_mut(v).reserve.clear();
-        }
-    }
     // Reserve edges getters / setters
-    fn push_reserve(&mut self, v: usize, w: usize) {
+    fn push_reserve(&mut self, v: V, w: V) {
         debug_assert!(self.is_seen(v));
         debug_assert!(!self.is_closed(v));
-        self.get_node_mut(v).reserve.push_back(w);
+        self.get_node_mut(v).reserve.push_back((v, w));
     }
-    fn pop_reserve(&mut self, v: usize) -> Option<usize> {
+    fn pop_reserve(&mut self, v: V) -> Option<(V, V)> {
         debug_assert!(self.is_seen(v));
         debug_assert!(!self.is_closed(v));
         self.get_node_mut(v).reserve.pop_back()
     }
     // Jump list getters / setters
-    fn get_nth_jump(&self, v: usize, n: usize) -> usize {
+    fn get_nth_jump(&self, v: V, n: usize) -> V {
         debug_assert!(self.is_closed(v));
         debug_assert!(self.get_node(v).jumps.len() > n);
         self.get_node(v).jumps[n]
     }
-    fn get_first_jump(&self, v: usize) -> usize {
+    fn get_first_jump(&self, v: V) -> V {
         // println!("get_first_jump: {} {}", v, self.is_closed(v));
         debug_assert!(self.is_closed(v));
         debug_assert!(!self.get_node(v).jumps.is_empty());
         self.get_nth_jump(v, 0)
     }
-    fn get_last_jump(&self, v: usize) -> usize {
+    fn get_last_jump(&self, v: V) -> V {
         // Get the current last element in the jumps list.
         debug_assert!(self.is_closed(v));
         debug_assert!(!self.get_node(v).jumps.is_empty());
         *self.get_node(v).jumps.last().unwrap()
     }
-    fn get_num_jumps(&self, v: usize) -> usize {
+    fn get_num_jumps(&self, v: V) -> usize {
         // Get the length of the jumps list
         // (open vertices implicitly have no jumps)
         if self.is_closed(v) {
@@ -117,31 +169,31 @@ impl JumpStateGraph {
             0
         }
     }
-    fn pop_last_jump(&mut self, v: usize) {
+    fn pop_last_jump(&mut self, v: V) {
         // Remove the current last element in the jumps list.
         // println!("  Popping last jump: {}", v);
         debug_assert!(self.is_closed(v));
         debug_assert!(!self.get_node(v).jumps.is_empty());
         self.get_node_mut(v).jumps.pop();
     }
-    fn clear_jumps(&mut self, v: usize) {
+    fn clear_jumps(&mut self, v: V) {
         // println!("  Clearing jumps: {}", v);
         debug_assert!(self.is_closed(v));
         debug_assert!(!self.get_node(v).jumps.is_empty());
         self.get_node_mut(v).jumps.clear();
     }
-    fn push_last_jump(&mut self, v: usize, w: usize) {
+    fn push_last_jump(&mut self, v: V, w: V) {
         // Add a last element to the jumps list.
         // println!("  Pushing jump: {}, {}", v, w);
         debug_assert!(self.is_closed(v));
         self.get_node_mut(v).jumps.push(w);
     }
     // Not reachable getters and setters
-    fn is_not_reachable(&self, v: usize, w: usize) -> bool {
+    fn is_not_reachable(&self, v: V, w: V) -> bool {
         // println!("Check not reachable: {} {}", v, w);
         self.get_node(v).not_reachable.contains(&w)
     }
-    fn add_not_reachable(&mut self, v: usize, w: usize) {
+    fn add_not_reachable(&mut self, v: V, w: V) {
         // println!("Adding not reachable: {} {}", v, w);
         self.get_node_mut(v).not_reachable.insert(w);
     }
@@ -163,7 +215,7 @@ impl JumpStateGraph {
         Also shortcuts using the NotReachable sets if it can determine
         early that w is not reachable from v.
     */
-    fn is_root(&mut self, v: usize, end: usize) -> bool {
+    fn is_root(&mut self, v: V, end: V) -> bool {
         debug_assert!(self.is_unknown(v) || self.is_open(v));
         debug_assert!(self.is_open(end));
         if self.is_open(v) {
@@ -190,8 +242,8 @@ impl JumpStateGraph {
         Merge the path from vertex v to the Open vertex it currently points
         to.
     */
-    fn merge_path_from(&mut self, v: usize) {
-        let to_merge: Vec<usize> = {
+    fn merge_path_from(&mut self, v: V) {
+        let to_merge: Vec<V> = {
             iter::successors(Some(v), |&w| {
                 // println!("{} {:?} {}", w, self.get_status(w));
                 if self.is_closed(w) {
@@ -212,9 +264,14 @@ impl JumpStateGraph {
         Initialize function for a newly closed vertex, to find an univisted
         vertex.
     */
-    fn initialize_jumps(&mut self, v: usize) {
+    fn initialize_jumps(&mut self, v: V) {
         // println!("Initializing jumps from: {}", v);
-        while let Some(w) = self.pop_reserve(v) {
+        while let Some((u, w)) = self.pop_reserve(v) {
+            // The jump list only uses the first edge, but keep every
+            // processed edge so that closed vertices have all their
+            // fwd-edges (used for explanations and almost-sure searches;
+            // labels are only needed on back edges, for witnesses).
+            self.graph.ensure_edge_fwd(u, w, None);
             if self.is_dead(w) {
                 // println!("  (dead)");
                 continue;
@@ -227,16 +284,23 @@ impl JumpStateGraph {
                 // println!("  (setting jump and returning)");
                 debug_assert!(self.get_node(v).jumps.is_empty());
                 self.set_status(v, Status::Unknown);
-                self.graph.ensure_edge_fwd(v, w);
                 self.push_last_jump(v, w);
                 return;
             }
         }
         // No more edges -- v is dead.
-        // Recurse on all edges backwards from v.
+        self.mark_dead(v);
+    }
+
+    /*
+        Mark a vertex dead, and recurse on all edges backwards from it.
+    */
+    fn mark_dead(&mut self, v: V) {
+        debug_assert!(self.get_node(v).jumps.is_empty());
+        debug_assert!(self.get_node(v).reserve.is_empty());
         self.set_status(v, Status::Dead);
         // println!("Found Dead: {}", v);
-        let to_recurse: HashSet<usize> = self
+        let to_recurse: HashSet<V> = self
             .graph
             .iter_bck_edges(v)
             .filter(|&u| self.is_unknown(u))
@@ -250,6 +314,12 @@ impl JumpStateGraph {
             self.set_status(u, Status::Open);
         }
         // Then go through and initialize jumps for each one
+        // (or in a batch, wait until the end of the batch: by then more of
+        // their edges may be dead, which saves re-initializing them again)
+        if let Some(pending) = &mut self.batch {
+            pending.extend(to_recurse);
+            return;
+        }
         for &u in &to_recurse {
             // println!("  Recursing on: {}", u);
             self.initialize_jumps(u);
@@ -257,54 +327,348 @@ impl JumpStateGraph {
     }
 
     /*
-        Calculate new live states
+        Truncate the jump lists leading through a closed vertex v, before v
+        is reopened: the jumps past v may no longer be on the path from
+        each state once v is merged (only the first jump, a real edge, is
+        kept).
     */
-    fn calculate_new_live_states(&mut self, v: usize) {
-        // Same fn as in Naive
-        if self.is_live(v) {
-            let new_live: HashSet<usize> = self
+    fn truncate_jumps_through(&mut self, v: V) {
+        let mut seen = HashSet::new();
+        let mut stack = vec![self.graph.get_canon_vertex(v)];
+        while let Some(x) = stack.pop() {
+            let preds: Vec<V> = self
                 .graph
-                .dfs_bck(iter::once(v), |u| {
-                    debug_assert!(!self.is_dead(u));
-                    !self.is_live(u)
+                .iter_bck_edges(x)
+                .filter(|&u| self.is_unknown(u))
+                .filter(|&u| {
+                    self.graph.is_same_vertex(self.get_first_jump(u), x)
                 })
                 .collect();
-            for &u in new_live.iter() {
-                self.set_status(u, Status::Live);
+            for u in preds {
+                if seen.insert(u) {
+                    self.get_node_mut(u).jumps.truncate(1);
+                    stack.push(u);
+                }
+            }
+        }
+    }
+}
+impl<V: StateID> Bookkeeping<V> for JumpStateGraph<V> {
+    type Label = Node<V>;
+    fn parts(&self) -> Parts<'_, V, Self::Label> {
+        Parts {
+            graph: &self.graph,
+            log: &self.log,
+            reach: &self.reach,
+            kinds: &self.kinds,
+            classes: &self.classes,
+            markov: &self.markov,
+            subsumed: &self.subsumed,
+        }
+    }
+    fn parts_mut(&mut self) -> PartsMut<'_, V, Self::Label> {
+        PartsMut {
+            graph: &self.graph,
+            log: &mut self.log,
+            reach: &mut self.reach,
+            kinds: &self.kinds,
+            classes: &mut self.classes,
+            markov: &mut self.markov,
+            subsumed: &mut self.subsumed,
+        }
+    }
+    fn set_status(&mut self, v: V, status: Status) {
+        // println!("  Set status: {} {:?}", v, status);
+        debug_assert!(self.is_seen(v));
+        let changed = self.get_status(v) != Some(status);
+        self.graph.update_label(v, |node| node.status = status);
+        if changed && (status == Status::Live || status == Status::Dead) {
+            self.log.record(self.graph.iter_aliases(v), status);
+            if status == Status::Dead {
+                self.subsumed.record_dead(self.graph.iter_aliases(v));
+            }
+        }
+        // Mark live in particular deletes jumps, and adds reserve edges to
+        // the graph (they are only needed for reachability from initial
+        // states).
+        if status == Status::Live {
+            self.get_node_mut(v).jumps.clear();
+            let reserve = std::mem::take(&mut self.get_node_mut(v).reserve);
+            for (u, w) in reserve {
+                self.graph.ensure_edge_fwd(u, w, None);
             }
         }
     }
+    fn iter_succs(&self, v: V) -> impl Iterator<Item = V> + '_ {
+        // Edges not processed yet are in reserve, not in the graph
+        let reserve = &self.get_node(v).reserve;
+        self.graph.iter_fwd_edges(v).chain(reserve.iter().map(|&(_, w)| w))
+    }
+    fn mark_subsumed_dead(&mut self, v: V) {
+        self.truncate_jumps_through(v);
+        self.clear_jumps(v);
+        let reserve = std::mem::take(&mut self.get_node_mut(v).reserve);
+        for (u, w) in reserve {
+            self.graph.ensure_edge_fwd(u, w, None);
+        }
+        self.mark_dead(v);
+    }
 }
-impl StateGraph for JumpStateGraph {
+impl<V: StateID> StateGraph<V> for JumpStateGraph<V> {
     fn new() -> Self {
         Default::default()
     }
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize) {
+    fn is_buchi(&self) -> bool {
+        false
+    }
+    fn new_markov() -> Self {
+        Self { markov: Markov::new_markov(), ..Default::default() }
+    }
+    fn is_markov(&self) -> bool {
+        self.markov.is_markov()
+    }
+    fn add_labeled_transition_unchecked(
+        &mut self,
+        v1: V,
+        v2: V,
+        label: Option<usize>,
+    ) {
         // println!("# Adding transition: {}, {}", v1, v2);
-        self.graph.ensure_edge_bck(v1, v2);
-        self.calculate_new_live_states(v2);
-        if !self.is_live(v1) {
+        self.graph.ensure_edge_bck(v1, v2, label);
+        if let Some(dist) = self.log.distance(v2) {
+            self.calculate_new_live_states(v1, dist + 1);
+        }
+        if self.is_live(v1) {
+            self.graph.ensure_edge_fwd(v1, v2, None);
+        } else {
             self.push_reserve(v1, v2);
         }
+        let graph = &self.graph;
+        self.classes
+            .add_transition_for(v1, v2, |u| graph.iter_bck_edges_unmerged(u));
+        if self.reach.is_reached(v1) {
+            self.calculate_reachable_from_initial(v2);
+        }
+        if self.subsumed.is_doomed(v1) {
+            self.calculate_doomed(v2);
+            self.calculate_subsumed_dead(&[]);
+        }
     }
-    fn mark_closed_unchecked(&mut self, v: usize) {
-        // println!("# Marking Closed: {}", v);
+    fn mark_closed_unchecked(&mut self, v: V) {
         self.graph.ensure_vertex(v);
+        self.mark_closed_merged(v);
+        self.calculate_closed_for_classes(v, &[]);
+        if self.is_live(v) {
+            // (Only in Markov mode, or with other classes in use)
+            self.calculate_almost_sure(&[v]);
+            return;
+        }
+        // println!("# Marking Closed: {}", v);
         self.initialize_jumps(v);
+        self.calculate_subsumed_dead(&[v]);
     }
-    fn mark_live_unchecked(&mut self, v: usize) {
+    fn mark_live_unchecked(&mut self, v: V) {
         self.graph.ensure_vertex(v);
         self.set_status(v, Status::Live);
-        self.calculate_new_live_states(v);
+        self.calculate_new_live_states(v, 0);
+        self.log.mark_live(v);
+    }
+    fn mark_live_for_unchecked(&mut self, v: V, class: usize) {
+        self.graph.ensure_vertex(v);
+        let graph = &self.graph;
+        self.classes
+            .mark_live_for(v, class, |u| graph.iter_bck_edges_unmerged(u));
+    }
+    fn merge_unchecked(&mut self, v1: V, v2: V) {
+        // println!("# Merging: {}, {}", v1, v2);
+        self.graph.ensure_vertex(v1);
+        self.graph.ensure_vertex(v2);
+        self.graph.ensure_edge_bck(v1, v2, None);
+        self.graph.ensure_edge_bck(v2, v1, None);
+        self.calculate_merged(v1, v2);
+        if self.graph.is_same_vertex(v1, v2) {
+            return;
+        }
+        if self.is_live(v1) || self.is_dead(v1) {
+            self.graph.merge_using(v1, v2, merge_decided_nodes);
+            return;
+        }
+        if self.is_open(v1) && self.is_open(v2) {
+            self.graph.merge_using(v1, v2, merge_nodes);
+            self.calculate_subsumed_dead(&[]);
+            return;
+        }
+        // Reopen the closed ones, keeping only the edge each one jumps to
+        // (the other edges processed so far are dead), as when recursing on
+        // a dead state. Then the merged state is closed again from the
+        // combined reserve.
+        for v in [v1, v2] {
+            if self.is_unknown(v) {
+                self.truncate_jumps_through(v);
+                let w = self.get_first_jump(v);
+                self.clear_jumps(v);
+                self.set_status(v, Status::Open);
+                self.push_reserve(v, w);
+            }
+        }
+        self.graph.merge_using(v1, v2, merge_nodes);
+        self.initialize_jumps(v1);
+        self.calculate_subsumed_dead(&[v1]);
+    }
+    fn subsumed_unchecked(&mut self, v1: V, v2: V) {
+        self.graph.ensure_vertex(v1);
+        self.graph.ensure_vertex(v2);
+        self.add_subsumed(v1, v2);
     }
-    fn not_reachable_unchecked(&mut self, v1: usize, v2: usize) {
+    fn not_reachable_unchecked(&mut self, v1: V, v2: V) {
         self.graph.ensure_vertex(v1);
         // Could do self.graph.ensure_vertex(v2), but not necessary
         self.add_not_reachable(v1, v2);
     }
-    fn get_status(&self, v: usize) -> Option<Status> {
+    fn mark_initial_unchecked(&mut self, v: V) {
+        self.graph.ensure_vertex(v);
+        self.reach.mark_initial(v);
+        self.calculate_reachable_from_initial(v);
+    }
+    fn process_batch(&mut self, ts: &[Transaction<V>]) {
+        // Dead states found during the batch don't immediately recurse
+        // backwards; the states to recurse on are re-initialized at the end.
+        // In the meantime they are Open, as during the recursion, so that
+        // their jumps can be re-initialized (unless they became live).
+        // (Merges and Subsumed hints end the batch, see split_batch)
+        let ts = self.split_batch(ts);
+        self.batch = Some(Vec::new());
+        for &t in ts {
+            self.process(t);
+        }
+        let pending = self.batch.take().unwrap();
+        for &u in &pending {
+            if self.is_open(u) {
+                self.initialize_jumps(u);
+            }
+        }
+        // (Those re-initialized may be Dead by a hint found meanwhile)
+        self.calculate_subsumed_dead(&pending);
+    }
+    fn get_status(&self, v: V) -> Option<Status> {
         self.graph.get_label(v).map(|l| l.status)
     }
+    fn get_status_for(&self, v: V, class: usize) -> Option<Status> {
+        self.status_for(v, class)
+    }
+    fn iter_classes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.classes.iter_classes()
+    }
+    fn only_reaches_closed(&self, v: V) -> bool {
+        self.classes.only_reaches_closed(v)
+    }
+    fn is_marked_closed(&self, v: V) -> bool {
+        self.log.is_closed(v)
+    }
+    fn is_almost_sure(&self, v: V) -> bool {
+        self.markov.is_sure(v, &self.log)
+    }
+    fn get_kind(&self, v: V) -> Kind {
+        self.kinds.kind(v)
+    }
+    fn iter_status(&self, status: Status) -> impl Iterator<Item = V> + '_ {
+        self.graph.iter_indexed(status as usize)
+    }
+    fn count_status(&self, status: Status) -> usize {
+        self.graph.count_indexed(status as usize)
+    }
+    fn iter_initial(&self) -> impl Iterator<Item = V> + '_ {
+        self.reach.iter_initial()
+    }
+    fn is_reachable_from_initial(&self, v: V) -> bool {
+        self.reach.is_reached(v)
+    }
+    fn explain_live(&self, v: V) -> Option<Vec<V>> {
+        if !self.is_live(v) {
+            return None;
+        }
+        let targets = self.log.iter_marked_live();
+        self.graph.shortest_path_unmerged(v, targets, |u| self.is_live(u))
+    }
+    fn explain_dead(&self, v: V) -> Option<Vec<V>> {
+        if !self.is_dead(v) {
+            return None;
+        }
+        Some(self.graph.reachable_unmerged(v))
+    }
+    fn is_reachable(&self, v: V, w: V) -> Option<bool> {
+        if self.graph.is_same_vertex(v, w) {
+            return Some(true);
+        } else if !self.is_seen(v) {
+            return None;
+        } else if self.iter_succs(v).any(|u| self.graph.is_same_vertex(u, w)) {
+            // (Including reserve edges, which the search below doesn't see)
+            return Some(true);
+        } else if self.is_not_reachable(v, w) {
+            return Some(false);
+        }
+        // Every jump is reachable, so follow the last jumps as in is_root
+        // (without updating them)
+        let mut u = v;
+        for _ in 0..REACHABLE_SEARCH_MAX {
+            let jumps = &self.get_node(u).jumps;
+            if jumps.iter().any(|&x| self.graph.is_same_vertex(x, w)) {
+                return Some(true);
+            }
+            match jumps.last() {
+                Some(&x) if self.is_closed(x) => u = x,
+                _ => break,
+            }
+        }
+        // Otherwise search, following the edges in reserve too: then each
+        // state marked closed has all of its fwd-edges in the search
+        let v = self.graph.get_canon_vertex(v);
+        let succs =
+            |u| self.iter_succs(u).map(|x| self.graph.get_canon_vertex(x));
+        let search = DepthFirstSearch::new(iter::once(v), succs);
+        let mut all_complete = true;
+        for (count, u) in iter::once(v).chain(search).enumerate() {
+            if self.graph.is_same_vertex(u, w) {
+                return Some(true);
+            } else if count == REACHABLE_SEARCH_MAX {
+                return None;
+            }
+            all_complete &= self.log.is_closed(u);
+        }
+        if all_complete {
+            Some(false)
+        } else {
+            None
+        }
+    }
+    fn witness_live(&self, v: V) -> Option<Vec<usize>> {
+        let path = self.explain_live(v)?;
+        Some(self.graph.path_labels_unmerged(&path))
+    }
+    fn distance_to_live(&self, v: V) -> Option<usize> {
+        self.log.distance(v)
+    }
+    fn drain_status_changes(&mut self) -> Vec<StatusChange<V>> {
+        self.log.drain()
+    }
+    fn push(&mut self) {
+        self.graph.push();
+        self.log.push();
+        self.reach.push();
+        self.kinds.push();
+        self.classes.push();
+        self.markov.push();
+        self.subsumed.push();
+    }
+    fn pop(&mut self) {
+        self.graph.pop();
+        self.log.pop();
+        self.reach.pop();
+        self.kinds.pop();
+        self.classes.pop();
+        self.markov.pop();
+        self.subsumed.pop();
+    }
     fn get_space(&self) -> usize {
         self.graph.get_space()
     }
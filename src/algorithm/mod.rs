@@ -1,3 +1,4 @@
+mod bookkeeping;
 pub mod jump;
 pub mod naive;
 pub mod simple;
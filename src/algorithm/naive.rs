@@ -4,79 +4,404 @@
 
     This just stores the graph using hash tables, and
     does naive DFS to determine whether states are dead.
+
+    Also supports universal states (see interface::Kind) and Büchi mode
+    (see StateGraph::new_buchi), so it serves as the reference for both.
 */
 
+use super::bookkeeping::{merge_status, Bookkeeping, Parts, PartsMut};
 use crate::graph::DiGraph;
-use crate::interface::{StateGraph, Status};
+use crate::interface::{
+    Kind, StateGraph, StateGraphError, StateID, Status, StatusChange,
+    Transaction,
+};
+use crate::status_log::{
+    Classes, Kinds, Markov, Reachability, StatusLog, Subsumption,
+};
 use std::collections::HashSet;
 use std::iter;
 
-#[derive(Debug, Default)]
-pub struct NaiveStateGraph {
-    graph: DiGraph<usize, Status>,
+#[derive(Debug)]
+pub struct NaiveStateGraph<V = usize> {
+    graph: DiGraph<V, Status>,
+    // Status changes not yet drained, and states marked live
+    log: StatusLog<V>,
+    // The rest of the bookkeeping, by concern (see status_log)
+    reach: Reachability<V>,
+    kinds: Kinds<V>,
+    classes: Classes<V>,
+    markov: Markov<V>,
+    subsumed: Subsumption<V>,
+    // While processing a batch: states closed so far in the batch
+    batch: Option<Vec<V>>,
 }
-impl NaiveStateGraph {
-    fn calculate_new_live_states(&mut self, v: usize) {
-        // Mark all states Live backwards from v, but not including v
-        if self.is_live(v) {
-            let new_live: HashSet<usize> = self
-                .graph
-                .dfs_bck(iter::once(v), |u| {
-                    debug_assert!(!self.is_dead(u));
-                    !self.is_live(u)
-                })
-                .collect();
-            for &u in new_live.iter() {
-                self.graph.overwrite_vertex(u, Status::Live);
-            }
+impl<V> Default for NaiveStateGraph<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        let graph = DiGraph::new_indexed(|&status| status as usize);
+        Self {
+            graph,
+            log: Default::default(),
+            reach: Default::default(),
+            kinds: Default::default(),
+            classes: Default::default(),
+            markov: Default::default(),
+            subsumed: Default::default(),
+            batch: None,
+        }
+    }
+}
+impl<V: StateID> NaiveStateGraph<V> {
+    fn calculate_accepting_cycles(&mut self) {
+        // In Büchi mode, accepting states on a cycle of closed states are
+        // Live, as if marked live. Naive: search from each of them.
+        if !self.kinds.is_buchi() {
+            return;
+        }
+        let on_cycle: Vec<V> = self
+            .kinds
+            .iter_accepting()
+            .filter(|&a| self.is_unknown(a))
+            .filter(|&a| {
+                self.graph
+                    .dfs_fwd(iter::once(a), |w| self.is_u_or_d(w))
+                    .any(|w| self.graph.iter_fwd_edges(w).any(|x| x == a))
+            })
+            .collect();
+        // (Each of them is marked live, even if found Live through another)
+        for a in on_cycle {
+            self.log.mark_live(a);
+            self.calculate_new_live_states(a, 0);
         }
     }
     fn recalculate_dead_states(&mut self) {
         // Recalculate the subset of closed states that are dead: states
         // that can't reach an Open state (i.e. all reachable states are
-        // dead or unknown).
+        // dead or unknown). With universal states, a universal state can
+        // only avoid being dead if all of its successors do. States already
+        // Dead stay Dead (with Subsumed hints, they may reach Open states).
         // This is the only nontrivial aspect of the naive implementation,
         // uses a DFS, and is worst-case O(m).
 
         // Initialize
-        let (unkdead, openlive): (HashSet<usize>, HashSet<usize>) =
+        let (unkdead, openlive): (HashSet<V>, HashSet<V>) =
             self.graph.iter_vertices().partition(|&v| self.is_u_or_d(v));
-        let not_dead: HashSet<usize> = self
-            .graph
-            .dfs_bck(openlive.iter().copied(), |v| unkdead.contains(&v))
-            .collect();
+        let not_dead = self.graph.and_or_search_bck(
+            openlive.iter().copied(),
+            |v| self.is_unknown(v),
+            |v| self.kinds.is_universal(v),
+        );
 
         // Mark not-not-dead states as dead
         for &v in unkdead.iter() {
             debug_assert!(!(self.is_dead(v) && not_dead.contains(&v)));
             if !not_dead.contains(&v) {
-                self.graph.overwrite_vertex(v, Status::Dead);
+                self.set_status(v, Status::Dead);
             }
         }
     }
 }
-impl StateGraph for NaiveStateGraph {
+impl<V: StateID> Bookkeeping<V> for NaiveStateGraph<V> {
+    type Label = Status;
+    fn parts(&self) -> Parts<'_, V, Self::Label> {
+        Parts {
+            graph: &self.graph,
+            log: &self.log,
+            reach: &self.reach,
+            kinds: &self.kinds,
+            classes: &self.classes,
+            markov: &self.markov,
+            subsumed: &self.subsumed,
+        }
+    }
+    fn parts_mut(&mut self) -> PartsMut<'_, V, Self::Label> {
+        PartsMut {
+            graph: &self.graph,
+            log: &mut self.log,
+            reach: &mut self.reach,
+            kinds: &self.kinds,
+            classes: &mut self.classes,
+            markov: &mut self.markov,
+            subsumed: &mut self.subsumed,
+        }
+    }
+    fn set_status(&mut self, v: V, status: Status) {
+        // Also records the change if v is newly Live or Dead
+        let changed = self.get_status(v) != Some(status);
+        self.graph.overwrite_vertex(v, status);
+        if changed && (status == Status::Live || status == Status::Dead) {
+            self.log.record(self.graph.iter_aliases(v), status);
+            if status == Status::Dead {
+                self.subsumed.record_dead(self.graph.iter_aliases(v));
+            }
+        }
+    }
+    fn iter_succs(&self, v: V) -> impl Iterator<Item = V> + '_ {
+        self.graph.iter_fwd_edges(v)
+    }
+    fn calculate_subsumed_dead(&mut self, vs: &[V]) {
+        // Closed states subsumed by a Dead state are Dead, and so are the
+        // closed states reachable from those (doomed; see
+        // subsumed_unchecked): check vs and the states queued since, then
+        // recalculate dead states, as more closed states may only reach Open
+        // states through them.
+        if !self.subsumed.has_subsumed() {
+            return;
+        }
+        for &v in vs {
+            self.subsumed.push_queue(v);
+        }
+        loop {
+            let mut found = false;
+            while let Some(v) = self.subsumed.pop_queue() {
+                if self.is_unknown(v)
+                    && (self.subsumed.is_doomed(v)
+                        || self.is_subsumed_by_dead(v))
+                {
+                    self.set_status(v, Status::Dead);
+                    self.calculate_doomed(v);
+                    found = true;
+                }
+            }
+            if !found {
+                break;
+            }
+            self.recalculate_dead_states();
+        }
+    }
+}
+impl<V: StateID> StateGraph<V> for NaiveStateGraph<V> {
+    const ALTERNATING: bool = true;
     fn new() -> Self {
         Default::default()
     }
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize) {
-        self.graph.ensure_edge(v1, v2);
-        self.calculate_new_live_states(v2);
+    fn new_buchi() -> Option<Self> {
+        Some(Self { kinds: Kinds::new_buchi(), ..Default::default() })
+    }
+    fn is_buchi(&self) -> bool {
+        self.kinds.is_buchi()
     }
-    fn mark_closed_unchecked(&mut self, v: usize) {
-        self.graph.overwrite_vertex(v, Status::Unknown);
-        self.recalculate_dead_states();
+    fn new_markov() -> Self {
+        Self { markov: Markov::new_markov(), ..Default::default() }
     }
-    fn mark_live_unchecked(&mut self, v: usize) {
-        self.graph.overwrite_vertex(v, Status::Live);
-        self.calculate_new_live_states(v);
+    fn is_markov(&self) -> bool {
+        self.markov.is_markov()
     }
-    fn not_reachable_unchecked(&mut self, _v1: usize, _v2: usize) {
+    fn add_labeled_transition_unchecked(
+        &mut self,
+        v1: V,
+        v2: V,
+        label: Option<usize>,
+    ) {
+        if v1 == v2 {
+            // (Only in Büchi mode) Checked when v1 is closed
+            self.graph.ensure_vertex(v1);
+            self.kinds.mark_self_loop(v1);
+            return;
+        }
+        self.graph.ensure_edge(v1, v2, label);
+        // (An open universal state isn't Live until it is closed)
+        if let Some(dist) = self.log.distance(v2) {
+            if !self.kinds.is_universal(v1) {
+                self.calculate_new_live_states(v1, dist + 1);
+            }
+        }
+        let graph = &self.graph;
+        self.classes
+            .add_transition_for(v1, v2, |u| graph.iter_bck_edges_unmerged(u));
+        if self.reach.is_reached(v1) {
+            self.calculate_reachable_from_initial(v2);
+        }
+        if self.subsumed.is_doomed(v1) {
+            self.calculate_doomed(v2);
+            self.calculate_subsumed_dead(&[]);
+        }
+    }
+    fn mark_closed_unchecked(&mut self, v: V) {
+        self.graph.ensure_vertex(v);
+        self.mark_closed_merged(v);
+        self.calculate_closed_for_classes(v, &[]);
+        if self.is_live(v) {
+            // (Only in Markov mode, or with other classes in use)
+            self.calculate_almost_sure(&[v]);
+            return;
+        }
+        self.set_status(v, Status::Unknown);
+        self.calculate_universal_live(v);
+        self.calculate_accepting_self_loop(v);
+        match &mut self.batch {
+            Some(closed) => closed.push(v),
+            None => {
+                self.calculate_accepting_cycles();
+                self.recalculate_dead_states();
+                self.calculate_subsumed_dead(&[v]);
+            }
+        }
+    }
+    fn mark_live_unchecked(&mut self, v: V) {
+        self.set_status(v, Status::Live);
+        self.calculate_new_live_states(v, 0);
+        self.log.mark_live(v);
+    }
+    fn mark_live_for_unchecked(&mut self, v: V, class: usize) {
+        self.graph.ensure_vertex(v);
+        let graph = &self.graph;
+        self.classes
+            .mark_live_for(v, class, |u| graph.iter_bck_edges_unmerged(u));
+    }
+    fn merge_unchecked(&mut self, v1: V, v2: V) {
+        self.graph.ensure_edge(v1, v2, None);
+        self.graph.ensure_edge(v2, v1, None);
+        self.calculate_merged(v1, v2);
+        self.graph.merge_using(v1, v2, merge_status);
+        // (Never in a batch, see process_batch)
+        if self.is_unknown(v1) {
+            self.recalculate_dead_states();
+        }
+        // (States doomed through the merge may be Dead, even if v1 is Open)
+        self.calculate_subsumed_dead(&[v1]);
+    }
+    fn subsumed_unchecked(&mut self, v1: V, v2: V) {
+        // (Never in a batch either)
+        self.graph.ensure_vertex(v1);
+        self.graph.ensure_vertex(v2);
+        self.add_subsumed(v1, v2);
+    }
+    fn not_reachable_unchecked(&mut self, _v1: V, _v2: V) {
         // Ignore NotReachable
     }
-    fn get_status(&self, v: usize) -> Option<Status> {
+    fn mark_initial_unchecked(&mut self, v: V) {
+        self.graph.ensure_vertex(v);
+        self.reach.mark_initial(v);
+        self.calculate_reachable_from_initial(v);
+    }
+    fn mark_universal_unchecked(
+        &mut self,
+        v: V,
+    ) -> Result<(), StateGraphError<V>> {
+        self.graph.ensure_vertex(v);
+        self.kinds.mark_universal(v);
+        Ok(())
+    }
+    fn mark_accepting_unchecked(
+        &mut self,
+        v: V,
+    ) -> Result<(), StateGraphError<V>> {
+        self.graph.ensure_vertex(v);
+        self.kinds.mark_accepting(v);
+        Ok(())
+    }
+    fn process_batch(&mut self, ts: &[Transaction<V>]) {
+        // Recalculate accepting cycles and dead states once, at the end of
+        // the batch. (Merges and Subsumed hints end the batch, see
+        // split_batch)
+        // Not in Büchi mode, where accepting cycles found Live decide
+        // whether later transactions are redundant (as in Simple).
+        if self.kinds.is_buchi() {
+            for &t in ts {
+                self.process(t);
+            }
+            return;
+        }
+        let ts = self.split_batch(ts);
+        self.batch = Some(Vec::new());
+        for &t in ts {
+            self.process(t);
+        }
+        let closed = self.batch.take().unwrap();
+        if !closed.is_empty() {
+            self.calculate_accepting_cycles();
+            self.recalculate_dead_states();
+            self.calculate_subsumed_dead(&closed);
+        }
+    }
+    fn get_status(&self, v: V) -> Option<Status> {
         self.graph.get_label(v).copied()
     }
+    fn get_status_for(&self, v: V, class: usize) -> Option<Status> {
+        self.status_for(v, class)
+    }
+    fn iter_classes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.classes.iter_classes()
+    }
+    fn only_reaches_closed(&self, v: V) -> bool {
+        self.classes.only_reaches_closed(v)
+    }
+    fn is_marked_closed(&self, v: V) -> bool {
+        self.log.is_closed(v)
+    }
+    fn is_almost_sure(&self, v: V) -> bool {
+        self.markov.is_sure(v, &self.log)
+    }
+    fn get_kind(&self, v: V) -> Kind {
+        self.kinds.kind(v)
+    }
+    fn iter_status(&self, status: Status) -> impl Iterator<Item = V> + '_ {
+        self.graph.iter_indexed(status as usize)
+    }
+    fn count_status(&self, status: Status) -> usize {
+        self.graph.count_indexed(status as usize)
+    }
+    fn iter_initial(&self) -> impl Iterator<Item = V> + '_ {
+        self.reach.iter_initial()
+    }
+    fn is_reachable_from_initial(&self, v: V) -> bool {
+        self.reach.is_reached(v)
+    }
+    fn explain_live(&self, v: V) -> Option<Vec<V>> {
+        if !self.is_live(v) {
+            return None;
+        }
+        let targets = self.log.iter_marked_live();
+        self.graph.shortest_path_unmerged(v, targets, |u| self.is_live(u))
+    }
+    fn explain_dead(&self, v: V) -> Option<Vec<V>> {
+        if !self.is_dead(v) {
+            return None;
+        }
+        Some(self.graph.reachable_unmerged(v))
+    }
+    fn is_reachable(&self, v: V, w: V) -> Option<bool> {
+        // Unbounded search; no path can ever be added from v if no
+        // transitions can be added from anything reachable from v. (Not
+        // just closed: in Markov mode, Live states can still get them.)
+        let limit = usize::MAX;
+        self.graph.is_reachable_bounded(
+            v,
+            w,
+            limit,
+            |_| true,
+            |u| !self.can_add_from(u),
+        )
+    }
+    fn witness_live(&self, v: V) -> Option<Vec<usize>> {
+        let path = self.explain_live(v)?;
+        Some(self.graph.path_labels_unmerged(&path))
+    }
+    fn distance_to_live(&self, v: V) -> Option<usize> {
+        self.log.distance(v)
+    }
+    fn drain_status_changes(&mut self) -> Vec<StatusChange<V>> {
+        self.log.drain()
+    }
+    fn push(&mut self) {
+        self.graph.push();
+        self.log.push();
+        self.reach.push();
+        self.kinds.push();
+        self.classes.push();
+        self.markov.push();
+        self.subsumed.push();
+    }
+    fn pop(&mut self) {
+        self.graph.pop();
+        self.log.pop();
+        self.reach.pop();
+        self.kinds.pop();
+        self.classes.pop();
+        self.markov.pop();
+        self.subsumed.pop();
+    }
     fn get_space(&self) -> usize {
         self.graph.get_space()
     }
@@ -9,33 +9,94 @@
     with one improvement: we used LinkedList instead of HashSet for
     storing edges, because it allows merging edge sets in O(1).
     (see graph.rs)
+
+    Also supports universal states (see interface::Kind). Their cycles
+    can't be merged (states on the same cycle may have different statuses),
+    so once there are universal states, dead states are found by
+    recalculating (as in Naive) only the states backward from those closed.
+
+    In Büchi mode, merging cycles also finds the accepting ones: a cycle is
+    accepting if one of the states merged into it is accepting.
+
+    NotReachable hints prune the search for cycles: a state that can't reach
+    v isn't on a cycle through it, and neither is any state only reachable
+    through that one.
 */
 
-use crate::graph::DiGraph;
-use crate::interface::{StateGraph, Status};
+use super::bookkeeping::{merge_status, Bookkeeping, Parts, PartsMut};
+use crate::graph::{DiGraph, REACHABLE_SEARCH_MAX};
+use crate::interface::{
+    Kind, StateGraph, StateGraphError, StateID, Status, StatusChange,
+    Transaction,
+};
+use crate::status_log::{
+    Classes, Hints, Kinds, Markov, Reachability, StatusLog, Subsumption,
+};
 use std::collections::HashSet;
 use std::iter;
 
-#[derive(Debug, Default)]
-pub struct SimpleStateGraph {
-    graph: DiGraph<usize, Status>,
+#[derive(Debug)]
+pub struct SimpleStateGraph<V = usize> {
+    graph: DiGraph<V, Status>,
+    // Status changes not yet drained, and states marked live
+    log: StatusLog<V>,
+    // The rest of the bookkeeping, by concern (see status_log)
+    reach: Reachability<V>,
+    kinds: Kinds<V>,
+    classes: Classes<V>,
+    markov: Markov<V>,
+    subsumed: Subsumption<V>,
+    hints: Hints<V>,
+    // While processing a batch: states closed so far in the batch
+    batch: Option<Vec<V>>,
+}
+impl<V> Default for SimpleStateGraph<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        let graph = DiGraph::new_indexed(|&status| status as usize);
+        Self {
+            graph,
+            log: Default::default(),
+            reach: Default::default(),
+            kinds: Default::default(),
+            classes: Default::default(),
+            markov: Default::default(),
+            subsumed: Default::default(),
+            hints: Default::default(),
+            batch: None,
+        }
+    }
 }
-impl SimpleStateGraph {
-    fn merge_vertices(&mut self, v1: usize, v2: usize) {
+impl<V: StateID> SimpleStateGraph<V> {
+    fn merge_vertices(&mut self, v1: V, v2: V) {
         // println!("  Merging: {} {}", v1, v2);
         debug_assert!(self.is_u_or_d(v1));
         debug_assert!(self.is_u_or_d(v2));
         debug_assert!(v1 != v2);
         self.graph.merge(v1, v2);
     }
-    fn merge_all_cycles(&mut self, v: usize) {
+    fn merge_all_cycles(&mut self, v: V) {
         // println!("  Merging cycles through: {}", v);
         // Merge all cycles through v
-        // (assuming no other cycles in closed states)
-        debug_assert!(self.is_u_or_d(v));
-        let fwd_reachable: HashSet<usize> =
-            self.graph.dfs_fwd(iter::once(v), |w| self.is_u_or_d(w)).collect();
-        let bi_reachable: HashSet<usize> = self
+        // (assuming no other cycles in closed existential states; Dead
+        // states can't be on one, but with Subsumed hints they may reach
+        // Unknown states until those are found Dead too)
+        debug_assert!(self.is_unknown(v));
+        if self.kinds.is_universal(v) {
+            return;
+        }
+        // NotReachable hints rule out the states that can't reach v, so the
+        // forward search stops at them
+        let not_reaching = self.hints.not_reaching(&self.graph, v);
+        let fwd_reachable: HashSet<V> = self
+            .graph
+            .dfs_fwd(iter::once(v), |w| {
+                self.is_unknown(w)
+                    && !self.kinds.is_universal(w)
+                    && !not_reaching.contains(&w)
+            })
+            .collect();
+        let bi_reachable: HashSet<V> = self
             .graph
             .dfs_bck(iter::once(v), |u| fwd_reachable.contains(&u))
             .collect();
@@ -44,69 +105,375 @@ impl SimpleStateGraph {
             debug_assert!(u != v);
             self.merge_vertices(u, v);
         }
+        if self.kinds.is_buchi() && !bi_reachable.is_empty() {
+            // States merged earlier can't be accepting (their cycle would be
+            // Live, and Live states aren't merged), so only check the rest
+            let accepting: Vec<V> = bi_reachable
+                .iter()
+                .copied()
+                .chain(iter::once(v))
+                .filter(|&u| self.kinds.is_accepting(u))
+                .collect();
+            for a in accepting {
+                self.log.mark_live(a);
+                self.calculate_new_live_states(a, 0);
+            }
+        }
     }
-    fn check_dead_iterative(&mut self, v: usize) {
-        // Check if v is dead and recurse on back edges.
-        // println!("  Checking if dead iteratively from: {}", v);
-        let now_dead: HashSet<usize> = self
+    fn check_dead_iterative(&mut self, vs: &[V]) {
+        // Check if each v in vs is dead and recurse on back edges.
+        // println!("  Checking if dead iteratively from: {:?}", vs);
+        debug_assert!(vs.iter().all(|&v| self.is_u_or_d(v)));
+        if self.kinds.has_universal() {
+            self.check_dead_alternating(vs);
+            return;
+        }
+        let now_dead: HashSet<V> = self
             .graph
             .topo_search_bck(
-                iter::once(v),
+                vs.iter().copied(),
                 |u| self.is_u_or_d(u),
                 |w| !self.is_dead(w),
             )
             .collect();
         debug_assert!(
             now_dead.is_empty()
-                || now_dead.contains(&self.graph.get_canon_vertex(v))
+                || vs.iter().any(|&v| {
+                    now_dead.contains(&self.graph.get_canon_vertex(v))
+                })
         );
         for &u in now_dead.iter() {
             // println!("  Marking dead: {}", u);
-            self.graph.overwrite_vertex(u, Status::Dead);
+            self.set_status(u, Status::Dead);
         }
     }
-    fn calculate_new_live_states(&mut self, v: usize) {
-        // Same fn as in Naive
-        if self.is_live(v) {
-            let new_live: HashSet<usize> = self
-                .graph
-                .dfs_bck(iter::once(v), |u| {
-                    debug_assert!(!self.is_dead(u));
-                    !self.is_live(u)
-                })
-                .collect();
-            for &u in new_live.iter() {
-                self.graph.overwrite_vertex(u, Status::Live);
+    fn check_dead_alternating(&mut self, vs: &[V]) {
+        // Recalculate dead states among the Unknown states that can reach
+        // vs through Unknown states; the status of the others can't depend
+        // on vs. Successors outside this region keep their status.
+        let starts: Vec<V> = vs
+            .iter()
+            .map(|&v| self.graph.get_canon_vertex(v))
+            .filter(|&v| self.is_unknown(v))
+            .collect();
+        let region: HashSet<V> = self
+            .graph
+            .dfs_bck(starts.iter().copied(), |u| self.is_unknown(u))
+            .chain(starts.iter().copied())
+            .collect();
+        let not_dead_succs: HashSet<V> = region
+            .iter()
+            .flat_map(|&u| self.graph.iter_fwd_edges(u))
+            .filter(|&w| !region.contains(&w) && !self.is_dead(w))
+            .collect();
+        let not_dead = self.graph.and_or_search_bck(
+            not_dead_succs.into_iter(),
+            |u| region.contains(&u),
+            |u| self.kinds.is_universal(u),
+        );
+        for &u in region.iter() {
+            if !not_dead.contains(&u) {
+                self.set_status(u, Status::Dead);
+            }
+        }
+    }
+}
+impl<V: StateID> Bookkeeping<V> for SimpleStateGraph<V> {
+    type Label = Status;
+    fn parts(&self) -> Parts<'_, V, Self::Label> {
+        Parts {
+            graph: &self.graph,
+            log: &self.log,
+            reach: &self.reach,
+            kinds: &self.kinds,
+            classes: &self.classes,
+            markov: &self.markov,
+            subsumed: &self.subsumed,
+        }
+    }
+    fn parts_mut(&mut self) -> PartsMut<'_, V, Self::Label> {
+        PartsMut {
+            graph: &self.graph,
+            log: &mut self.log,
+            reach: &mut self.reach,
+            kinds: &self.kinds,
+            classes: &mut self.classes,
+            markov: &mut self.markov,
+            subsumed: &mut self.subsumed,
+        }
+    }
+    fn set_status(&mut self, v: V, status: Status) {
+        // Also records the change if v is newly Live or Dead
+        let changed = self.get_status(v) != Some(status);
+        self.graph.overwrite_vertex(v, status);
+        if changed && (status == Status::Live || status == Status::Dead) {
+            self.log.record(self.graph.iter_aliases(v), status);
+            if status == Status::Dead {
+                self.subsumed.record_dead(self.graph.iter_aliases(v));
             }
         }
     }
+    fn iter_succs(&self, v: V) -> impl Iterator<Item = V> + '_ {
+        self.graph.iter_fwd_edges(v)
+    }
+    fn mark_subsumed_dead(&mut self, v: V) {
+        // Check the states backward from v, rather than recalculating as
+        // in Naive
+        self.set_status(v, Status::Dead);
+        let preds: Vec<V> = self
+            .graph
+            .iter_bck_edges(v)
+            .filter(|&u| self.is_unknown(u))
+            .collect();
+        self.check_dead_iterative(&preds);
+    }
 }
-impl StateGraph for SimpleStateGraph {
+impl<V: StateID> StateGraph<V> for SimpleStateGraph<V> {
+    const ALTERNATING: bool = true;
     fn new() -> Self {
         Default::default()
     }
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize) {
+    fn new_buchi() -> Option<Self> {
+        Some(Self { kinds: Kinds::new_buchi(), ..Default::default() })
+    }
+    fn is_buchi(&self) -> bool {
+        self.kinds.is_buchi()
+    }
+    fn new_markov() -> Self {
+        Self { markov: Markov::new_markov(), ..Default::default() }
+    }
+    fn is_markov(&self) -> bool {
+        self.markov.is_markov()
+    }
+    fn add_labeled_transition_unchecked(
+        &mut self,
+        v1: V,
+        v2: V,
+        label: Option<usize>,
+    ) {
         // println!("Adding transition: {} {}", v1, v2);
-        self.graph.ensure_edge(v1, v2);
-        self.calculate_new_live_states(v2);
+        if v1 == v2 {
+            // (Only in Büchi mode) Checked when v1 is closed
+            self.graph.ensure_vertex(v1);
+            self.kinds.mark_self_loop(v1);
+            return;
+        }
+        self.graph.ensure_edge(v1, v2, label);
+        // (An open universal state isn't Live until it is closed)
+        if let Some(dist) = self.log.distance(v2) {
+            if !self.kinds.is_universal(v1) {
+                self.calculate_new_live_states(v1, dist + 1);
+            }
+        }
+        let graph = &self.graph;
+        self.classes
+            .add_transition_for(v1, v2, |u| graph.iter_bck_edges_unmerged(u));
+        if self.reach.is_reached(v1) {
+            self.calculate_reachable_from_initial(v2);
+        }
+        if self.subsumed.is_doomed(v1) {
+            self.calculate_doomed(v2);
+            self.calculate_subsumed_dead(&[]);
+        }
     }
-    fn mark_closed_unchecked(&mut self, v: usize) {
+    fn mark_closed_unchecked(&mut self, v: V) {
+        self.graph.ensure_vertex(v);
+        self.mark_closed_merged(v);
+        self.calculate_closed_for_classes(v, &[]);
+        if self.is_live(v) {
+            // (Only in Markov mode, or with other classes in use)
+            self.calculate_almost_sure(&[v]);
+            return;
+        }
         // println!("Marking closed: {}", v);
-        self.graph.overwrite_vertex(v, Status::Unknown);
-        self.merge_all_cycles(v);
-        self.check_dead_iterative(v);
+        self.set_status(v, Status::Unknown);
+        self.calculate_universal_live(v);
+        self.calculate_accepting_self_loop(v);
+        if let Some(closed) = &mut self.batch {
+            closed.push(v);
+        } else if self.is_unknown(v) {
+            // (Unless v is universal or on an accepting self-loop, and now
+            // Live)
+            self.merge_all_cycles(v);
+            if self.is_unknown(v) {
+                self.check_dead_iterative(&[v]);
+            }
+            self.calculate_subsumed_dead(&[v]);
+        }
     }
-    fn mark_live_unchecked(&mut self, v: usize) {
+    fn mark_live_unchecked(&mut self, v: V) {
         // println!("Marking live: {}", v);
-        self.graph.overwrite_vertex(v, Status::Live);
-        self.calculate_new_live_states(v);
+        self.set_status(v, Status::Live);
+        self.calculate_new_live_states(v, 0);
+        self.log.mark_live(v);
+    }
+    fn mark_live_for_unchecked(&mut self, v: V, class: usize) {
+        self.graph.ensure_vertex(v);
+        let graph = &self.graph;
+        self.classes
+            .mark_live_for(v, class, |u| graph.iter_bck_edges_unmerged(u));
+    }
+    fn merge_unchecked(&mut self, v1: V, v2: V) {
+        // As in Naive; the merged state may then be on new cycles (all
+        // through it), as when it is closed
+        self.graph.ensure_edge(v1, v2, None);
+        self.graph.ensure_edge(v2, v1, None);
+        self.calculate_merged(v1, v2);
+        self.graph.merge_using(v1, v2, merge_status);
+        if self.is_unknown(v1) {
+            let v = self.graph.get_canon_vertex(v1);
+            self.merge_all_cycles(v);
+            self.check_dead_iterative(&[v]);
+        }
+        self.calculate_subsumed_dead(&[v1]);
     }
-    fn not_reachable_unchecked(&mut self, _v1: usize, _v2: usize) {
-        // Ignore NotReachable
+    fn subsumed_unchecked(&mut self, v1: V, v2: V) {
+        self.graph.ensure_vertex(v1);
+        self.graph.ensure_vertex(v2);
+        self.add_subsumed(v1, v2);
+    }
+    fn not_reachable_unchecked(&mut self, v1: V, v2: V) {
+        // Only used to prune the searches in merge_all_cycles
+        self.hints.add_not_reachable(v1, v2);
+    }
+    fn mark_initial_unchecked(&mut self, v: V) {
+        self.graph.ensure_vertex(v);
+        self.reach.mark_initial(v);
+        self.calculate_reachable_from_initial(v);
+    }
+    fn mark_universal_unchecked(
+        &mut self,
+        v: V,
+    ) -> Result<(), StateGraphError<V>> {
+        self.graph.ensure_vertex(v);
+        self.kinds.mark_universal(v);
+        Ok(())
+    }
+    fn mark_accepting_unchecked(
+        &mut self,
+        v: V,
+    ) -> Result<(), StateGraphError<V>> {
+        self.graph.ensure_vertex(v);
+        self.kinds.mark_accepting(v);
+        Ok(())
+    }
+    fn process_batch(&mut self, ts: &[Transaction<V>]) {
+        // Merge cycles and check for dead states once, at the end of the
+        // batch. States closed in the batch may have become live since.
+        // (Merges and Subsumed hints end the batch, see split_batch)
+        // Not in Büchi mode: there, merging a cycle may find its states
+        // Live, which decides whether later transactions from them are
+        // redundant, so it can't wait until the end of the batch.
+        if self.kinds.is_buchi() {
+            for &t in ts {
+                self.process(t);
+            }
+            return;
+        }
+        let ts = self.split_batch(ts);
+        self.batch = Some(Vec::new());
+        for &t in ts {
+            self.process(t);
+        }
+        let mut closed = self.batch.take().unwrap();
+        closed.retain(|&v| self.is_u_or_d(v));
+        for &v in &closed {
+            let v = self.graph.get_canon_vertex(v);
+            if self.is_unknown(v) {
+                self.merge_all_cycles(v);
+            }
+        }
+        closed.retain(|&v| self.is_u_or_d(v));
+        self.check_dead_iterative(&closed);
+        self.calculate_subsumed_dead(&closed);
     }
-    fn get_status(&self, v: usize) -> Option<Status> {
+    fn get_status(&self, v: V) -> Option<Status> {
         self.graph.get_label(v).copied()
     }
+    fn get_status_for(&self, v: V, class: usize) -> Option<Status> {
+        self.status_for(v, class)
+    }
+    fn iter_classes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.classes.iter_classes()
+    }
+    fn only_reaches_closed(&self, v: V) -> bool {
+        self.classes.only_reaches_closed(v)
+    }
+    fn is_marked_closed(&self, v: V) -> bool {
+        self.log.is_closed(v)
+    }
+    fn is_almost_sure(&self, v: V) -> bool {
+        self.markov.is_sure(v, &self.log)
+    }
+    fn get_kind(&self, v: V) -> Kind {
+        self.kinds.kind(v)
+    }
+    fn iter_status(&self, status: Status) -> impl Iterator<Item = V> + '_ {
+        self.graph.iter_indexed(status as usize)
+    }
+    fn count_status(&self, status: Status) -> usize {
+        self.graph.count_indexed(status as usize)
+    }
+    fn iter_initial(&self) -> impl Iterator<Item = V> + '_ {
+        self.reach.iter_initial()
+    }
+    fn is_reachable_from_initial(&self, v: V) -> bool {
+        self.reach.is_reached(v)
+    }
+    fn explain_live(&self, v: V) -> Option<Vec<V>> {
+        if !self.is_live(v) {
+            return None;
+        }
+        let targets = self.log.iter_marked_live();
+        self.graph.shortest_path_unmerged(v, targets, |u| self.is_live(u))
+    }
+    fn explain_dead(&self, v: V) -> Option<Vec<V>> {
+        if !self.is_dead(v) {
+            return None;
+        }
+        Some(self.graph.reachable_unmerged(v))
+    }
+    fn is_reachable(&self, v: V, w: V) -> Option<bool> {
+        // Same as Naive, but bounded
+        let limit = REACHABLE_SEARCH_MAX;
+        self.graph.is_reachable_bounded(
+            v,
+            w,
+            limit,
+            |_| true,
+            |u| !self.can_add_from(u),
+        )
+    }
+    fn witness_live(&self, v: V) -> Option<Vec<usize>> {
+        let path = self.explain_live(v)?;
+        Some(self.graph.path_labels_unmerged(&path))
+    }
+    fn distance_to_live(&self, v: V) -> Option<usize> {
+        self.log.distance(v)
+    }
+    fn drain_status_changes(&mut self) -> Vec<StatusChange<V>> {
+        self.log.drain()
+    }
+    fn push(&mut self) {
+        self.graph.push();
+        self.log.push();
+        self.reach.push();
+        self.kinds.push();
+        self.classes.push();
+        self.markov.push();
+        self.subsumed.push();
+        self.hints.push();
+    }
+    fn pop(&mut self) {
+        self.graph.pop();
+        self.log.pop();
+        self.reach.pop();
+        self.kinds.pop();
+        self.classes.pop();
+        self.markov.pop();
+        self.subsumed.pop();
+        self.hints.pop();
+    }
     fn get_space(&self) -> usize {
         self.graph.get_space()
     }
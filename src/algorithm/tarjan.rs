@@ -12,53 +12,98 @@
     graph functionality in graph::DiGraph.
     However, there is still a fair amount of code duplication between here and
     simple.rs, which could be improved.
+
+    Supports Büchi mode the same way as Simple: when forming a component,
+    it is accepting if one of the states merged into it is accepting.
+
+    NotReachable hints prune forming a component the same way as in Simple,
+    and skip the backward search when they rule out a cycle altogether.
 */
 
-use crate::graph::DiGraph;
-use crate::interface::{StateGraph, Status};
+use super::bookkeeping::{Bookkeeping, Parts, PartsMut};
+use crate::graph::{DiGraph, REACHABLE_SEARCH_MAX};
+use crate::interface::{
+    Kind, StateGraph, StateGraphError, StateID, Status, StatusChange,
+    Transaction,
+};
+use crate::status_log::{
+    Classes, Hints, Kinds, Markov, Reachability, StatusLog, Subsumption,
+};
 use std::collections::{HashMap, HashSet};
 use std::iter;
 
 // The key to the algorithm: pseudo-topological numbering
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
-struct Level(usize);
-impl Default for Level {
-    fn default() -> Self {
-        Level(0)
-    }
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub(super) struct Level(usize);
+
+// Changes to pending edges, recorded while there is at least one scope
+#[derive(Debug)]
+enum PendingUndo<V> {
+    Push(V),
+    Remove(V, Vec<V>),
 }
 
-#[derive(Debug, Default)]
-pub struct TarjanStateGraph {
-    graph: DiGraph<usize, (Status, Level)>,
+#[derive(Debug)]
+pub struct TarjanStateGraph<V = usize> {
+    graph: DiGraph<V, (Status, Level)>,
     // edges from open states, not yet added to the graph
-    pending_edges_fwd: HashMap<usize, Vec<usize>>,
+    pending_edges_fwd: HashMap<V, Vec<V>>,
     // count of graph edges
     edge_counter: usize,
+    // status changes not yet drained, and states marked live
+    log: StatusLog<V>,
+    // the rest of the bookkeeping, by concern (see status_log)
+    reach: Reachability<V>,
+    kinds: Kinds<V>,
+    classes: Classes<V>,
+    markov: Markov<V>,
+    subsumed: Subsumption<V>,
+    hints: Hints<V>,
+    // undo trail for pending edges, and for each scope the trail length and
+    // edge count at the time of push
+    pending_trail: Vec<PendingUndo<V>>,
+    scopes: Vec<(usize, usize)>,
+    // while processing a batch: states closed so far in the batch
+    batch: Option<Vec<V>>,
+}
+impl<V> Default for TarjanStateGraph<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            graph: DiGraph::new_indexed(|label| label.0 as usize),
+            pending_edges_fwd: Default::default(),
+            edge_counter: 0,
+            log: Default::default(),
+            reach: Default::default(),
+            kinds: Default::default(),
+            classes: Default::default(),
+            markov: Default::default(),
+            subsumed: Default::default(),
+            hints: Default::default(),
+            pending_trail: Default::default(),
+            scopes: Default::default(),
+            batch: None,
+        }
+    }
 }
-impl TarjanStateGraph {
+impl<V: StateID> TarjanStateGraph<V> {
     /* The core parameter for the algorithm: delta = sqrt(num edges) */
     fn delta(&self) -> usize {
         (self.edge_counter as f64).sqrt() as usize
     }
 
     /* Vertex label getters / setters */
-    fn set_status(&mut self, v: usize, status: Status) {
-        // println!("Setting status: {} {:?}", v, status);
-        debug_assert!(self.is_seen(v));
-        self.graph.get_label_mut(v).unwrap().0 = status;
-    }
-    fn get_level(&self, v: usize) -> Level {
+    fn get_level(&self, v: V) -> Level {
         debug_assert!(self.is_seen(v));
         self.graph.get_label(v).unwrap().1
     }
-    fn set_level(&mut self, v: usize, level: Level) {
+    fn set_level(&mut self, v: V, level: Level) {
         // println!("Setting level: {} {:?}", v, level);
         debug_assert!(self.is_seen(v));
         self.graph.get_label_mut(v).unwrap().1 = level;
     }
 
-    fn update_levels_iterative(&mut self, v1: usize, v2: usize) {
+    fn update_levels_iterative(&mut self, v1: V, v2: V) -> Vec<V> {
         // println!("Updating levels: {} {}", v1, v2);
         // println!("Graph: {:?}", self.graph);
         // Update levels after adding an edge (v1, v2),
@@ -72,6 +117,8 @@ impl TarjanStateGraph {
         //   either. This could be added later to graph.rs. This means that
         //   instead of stopping after Delta unique vertices, I might be
         //   stopping earlier after Delta edges.
+        // Returns the accepting states in the component formed, if any (in
+        // Büchi mode), which the caller should mark live.
 
         debug_assert_eq!(self.get_status(v1), Some(Status::Unknown));
         debug_assert!(self.get_status(v2).is_some());
@@ -80,14 +127,24 @@ impl TarjanStateGraph {
             self.is_closed(v2) || self.graph.iter_fwd_edges(v2).count() == 0
         );
 
+        // v1 may no longer be canonical if an earlier edge in the same
+        // mark_closed merged it into a cycle; the searches below compare
+        // against canonical vertices.
+        let v1 = self.graph.get_canon_vertex(v1);
+        let v2 = self.graph.get_canon_vertex(v2);
+
         // ===== STEP 1: Test Order =====
         let level1 = self.get_level(v1);
         let level2 = self.get_level(v2);
         if self.graph.is_same_vertex(v1, v2) || level1 < level2 {
-            return;
+            return Vec::new();
         }
 
         // ===== STEP 2: Search Backward =====
+        // (Skipped if a NotReachable hint rules out a cycle, i.e. says v1 is
+        // not reachable from v2; Step 3 still restores the order)
+        let limit =
+            if self.is_hinted_not_reachable(v2, v1) { 0 } else { self.delta() };
         let mut found_cycle = false;
         let mut count = 0;
         let mut set_bck = HashSet::new();
@@ -96,13 +153,15 @@ impl TarjanStateGraph {
             .graph
             .dfs_bck(iter::once(v1), |u| {
                 // println!("Step 2 DFS back trying: {}", u);
-                debug_assert!(!self.is_dead(u));
+                // (With Subsumed hints, a Dead state may reach an Unknown
+                // one until it is found Dead too)
+                debug_assert!(!self.is_dead(u) || self.subsumed.has_subsumed());
                 debug_assert!(
                     !self.is_unknown(u) || self.get_level(u) <= level1
                 );
                 self.is_unknown(u) && self.get_level(u) == level1
             })
-            .take(self.delta())
+            .take(limit)
         {
             // println!("Step 2 DFS back visiting: {}", u);
             if self.graph.is_same_vertex(u, v2) {
@@ -135,7 +194,7 @@ impl TarjanStateGraph {
             };
 
             self.set_level(v2, new_level);
-            let level_to_increase: Vec<usize> = self
+            let level_to_increase: Vec<V> = self
                 .graph
                 .dfs_fwd(iter::once(v2), |w| {
                     // println!("Step 3 DFS fwd trying: {}", w);
@@ -164,24 +223,31 @@ impl TarjanStateGraph {
         let level2 = self.get_level(v2);
 
         // ===== STEP 4: Form Component =====
-        if found_cycle {
+        // (A cycle through a Dead v2 isn't one of Unknown states)
+        if found_cycle && !self.is_dead(v2) {
             // println!("Step 4 enabled (reason: found cycle)");
             debug_assert_eq!(level1, level2);
             debug_assert!(v1 != v2);
             // This part is roughly the same as merge_all_cycles in simple.rs
             let v1 = self.graph.get_canon_vertex(v1);
             let v2 = self.graph.get_canon_vertex(v2);
-            let fwd_reachable: HashSet<usize> = self
+            // Open vertices can be reached (by the edges out of closed
+            // ones), but must not be merged into the component; neither can
+            // those that NotReachable hints say can't reach v1
+            let not_reaching = self.hints.not_reaching(&self.graph, v1);
+            let fwd_reachable: HashSet<V> = self
                 .graph
                 .dfs_fwd(iter::once(v2), |w| {
                     debug_assert!(self.get_level(w) >= level1);
-                    self.get_level(w) == level1
+                    self.is_unknown(w)
+                        && self.get_level(w) == level1
+                        && !not_reaching.contains(&w)
                 })
                 .chain(iter::once(v2))
                 .collect();
             debug_assert!(fwd_reachable.contains(&(v1)));
             debug_assert!(fwd_reachable.contains(&(v2)));
-            let bi_reachable: HashSet<usize> = self
+            let bi_reachable: HashSet<V> = self
                 .graph
                 .dfs_bck(iter::once(v1), |u| fwd_reachable.contains(&u))
                 .chain(iter::once(v1))
@@ -194,82 +260,437 @@ impl TarjanStateGraph {
                     self.graph.merge(u, v1);
                 }
             }
+            // As in Simple, states merged earlier can't be accepting
+            if self.kinds.is_buchi() {
+                return bi_reachable
+                    .into_iter()
+                    .filter(|&u| self.kinds.is_accepting(u))
+                    .collect();
+            }
         }
 
         // ===== DONE =====
+        Vec::new()
+    }
+    fn is_hinted_not_reachable(&self, v: V, w: V) -> bool {
+        // Whether a NotReachable hint says w is not reachable from v
+        self.hints.has_not_reachable()
+            && self.graph.iter_aliases(v).any(|u| {
+                self.hints.iter_not_reachable(u).any(|x| {
+                    self.graph.is_seen(x) && self.graph.is_same_vertex(x, w)
+                })
+            })
     }
-    fn check_dead_iterative(&mut self, v: usize) {
+    fn check_dead_iterative(&mut self, vs: &[V]) {
         // This is the same procedure as in Simple
-        let now_dead: HashSet<usize> = self
+        debug_assert!(vs.iter().all(|&v| self.is_u_or_d(v)));
+        let now_dead: HashSet<V> = self
             .graph
             .topo_search_bck(
-                iter::once(v),
+                vs.iter().copied(),
                 |u| self.is_u_or_d(u),
                 |w| !self.is_dead(w),
             )
             .collect();
         debug_assert!(
             now_dead.is_empty()
-                || now_dead.contains(&self.graph.get_canon_vertex(v))
+                || vs.iter().any(|&v| {
+                    now_dead.contains(&self.graph.get_canon_vertex(v))
+                })
         );
         for &u in now_dead.iter() {
             self.set_status(u, Status::Dead);
         }
     }
-    fn calculate_new_live_states(&mut self, v: usize) {
-        // Same fn as in Naive
-        if self.is_live(v) {
-            let new_live: HashSet<usize> = self
-                .graph
-                .dfs_bck(iter::once(v), |u| {
-                    debug_assert!(!self.is_dead(u));
-                    !self.is_live(u)
-                })
-                .collect();
-            for &u in new_live.iter() {
-                self.set_status(u, Status::Live);
+    fn take_pending_edges(&mut self, v: V) -> Vec<V> {
+        // Pending edges are keyed by the state they were added from, so
+        // take those of every state merged with v
+        let aliases: Vec<V> = self.graph.iter_aliases(v).collect();
+        let mut result = Vec::new();
+        for u in aliases {
+            let pending = self.pending_edges_fwd.remove(&u).unwrap_or_default();
+            if !self.scopes.is_empty() && !pending.is_empty() {
+                self.pending_trail
+                    .push(PendingUndo::Remove(u, pending.clone()));
             }
+            result.extend(pending);
         }
+        result
+    }
+    fn has_pending_edges(&self, v: V) -> bool {
+        self.graph
+            .iter_aliases(v)
+            .any(|u| self.pending_edges_fwd.contains_key(&u))
     }
 }
-impl StateGraph for TarjanStateGraph {
+impl<V: StateID> Bookkeeping<V> for TarjanStateGraph<V> {
+    type Label = (Status, Level);
+    fn parts(&self) -> Parts<'_, V, Self::Label> {
+        Parts {
+            graph: &self.graph,
+            log: &self.log,
+            reach: &self.reach,
+            kinds: &self.kinds,
+            classes: &self.classes,
+            markov: &self.markov,
+            subsumed: &self.subsumed,
+        }
+    }
+    fn parts_mut(&mut self) -> PartsMut<'_, V, Self::Label> {
+        PartsMut {
+            graph: &self.graph,
+            log: &mut self.log,
+            reach: &mut self.reach,
+            kinds: &self.kinds,
+            classes: &mut self.classes,
+            markov: &mut self.markov,
+            subsumed: &mut self.subsumed,
+        }
+    }
+    fn set_status(&mut self, v: V, status: Status) {
+        // println!("Setting status: {} {:?}", v, status);
+        debug_assert!(self.is_seen(v));
+        let changed = self.get_status(v) != Some(status);
+        self.graph.update_label(v, |label| label.0 = status);
+        if changed && (status == Status::Live || status == Status::Dead) {
+            self.log.record(self.graph.iter_aliases(v), status);
+            if status == Status::Dead {
+                self.subsumed.record_dead(self.graph.iter_aliases(v));
+            }
+        }
+    }
+    fn iter_succs(&self, v: V) -> impl Iterator<Item = V> + '_ {
+        // Edges from open states are still pending, not in the graph
+        let pending = self
+            .graph
+            .iter_aliases(v)
+            .filter_map(move |u| self.pending_edges_fwd.get(&u))
+            .flatten();
+        self.graph.iter_fwd_edges(v).chain(pending.copied())
+    }
+    fn mark_subsumed_dead(&mut self, v: V) {
+        // Check the states backward from v, rather than recalculating as
+        // in Naive
+        self.set_status(v, Status::Dead);
+        let preds: Vec<V> = self
+            .graph
+            .iter_bck_edges(v)
+            .filter(|&u| self.is_unknown(u))
+            .collect();
+        self.check_dead_iterative(&preds);
+    }
+}
+impl<V: StateID> StateGraph<V> for TarjanStateGraph<V> {
     fn new() -> Self {
         Default::default()
     }
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize) {
+    fn new_buchi() -> Option<Self> {
+        Some(Self { kinds: Kinds::new_buchi(), ..Default::default() })
+    }
+    fn is_buchi(&self) -> bool {
+        self.kinds.is_buchi()
+    }
+    fn new_markov() -> Self {
+        Self { markov: Markov::new_markov(), ..Default::default() }
+    }
+    fn is_markov(&self) -> bool {
+        self.markov.is_markov()
+    }
+    fn add_labeled_transition_unchecked(
+        &mut self,
+        v1: V,
+        v2: V,
+        label: Option<usize>,
+    ) {
+        if v1 == v2 {
+            // (Only in Büchi mode) Checked when v1 is closed
+            self.graph.ensure_vertex(v1);
+            self.kinds.mark_self_loop(v1);
+            return;
+        }
         self.graph.ensure_vertex(v1);
         self.graph.ensure_vertex(v2);
-        debug_assert_eq!(self.get_status(v1), Some(Status::Open));
+        debug_assert!(self.can_add_from(v1));
         self.pending_edges_fwd.entry(v1).or_default().push(v2);
-        self.graph.ensure_edge_bck(v1, v2);
+        if !self.scopes.is_empty() {
+            self.pending_trail.push(PendingUndo::Push(v1));
+        }
+        self.graph.ensure_edge_bck(v1, v2, label);
         self.edge_counter += 1;
-        self.calculate_new_live_states(v2);
+        if let Some(dist) = self.log.distance(v2) {
+            self.calculate_new_live_states(v1, dist + 1);
+        }
+        let graph = &self.graph;
+        self.classes
+            .add_transition_for(v1, v2, |u| graph.iter_bck_edges_unmerged(u));
+        if self.reach.is_reached(v1) {
+            self.calculate_reachable_from_initial(v2);
+        }
+        if self.subsumed.is_doomed(v1) {
+            self.calculate_doomed(v2);
+            self.calculate_subsumed_dead(&[]);
+        }
     }
-    fn mark_closed_unchecked(&mut self, v: usize) {
+    fn mark_closed_unchecked(&mut self, v: V) {
         self.graph.ensure_vertex(v);
+        self.mark_closed_merged(v);
+        self.calculate_closed_for_classes(v, &[]);
+        if self.is_live(v) {
+            // (Only in Markov mode, or with other classes in use) Add
+            // pending fwd-edges, which are needed to find almost surely live
+            // states
+            for w in self.take_pending_edges(v) {
+                self.graph.ensure_edge_fwd(v, w, None);
+            }
+            self.calculate_almost_sure(&[v]);
+            return;
+        }
         self.set_status(v, Status::Unknown);
         // Add pending fwd-edges
-        let mut to_add = self.pending_edges_fwd.remove(&v).unwrap_or_default();
+        let mut to_add = self.take_pending_edges(v);
+        let mut accepting = Vec::new();
         for w in to_add.drain(..) {
             debug_assert!(self.is_seen(w));
-            self.graph.ensure_edge_fwd(v, w);
-            self.update_levels_iterative(v, w);
+            // Labels are only needed on back edges (for witnesses)
+            self.graph.ensure_edge_fwd(v, w, None);
+            accepting.extend(self.update_levels_iterative(v, w));
             debug_assert_eq!(self.get_status(v), Some(Status::Unknown));
         }
         debug_assert!(!self.pending_edges_fwd.contains_key(&v));
-        self.check_dead_iterative(v);
+        // (As in Naive, each of them is marked live)
+        for a in accepting {
+            self.log.mark_live(a);
+            self.calculate_new_live_states(a, 0);
+        }
+        self.calculate_accepting_self_loop(v);
+        if let Some(closed) = &mut self.batch {
+            closed.push(v);
+        } else if self.is_unknown(v) {
+            // (Unless v is now Live, on an accepting cycle)
+            self.check_dead_iterative(&[v]);
+            self.calculate_subsumed_dead(&[v]);
+        }
     }
-    fn mark_live_unchecked(&mut self, v: usize) {
+    fn mark_live_unchecked(&mut self, v: V) {
         self.graph.ensure_vertex(v);
         self.set_status(v, Status::Live);
-        self.calculate_new_live_states(v);
+        self.calculate_new_live_states(v, 0);
+        self.log.mark_live(v);
+    }
+    fn mark_live_for_unchecked(&mut self, v: V, class: usize) {
+        self.graph.ensure_vertex(v);
+        let graph = &self.graph;
+        self.classes
+            .mark_live_for(v, class, |u| graph.iter_bck_edges_unmerged(u));
+    }
+    fn merge_unchecked(&mut self, v1: V, v2: V) {
+        // The merge is recorded as transitions both ways between v1 and v2,
+        // but the back edges are only added along with the forward edges
+        // (if any), so that the searches in update_levels_iterative see the
+        // same edges both ways
+        self.graph.ensure_vertex(v1);
+        self.graph.ensure_vertex(v2);
+        self.edge_counter += 2;
+        self.calculate_merged(v1, v2);
+        if !self.is_unknown(v1) && !self.is_unknown(v2) {
+            // Both Live, both Dead, or both Open: levels only need to stay
+            // above those of the closed states with edges into either
+            self.graph.ensure_edge_bck(v1, v2, None);
+            self.graph.ensure_edge_bck(v2, v1, None);
+            self.graph.merge_using(v1, v2, |(st1, l1), (st2, l2)| {
+                debug_assert_eq!(st1, st2);
+                (st1, l1.max(l2))
+            });
+            self.calculate_subsumed_dead(&[v1]);
+            return;
+        }
+        // Otherwise, close the Open one (if any) as in mark_closed, and add
+        // the edges both ways, which form a component
+        for v in [v1, v2] {
+            if self.is_open(v) {
+                self.set_status(v, Status::Unknown);
+                for w in self.take_pending_edges(v) {
+                    self.graph.ensure_edge_fwd(v, w, None);
+                    self.update_levels_iterative(v, w);
+                }
+            }
+        }
+        for (u, w) in [(v1, v2), (v2, v1)] {
+            self.graph.ensure_edge(u, w, None);
+            self.update_levels_iterative(u, w);
+        }
+        debug_assert!(self.graph.is_same_vertex(v1, v2));
+        self.check_dead_iterative(&[v1]);
+        self.calculate_subsumed_dead(&[v1]);
     }
-    fn not_reachable_unchecked(&mut self, _v1: usize, _v2: usize) {
-        // Ignore NotReachable
+    fn subsumed_unchecked(&mut self, v1: V, v2: V) {
+        self.graph.ensure_vertex(v1);
+        self.graph.ensure_vertex(v2);
+        self.add_subsumed(v1, v2);
+    }
+    fn not_reachable_unchecked(&mut self, v1: V, v2: V) {
+        // Only used to prune the searches in update_levels_iterative
+        self.hints.add_not_reachable(v1, v2);
+    }
+    fn mark_initial_unchecked(&mut self, v: V) {
+        self.graph.ensure_vertex(v);
+        self.reach.mark_initial(v);
+        self.calculate_reachable_from_initial(v);
+    }
+    fn mark_accepting_unchecked(
+        &mut self,
+        v: V,
+    ) -> Result<(), StateGraphError<V>> {
+        self.graph.ensure_vertex(v);
+        self.kinds.mark_accepting(v);
+        Ok(())
     }
-    fn get_status(&self, v: usize) -> Option<Status> {
+    fn process_batch(&mut self, ts: &[Transaction<V>]) {
+        // Check for dead states once, at the end of the batch.
+        // States closed in the batch may have become live since.
+        // (Merges and Subsumed hints end the batch, see split_batch)
+        let ts = self.split_batch(ts);
+        self.batch = Some(Vec::new());
+        for &t in ts {
+            self.process(t);
+        }
+        let mut closed = self.batch.take().unwrap();
+        closed.retain(|&v| self.is_u_or_d(v));
+        self.check_dead_iterative(&closed);
+        self.calculate_subsumed_dead(&closed);
+    }
+    fn get_status(&self, v: V) -> Option<Status> {
         self.graph.get_label(v).map(|l| l.0)
     }
+    fn get_status_for(&self, v: V, class: usize) -> Option<Status> {
+        self.status_for(v, class)
+    }
+    fn iter_classes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.classes.iter_classes()
+    }
+    fn only_reaches_closed(&self, v: V) -> bool {
+        self.classes.only_reaches_closed(v)
+    }
+    fn is_marked_closed(&self, v: V) -> bool {
+        self.log.is_closed(v)
+    }
+    fn is_almost_sure(&self, v: V) -> bool {
+        self.markov.is_sure(v, &self.log)
+    }
+    fn get_kind(&self, v: V) -> Kind {
+        self.kinds.kind(v)
+    }
+    fn iter_status(&self, status: Status) -> impl Iterator<Item = V> + '_ {
+        self.graph.iter_indexed(status as usize)
+    }
+    fn count_status(&self, status: Status) -> usize {
+        self.graph.count_indexed(status as usize)
+    }
+    fn iter_initial(&self) -> impl Iterator<Item = V> + '_ {
+        self.reach.iter_initial()
+    }
+    fn is_reachable_from_initial(&self, v: V) -> bool {
+        self.reach.is_reached(v)
+    }
+    fn explain_live(&self, v: V) -> Option<Vec<V>> {
+        if !self.is_live(v) {
+            return None;
+        }
+        let targets = self.log.iter_marked_live();
+        self.graph.shortest_path_unmerged(v, targets, |u| self.is_live(u))
+    }
+    fn explain_dead(&self, v: V) -> Option<Vec<V>> {
+        if !self.is_dead(v) {
+            return None;
+        }
+        Some(self.graph.reachable_unmerged(v))
+    }
+    fn is_reachable(&self, v: V, w: V) -> Option<bool> {
+        let limit = REACHABLE_SEARCH_MAX;
+        if self.is_seen(v)
+            && self.iter_succs(v).any(|u| self.graph.is_same_vertex(u, w))
+        {
+            // (Including pending edges, which the searches below don't see)
+            Some(true)
+        } else if self.is_dead(v) && !self.subsumed.has_subsumed() {
+            // Everything reachable from v is dead, so the graph won't change
+            // there, and levels only increase along edges: we only need to
+            // search vertices at most the level of w. (Not with Subsumed
+            // hints: then Dead states may reach Open ones.)
+            if !self.is_seen(w) {
+                return Some(false);
+            }
+            let level = self.get_level(w);
+            if level < self.get_level(v) {
+                return Some(false);
+            }
+            self.graph.is_reachable_bounded(
+                v,
+                w,
+                limit,
+                |u| self.get_level(u) <= level,
+                |_| true,
+            )
+        } else {
+            // Open states' edges are pending, not in the graph, and so are
+            // those of states marked live while open.
+            self.graph.is_reachable_bounded(
+                v,
+                w,
+                limit,
+                |_| true,
+                |u| !self.can_add_from(u) && !self.has_pending_edges(u),
+            )
+        }
+    }
+    fn witness_live(&self, v: V) -> Option<Vec<usize>> {
+        let path = self.explain_live(v)?;
+        Some(self.graph.path_labels_unmerged(&path))
+    }
+    fn distance_to_live(&self, v: V) -> Option<usize> {
+        self.log.distance(v)
+    }
+    fn drain_status_changes(&mut self) -> Vec<StatusChange<V>> {
+        self.log.drain()
+    }
+    fn push(&mut self) {
+        self.graph.push();
+        self.log.push();
+        self.reach.push();
+        self.kinds.push();
+        self.classes.push();
+        self.markov.push();
+        self.subsumed.push();
+        self.hints.push();
+        self.scopes.push((self.pending_trail.len(), self.edge_counter));
+    }
+    fn pop(&mut self) {
+        self.graph.pop();
+        self.log.pop();
+        self.reach.pop();
+        self.kinds.pop();
+        self.classes.pop();
+        self.markov.pop();
+        self.subsumed.pop();
+        self.hints.pop();
+        let (trail_len, edge_counter) =
+            self.scopes.pop().expect("pop without matching push");
+        while self.pending_trail.len() > trail_len {
+            match self.pending_trail.pop().unwrap() {
+                PendingUndo::Push(v) => {
+                    let pending = self.pending_edges_fwd.get_mut(&v).unwrap();
+                    pending.pop();
+                    if pending.is_empty() {
+                        self.pending_edges_fwd.remove(&v);
+                    }
+                }
+                PendingUndo::Remove(v, pending) => {
+                    self.pending_edges_fwd.insert(v, pending);
+                }
+            }
+        }
+        self.edge_counter = edge_counter;
+    }
     fn get_space(&self) -> usize {
         self.graph.get_space()
     }
@@ -0,0 +1,33 @@
+/*
+    Binary to check the NotReachable hints of an example input against its
+    transitions, reporting the first hint contradicted and a path that
+    contradicts it.
+
+    Useful for finding a bad hint emitted by a solver: the algorithms trust
+    the hints, so a bad one may only show up as a wrong status.
+*/
+
+use state_graph::driver;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "check hints",
+    about = "Check the NotReachable hints of an example input."
+)]
+struct Args {
+    // Path to example without file extension.
+    // e.g. "examples/handwritten/12"
+    basename: String,
+}
+impl Args {
+    fn run(self) {
+        if driver::run_check_hints(&self.basename).is_some() {
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
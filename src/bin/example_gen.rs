@@ -156,6 +156,41 @@ fn gen_reverseunkloop(n: usize) -> Example {
     paramed_example("reverseunkloop", n, ex_in, expect)
 }
 
+// Same as unkline and reverseunkline, but with a NotReachable hint for
+// each edge, which rules out a cycle through it
+
+fn gen_hintunkline(n: usize) -> Example {
+    let mut ex_in = ExampleInput(vec![]);
+    for i in 0..n {
+        ex_in.push(Transaction::Add(i, i + 1));
+        ex_in.push(Transaction::NotReachable(i + 1, i));
+        ex_in.push(Transaction::Close(i));
+    }
+    let expect = ExampleOutput {
+        live: vec![],
+        dead: vec![],
+        unknown: (0..n).collect(),
+        open: vec![n],
+    };
+    paramed_example("hintunkline", n, ex_in, expect)
+}
+
+fn gen_hintreverseunkline(n: usize) -> Example {
+    let mut ex_in = ExampleInput(vec![]);
+    for i in (0..n).rev() {
+        ex_in.push(Transaction::Add(i, i + 1));
+        ex_in.push(Transaction::NotReachable(i + 1, i));
+        ex_in.push(Transaction::Close(i));
+    }
+    let expect = ExampleOutput {
+        live: vec![],
+        dead: vec![],
+        unknown: (0..n).collect(),
+        open: vec![n],
+    };
+    paramed_example("hintreverseunkline", n, ex_in, expect)
+}
+
 /*
     Entrypoint
 */
@@ -171,4 +206,9 @@ fn main() {
         gen_reverseloop(i).save();
         gen_reverseunkloop(i).save();
     }
+    // (Only small sizes, which the tests use)
+    for &i in &[3, 10, 20, 100] {
+        gen_hintunkline(i).save();
+        gen_hintreverseunkline(i).save();
+    }
 }
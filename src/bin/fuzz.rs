@@ -0,0 +1,78 @@
+/*
+    Differential fuzzing: run all algorithms on random valid transaction
+    sequences, comparing against Naive after every transaction, and in
+    batches and push/pop scopes.
+
+    On failure, the sequence is shrunk and saved as a new example (without
+    expected output, so tests compare the algorithms against Naive). The
+    tests only process it step by step, so a failure found in batches or
+    with push/pop should also get a test of its own.
+*/
+
+use state_graph::constants::EX_DIR_FUZZ;
+use state_graph::example::{Example, ExampleInput};
+use state_graph::fuzz::{self, Rng};
+use state_graph::util;
+use std::panic;
+use std::process;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "fuzz",
+    about = "Differential fuzzing of all algorithms against naive."
+)]
+struct Args {
+    // Random seed; defaults to one based on the current time.
+    #[structopt(short, long)]
+    seed: Option<u64>,
+
+    // Number of random sequences to try.
+    #[structopt(short, long, default_value = "1000")]
+    iterations: usize,
+
+    // Number of states in each random sequence.
+    #[structopt(short = "n", long, default_value = "20")]
+    states: usize,
+
+    // Where to save failing examples.
+    #[structopt(short, long, default_value = EX_DIR_FUZZ)]
+    out_dir: String,
+}
+impl Args {
+    fn run(self) {
+        let seed = self.seed.unwrap_or_else(|| {
+            util::current_datetime_str().bytes().fold(0, |acc, b| {
+                acc.wrapping_mul(31).wrapping_add(u64::from(b))
+            })
+        });
+        println!("========= Fuzz =========");
+        println!("Seed: {}", seed);
+        // Panics are caught and reported as failures; don't print them
+        panic::set_hook(Box::new(|_| {}));
+        let mut rng = Rng::new(seed);
+        for i in 0..self.iterations {
+            let transactions = fuzz::gen_transactions(&mut rng, self.states);
+            if let Err(failure) = fuzz::check(&transactions) {
+                println!("Failure on iteration {}: {}", i, failure);
+                let shrunk =
+                    fuzz::shrink(&transactions, |ts| fuzz::check(ts).is_err());
+                let failure = fuzz::check(&shrunk).unwrap_err();
+                println!(
+                    "Shrunk to {} transactions: {}",
+                    shrunk.len(),
+                    failure
+                );
+                let basename = format!("{}/fuzz_{}_{}", self.out_dir, seed, i);
+                Example::new(&basename, ExampleInput(shrunk), None).save();
+                println!("Saved: {}", basename);
+                process::exit(1);
+            }
+        }
+        println!("No failures in {} iterations.", self.iterations);
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
@@ -0,0 +1,35 @@
+/*
+    Keep a single state graph alive and drive it with textual commands
+    on stdin, answering on stdout. See repl.rs for the protocol.
+
+    Example:
+        $ cargo run --bin repl -- -a jump
+        add 1 2
+        ok
+        close 1
+        ok
+        status 1
+        unknown
+*/
+
+use state_graph::driver::{self, Algorithm};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "repl",
+    about = "Drive a state graph algorithm with commands on stdin."
+)]
+struct Args {
+    #[structopt(short, long, default_value = "Naive")]
+    algorithm: Algorithm,
+}
+impl Args {
+    fn run(self) {
+        driver::run_repl(self.algorithm);
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
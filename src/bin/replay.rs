@@ -0,0 +1,47 @@
+/*
+    Replay a transaction log (JSON or binary) on a state graph algorithm,
+    reading it incrementally from a file or stdin.
+
+    Unlike run_example, the log is never loaded into memory all at once,
+    so this works for very long traces recorded from a solver.
+*/
+
+use state_graph::driver::{self, Algorithm};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "replay",
+    about = "Replay a transaction log from a file or stdin."
+)]
+struct Args {
+    // Path to the transaction log, including the extension.
+    // e.g. "examples/handwritten/2_in.json"
+    // Reads from stdin if omitted.
+    path: Option<String>,
+
+    // Optional expected output to check against.
+    // e.g. "examples/handwritten/2_expect.json"
+    #[structopt(short, long)]
+    expect: Option<String>,
+
+    #[structopt(short, long, default_value = "Naive")]
+    algorithm: Algorithm,
+
+    #[structopt(short, long, default_value = "10")]
+    timeout: u64,
+}
+impl Args {
+    fn run(self) {
+        driver::run_replay(
+            self.path.as_deref(),
+            self.expect.as_deref(),
+            self.algorithm,
+            self.timeout,
+        );
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
@@ -0,0 +1,220 @@
+/*
+    Compact binary encoding for transaction logs (ExampleInput)
+
+    Much smaller and faster to parse than the pretty-printed JSON, for
+    recording long runs of a solver and replaying them later.
+
+    Format: a header (the magic bytes "SGTL" followed by a version byte),
+    then one record per transaction: an opcode followed by one or two
+    vertex IDs (and the label, for a labeled transition, or
+    the class, for a state live for a class), each encoded as an
+    unsigned LEB128 varint.
+
+    Reading and writing are both streaming: transactions are encoded or
+    decoded one at a time, so the whole log never needs to be in memory.
+*/
+
+use super::interface::Transaction;
+use std::convert::TryFrom;
+use std::io::{self, ErrorKind, Read, Write};
+
+pub const MAGIC: &[u8; 4] = b"SGTL";
+const VERSION: u8 = 1;
+
+const OP_ADD: u64 = 0;
+const OP_CLOSE: u64 = 1;
+const OP_LIVE: u64 = 2;
+const OP_NOT_REACHABLE: u64 = 3;
+const OP_ADD_LABELED: u64 = 4;
+const OP_INITIAL: u64 = 5;
+const OP_UNIVERSAL: u64 = 6;
+const OP_ACCEPTING: u64 = 7;
+const OP_LIVE_FOR: u64 = 8;
+const OP_MERGE: u64 = 9;
+const OP_SUBSUMED: u64 = 10;
+
+/*
+    Varint encoding
+*/
+
+fn write_varint<W: Write>(writer: &mut W, mut x: u64) -> io::Result<()> {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (x & 0x7f) as u8;
+        x >>= 7;
+        if x == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    writer.write_all(&buf[..len])
+}
+
+fn read_byte<R: Read>(reader: &mut R) -> io::Result<Option<u8>> {
+    // Returns None on a clean end of input
+    let mut buf = [0u8];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(buf[0])),
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn read_varint<R: Read>(reader: &mut R, first: Option<u8>) -> io::Result<u64> {
+    // first: the first byte, if already read by the caller
+    let mut result = 0;
+    let mut shift = 0;
+    let mut next = first;
+    loop {
+        let byte = match next.take() {
+            Some(byte) => byte,
+            None => read_byte(reader)?.ok_or_else(|| {
+                io::Error::new(ErrorKind::UnexpectedEof, "truncated record")
+            })?,
+        };
+        if shift >= 64 || (shift == 63 && byte > 1) {
+            return Err(invalid_data("varint overflows 64 bits"));
+        }
+        result |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg.to_string())
+}
+
+/*
+    Streaming writer and reader
+*/
+
+pub struct TransactionWriter<W: Write> {
+    writer: W,
+}
+impl<W: Write> TransactionWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Self { writer })
+    }
+    pub fn write(&mut self, t: Transaction) -> io::Result<()> {
+        match t {
+            Transaction::Add(v1, v2) => self.write_record(OP_ADD, &[v1, v2]),
+            Transaction::AddLabeled(v1, v2, l) => {
+                self.write_record(OP_ADD_LABELED, &[v1, v2, l])
+            }
+            Transaction::Close(v) => self.write_record(OP_CLOSE, &[v]),
+            Transaction::Live(v) => self.write_record(OP_LIVE, &[v]),
+            Transaction::NotReachable(v1, v2) => {
+                self.write_record(OP_NOT_REACHABLE, &[v1, v2])
+            }
+            Transaction::Initial(v) => self.write_record(OP_INITIAL, &[v]),
+            Transaction::Universal(v) => self.write_record(OP_UNIVERSAL, &[v]),
+            Transaction::Accepting(v) => self.write_record(OP_ACCEPTING, &[v]),
+            Transaction::LiveFor(v, c) => {
+                self.write_record(OP_LIVE_FOR, &[v, c])
+            }
+            Transaction::Merge(v1, v2) => {
+                self.write_record(OP_MERGE, &[v1, v2])
+            }
+            Transaction::Subsumed(v1, v2) => {
+                self.write_record(OP_SUBSUMED, &[v1, v2])
+            }
+        }
+    }
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+    fn write_record(&mut self, op: u64, vs: &[usize]) -> io::Result<()> {
+        write_varint(&mut self.writer, op)?;
+        for &v in vs {
+            write_varint(&mut self.writer, v as u64)?;
+        }
+        Ok(())
+    }
+}
+
+// Iterates over the transactions in the log, stopping after the first error.
+pub struct TransactionReader<R: Read> {
+    reader: R,
+    done: bool,
+}
+impl<R: Read> TransactionReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("not a binary transaction log"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data("unsupported transaction log version"));
+        }
+        Ok(Self { reader, done: false })
+    }
+    fn read_vertex(&mut self) -> io::Result<usize> {
+        let v = read_varint(&mut self.reader, None)?;
+        usize::try_from(v).map_err(|_| invalid_data("vertex ID too large"))
+    }
+    fn read_transaction(&mut self) -> io::Result<Option<Transaction>> {
+        let first = match read_byte(&mut self.reader)? {
+            Some(byte) => byte,
+            None => return Ok(None),
+        };
+        let t = match read_varint(&mut self.reader, Some(first))? {
+            OP_ADD => {
+                Transaction::Add(self.read_vertex()?, self.read_vertex()?)
+            }
+            OP_CLOSE => Transaction::Close(self.read_vertex()?),
+            OP_LIVE => Transaction::Live(self.read_vertex()?),
+            OP_NOT_REACHABLE => Transaction::NotReachable(
+                self.read_vertex()?,
+                self.read_vertex()?,
+            ),
+            OP_ADD_LABELED => Transaction::AddLabeled(
+                self.read_vertex()?,
+                self.read_vertex()?,
+                self.read_vertex()?,
+            ),
+            OP_INITIAL => Transaction::Initial(self.read_vertex()?),
+            OP_UNIVERSAL => Transaction::Universal(self.read_vertex()?),
+            OP_ACCEPTING => Transaction::Accepting(self.read_vertex()?),
+            OP_LIVE_FOR => {
+                Transaction::LiveFor(self.read_vertex()?, self.read_vertex()?)
+            }
+            OP_MERGE => {
+                Transaction::Merge(self.read_vertex()?, self.read_vertex()?)
+            }
+            OP_SUBSUMED => {
+                Transaction::Subsumed(self.read_vertex()?, self.read_vertex()?)
+            }
+            _ => return Err(invalid_data("unknown transaction opcode")),
+        };
+        Ok(Some(t))
+    }
+}
+impl<R: Read> Iterator for TransactionReader<R> {
+    type Item = io::Result<Transaction>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_transaction().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
@@ -7,12 +7,19 @@
 
 // File extensions
 pub const EXAMPLE_IN_EXT: &str = "_in.json";
+pub const EXAMPLE_IN_BIN_EXT: &str = "_in.bin";
 pub const EXAMPLE_EXPECT_EXT: &str = "_expect.json";
 
 // Folders where examples are stored, optionally with expected output
 // Main/special folders
 pub const EX_DIR_GENERATED: &str = "examples/generated";
 pub const EX_DIR_HANDWRITTEN: &str = "examples/handwritten";
+pub const EX_DIR_FUZZ: &str = "examples/fuzz";
+// Examples with universal states (only for algorithms that support them, so
+// not in the list below)
+pub const EX_DIR_ALTERNATING: &str = "examples/alternating";
+// Examples in Büchi mode (likewise)
+pub const EX_DIR_BUCHI: &str = "examples/buchi";
 // All other folders
 pub const EX_DIR_REGEX_COMP: &str = "examples/regex/complement";
 pub const EX_DIR_REGEX_DATE: &str = "examples/regex/date";
@@ -34,6 +41,7 @@ pub const EX_DIR_RLIB_SUB2: &str = "examples/regex/regexlib/RegexSubset/unsat";
 pub const ALL_EXAMPLE_DIRS: &[&str] = &[
     EX_DIR_GENERATED,
     EX_DIR_HANDWRITTEN,
+    EX_DIR_FUZZ,
     EX_DIR_REGEX_COMP,
     EX_DIR_REGEX_DATE,
     EX_DIR_REGEX_LOOP,
@@ -7,11 +7,15 @@
 use super::algorithm::{
     JumpStateGraph, NaiveStateGraph, SimpleStateGraph, TarjanStateGraph,
 };
-use super::constants::EXAMPLE_IN_EXT;
-use super::example::{Example, ExampleResult};
+use super::constants::{EXAMPLE_IN_BIN_EXT, EXAMPLE_IN_EXT};
+use super::example::{Example, ExampleOutput, ExampleResult, Replay};
 use super::interface::StateGraph;
+use super::repl::Repl;
+use super::util;
+use super::validate::{HintValidator, HintViolation, ValidationError};
 use std::fmt::{self, Debug};
 use std::fs;
+use std::io::{self, BufRead};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
@@ -21,7 +25,7 @@ use structopt::StructOpt;
     Exposed enum for which state graph implementation to use
 */
 
-#[derive(Debug, StructOpt)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, StructOpt)]
 pub enum Algorithm {
     Naive,
     Simple,
@@ -56,6 +60,29 @@ impl fmt::Display for Algorithm {
     Run examples with a given algorithm
 */
 
+fn new_graph<G: StateGraph>(example: &Example) -> Result<G, &'static str> {
+    // Examples with accepting states are run in Büchi mode.
+    // Fails with what is unsupported if the algorithm can't run the example.
+    if example.input.is_alternating() && !G::ALTERNATING {
+        return Err("universal states");
+    }
+    if example.input.is_buchi() {
+        G::new_buchi().ok_or("Büchi mode")
+    } else {
+        Ok(G::new())
+    }
+}
+
+fn run_graph<G: StateGraph>(
+    example: &Example,
+    timeout: Duration,
+) -> ExampleResult {
+    match new_graph::<G>(example) {
+        Ok(mut graph) => example.run_with_timeout(&mut graph, timeout),
+        Err(what) => ExampleResult::Unsupported(what),
+    }
+}
+
 fn run_core(
     example: &Example,
     alg: Algorithm,
@@ -71,38 +98,14 @@ fn run_core(
         );
     }
     let result = match alg {
-        Algorithm::Naive => {
-            let mut graph = NaiveStateGraph::new();
-            example.run_with_timeout(&mut graph, timeout)
-        }
-        Algorithm::Simple => {
-            let mut graph = SimpleStateGraph::new();
-            example.run_with_timeout(&mut graph, timeout)
-        }
-        Algorithm::Tarjan => {
-            let mut graph = TarjanStateGraph::new();
-            example.run_with_timeout(&mut graph, timeout)
-        }
-        Algorithm::Jump => {
-            let mut graph = JumpStateGraph::new();
-            example.run_with_timeout(&mut graph, timeout)
-        }
+        Algorithm::Naive => run_graph::<NaiveStateGraph>(example, timeout),
+        Algorithm::Simple => run_graph::<SimpleStateGraph>(example, timeout),
+        Algorithm::Tarjan => run_graph::<TarjanStateGraph>(example, timeout),
+        Algorithm::Jump => run_graph::<JumpStateGraph>(example, timeout),
     };
 
     if verbose {
-        println!("=== Output ===");
-        println!("{}", result.output_str());
-        println!("=== Result ===");
-        if result.is_correct() {
-            if example.expected.is_some() {
-                println!("Output is correct.");
-            }
-            println!("Stastics: {}", result.summary());
-        } else {
-            println!("Output is incorrect!");
-            println!("=== Expected Output ===");
-            println!("{:?}", example.expected.as_ref().unwrap());
-        }
+        print_result(&result, example.expected.as_ref());
     } else {
         println!("{}: {}", alg, result.summary());
     }
@@ -110,6 +113,24 @@ fn run_core(
     result
 }
 
+fn print_result(result: &ExampleResult, expected: Option<&ExampleOutput>) {
+    println!("=== Output ===");
+    println!("{}", result.output_str());
+    println!("=== Result ===");
+    if result.is_correct() {
+        if expected.is_some() {
+            println!("Output is correct.");
+        }
+        println!("Stastics: {}", result.summary());
+    } else {
+        println!("Output is incorrect!");
+        if let Some(expected) = expected {
+            println!("=== Expected Output ===");
+            println!("{:?}", expected);
+        }
+    }
+}
+
 pub fn run_single_example(
     basename: &str,
     algorithm: Algorithm,
@@ -120,6 +141,111 @@ pub fn run_single_example(
     run_core(&example, algorithm, timeout, true);
 }
 
+/*
+    Check the NotReachable hints of an example against its transitions
+    (see validate.rs), stopping at the first one contradicted
+*/
+
+pub fn run_check_hints(basename: &str) -> Option<HintViolation> {
+    let example = Example::load_from(basename);
+    println!("===== {} =====", example.name());
+    println!("Checking NotReachable hints...");
+    let graph: NaiveStateGraph = new_graph(&example).unwrap();
+    let mut validator = HintValidator::new(graph);
+    let mut result = None;
+    for (i, &t) in example.input.0.iter().enumerate() {
+        if let Err(ValidationError::Hint(violation)) = validator.try_process(t)
+        {
+            println!("Transaction {}: {:?}", i, t);
+            result = Some(violation);
+            break;
+        }
+    }
+    match &result {
+        Some(violation) => println!("Hint violated -- {}", violation),
+        None => println!("All hints hold."),
+    }
+    result
+}
+
+/*
+    Streaming replay of a transaction log (JSON or binary), from a file
+    or from stdin if no path is given
+*/
+
+fn replay_core<G: StateGraph, R: BufRead>(
+    reader: R,
+    expected: Option<&ExampleOutput>,
+    timeout: Duration,
+) -> ExampleResult {
+    let mut graph = G::new();
+    Replay::new(&mut graph, timeout)
+        .replay_from(reader, expected)
+        .unwrap_or_else(|err| {
+            panic!("Could not replay transactions -- {}", err)
+        })
+}
+
+pub fn run_replay(
+    path: Option<&str>,
+    expectfile: Option<&str>,
+    algorithm: Algorithm,
+    timeout_secs: u64,
+) -> ExampleResult {
+    let expected: Option<ExampleOutput> = expectfile.map(util::from_json_file);
+    let expected = expected.as_ref();
+    let timeout = Duration::from_secs(timeout_secs);
+    let reader: Box<dyn BufRead> = match path {
+        Some(path) => Box::new(util::path_reader(path)),
+        None => Box::new(io::stdin().lock()),
+    };
+    println!("===== {} =====", path.unwrap_or("<stdin>"));
+    println!(
+        "Replaying with algorithm '{}' with timeout {}s...",
+        algorithm, timeout_secs
+    );
+    let result = match algorithm {
+        Algorithm::Naive => {
+            replay_core::<NaiveStateGraph, _>(reader, expected, timeout)
+        }
+        Algorithm::Simple => {
+            replay_core::<SimpleStateGraph, _>(reader, expected, timeout)
+        }
+        Algorithm::Tarjan => {
+            replay_core::<TarjanStateGraph, _>(reader, expected, timeout)
+        }
+        Algorithm::Jump => {
+            replay_core::<JumpStateGraph, _>(reader, expected, timeout)
+        }
+    };
+    print_result(&result, expected);
+    result
+}
+
+/*
+    Serve the line protocol (see repl.rs) on stdin/stdout
+*/
+
+pub fn run_repl(algorithm: Algorithm) {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let result = match algorithm {
+        Algorithm::Naive => {
+            Repl::<NaiveStateGraph>::new().run(stdin.lock(), stdout.lock())
+        }
+        Algorithm::Simple => {
+            Repl::<SimpleStateGraph>::new().run(stdin.lock(), stdout.lock())
+        }
+        Algorithm::Tarjan => {
+            Repl::<TarjanStateGraph>::new().run(stdin.lock(), stdout.lock())
+        }
+        Algorithm::Jump => {
+            Repl::<JumpStateGraph>::new().run(stdin.lock(), stdout.lock())
+        }
+    };
+    result.unwrap_or_else(|err| panic!("I/O error in REPL -- {}", err));
+}
+
 /*
     Assertion for unit testing
 */
@@ -130,25 +256,26 @@ pub fn assert_example(basename: &str, timeout_secs: u64) {
 
     // If example has expected output, check each algorithm is correct
     // separately. Otherwise, compare them with respect to each other.
+    // Algorithms that don't support the example (universal states or Büchi
+    // mode) are skipped.
+    let algorithms = [Algorithm::Simple, Algorithm::Tarjan, Algorithm::Jump];
     if example.expected.is_some() {
         println!("Asserting each algorithm output matches expected...");
         let naive = run_core(&example, Algorithm::Naive, timeout, true);
         assert!(naive.is_correct());
-        let simple = run_core(&example, Algorithm::Simple, timeout, true);
-        assert!(simple.is_correct());
-        let tarjan = run_core(&example, Algorithm::Tarjan, timeout, true);
-        assert!(tarjan.is_correct());
-        let jump = run_core(&example, Algorithm::Jump, timeout, true);
-        assert!(jump.is_correct());
+        for alg in algorithms {
+            let result = run_core(&example, alg, timeout, true);
+            assert!(result.is_correct() || result.is_unsupported());
+        }
     } else {
         println!("Asserting each algorithm output matches naive...");
         let naive = run_core(&example, Algorithm::Naive, timeout, true);
-        let simple = run_core(&example, Algorithm::Simple, timeout, true);
-        assert_eq!(naive.unwrap_output(), simple.unwrap_output());
-        let tarjan = run_core(&example, Algorithm::Tarjan, timeout, true);
-        assert_eq!(naive.unwrap_output(), tarjan.unwrap_output());
-        let jump = run_core(&example, Algorithm::Jump, timeout, true);
-        assert_eq!(naive.unwrap_output(), jump.unwrap_output());
+        for alg in algorithms {
+            let result = run_core(&example, alg, timeout, true);
+            if !result.is_unsupported() {
+                assert_eq!(naive.unwrap_output(), result.unwrap_output());
+            }
+        }
     }
 }
 
@@ -211,7 +338,8 @@ pub fn run_compare(basename: &str, timeout_secs: u64) -> String {
 */
 
 pub fn example_basenames_in_dir(dir: &str) -> Vec<String> {
-    fs::read_dir(PathBuf::from(dir))
+    // Includes examples in either JSON or binary format (once if both)
+    let mut result: Vec<String> = fs::read_dir(PathBuf::from(dir))
         .unwrap_or_else(|err| {
             panic!("couldn't view files in directory: {} ({})", dir, err)
         })
@@ -226,7 +354,13 @@ pub fn example_basenames_in_dir(dir: &str) -> Vec<String> {
                 panic!("found file path with invalid unicode ({:?})", err)
             })
         })
-        .map(|path| path.strip_suffix(EXAMPLE_IN_EXT).map(String::from))
-        .flatten()
-        .collect()
+        .filter_map(|path| {
+            path.strip_suffix(EXAMPLE_IN_EXT)
+                .or_else(|| path.strip_suffix(EXAMPLE_IN_BIN_EXT))
+                .map(String::from)
+        })
+        .collect();
+    result.sort_unstable();
+    result.dedup();
+    result
 }
@@ -5,14 +5,21 @@
     Includes interfacing with file input/output and running an example using
     a timeout.
 
-    Core types: ExampleInput, ExampleOutput, Example, and ExampleResult.
+    Core types: ExampleInput, ExampleOutput, Example, and ExampleResult;
+    plus Replay, for running transactions as they are streamed in.
 */
 
-use super::constants::{EXAMPLE_EXPECT_EXT, EXAMPLE_IN_EXT};
+use super::binlog::{self, TransactionReader, TransactionWriter};
+use super::constants::{
+    EXAMPLE_EXPECT_EXT, EXAMPLE_IN_BIN_EXT, EXAMPLE_IN_EXT,
+};
 use super::interface::{StateGraph, Status, Transaction};
 use super::util;
-use serde::{Deserialize, Serialize};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
 use std::path::PathBuf;
 use std::time::{Duration, SystemTime};
 
@@ -33,17 +40,25 @@ impl ExampleInput {
         let mut result = HashSet::new();
         for &t in &self.0 {
             match t {
-                Transaction::Add(v1, v2) => {
+                Transaction::Add(v1, v2)
+                | Transaction::AddLabeled(v1, v2, _) => {
                     result.insert(v1);
                     result.insert(v2);
                 }
                 Transaction::Close(v1) => {
                     result.insert(v1);
                 }
-                Transaction::Live(v1) => {
+                Transaction::Live(v1) | Transaction::LiveFor(v1, _) => {
                     result.insert(v1);
                 }
-                Transaction::NotReachable(v1, v2) => {
+                Transaction::Initial(v1)
+                | Transaction::Universal(v1)
+                | Transaction::Accepting(v1) => {
+                    result.insert(v1);
+                }
+                Transaction::NotReachable(v1, v2)
+                | Transaction::Merge(v1, v2)
+                | Transaction::Subsumed(v1, v2) => {
                     result.insert(v1);
                     result.insert(v2);
                 }
@@ -51,6 +66,29 @@ impl ExampleInput {
         }
         result
     }
+    // Whether there are universal states, which only some algorithms
+    // support (see StateGraph::ALTERNATING)
+    pub fn is_alternating(&self) -> bool {
+        self.0.iter().any(|t| matches!(t, Transaction::Universal(_)))
+    }
+    // Whether there are accepting states, in which case the example is run
+    // in Büchi mode (see StateGraph::new_buchi)
+    pub fn is_buchi(&self) -> bool {
+        self.0.iter().any(|t| matches!(t, Transaction::Accepting(_)))
+    }
+
+    // Compact binary format (see binlog.rs)
+    pub fn read_binary<R: Read>(reader: R) -> io::Result<Self> {
+        let transactions = TransactionReader::new(reader)?;
+        Ok(Self(transactions.collect::<io::Result<_>>()?))
+    }
+    pub fn write_binary<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = TransactionWriter::new(writer)?;
+        for &t in &self.0 {
+            writer.write(t)?;
+        }
+        writer.flush()
+    }
 }
 
 #[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
@@ -100,13 +138,19 @@ pub struct ReleaseStats {
 }
 pub enum ExampleResult {
     Timeout,
+    // The algorithm doesn't support the example (universal states, or
+    // Büchi mode), so it wasn't run
+    Unsupported(&'static str),
     Debug(DebugStats),
     Release(ReleaseStats),
 }
 impl ExampleResult {
+    pub fn is_unsupported(&self) -> bool {
+        matches!(self, Self::Unsupported(_))
+    }
     pub fn is_correct(&self) -> bool {
         match self {
-            Self::Timeout => false,
+            Self::Timeout | Self::Unsupported(_) => false,
             Self::Debug(res) => res.correct,
             Self::Release(res) => res.correct,
         }
@@ -114,6 +158,8 @@ impl ExampleResult {
     pub fn summary(&self) -> String {
         if let Self::Timeout = self {
             "Timeout".to_string()
+        } else if let Self::Unsupported(what) = self {
+            format!("Unsupported ({})", what)
         } else if !self.is_correct() {
             "Wrong Output".to_string()
         } else if let Self::Debug(res) = self {
@@ -127,6 +173,7 @@ impl ExampleResult {
     pub fn time_str(&self) -> String {
         match self {
             Self::Timeout => "Timeout".to_string(),
+            Self::Unsupported(_) => "Unsupported".to_string(),
             Self::Debug(res) => format!("{}", res.time),
             Self::Release(res) => format!("{}", res.time.as_millis()),
         }
@@ -134,6 +181,7 @@ impl ExampleResult {
     pub fn space_str(&self) -> String {
         match self {
             Self::Timeout => "Timeout".to_string(),
+            Self::Unsupported(_) => "Unsupported".to_string(),
             Self::Debug(res) => format!("{}", res.space),
             Self::Release(_) => "Unknown (not tracked)".to_string(),
         }
@@ -141,6 +189,9 @@ impl ExampleResult {
     pub fn unwrap_output(&self) -> &ExampleOutput {
         match self {
             Self::Timeout => panic!("Unwrapped Timeout!"),
+            Self::Unsupported(what) => {
+                panic!("Unwrapped Unsupported: {}", what)
+            }
             Self::Debug(res) => &res.output,
             Self::Release(res) => &res.output,
         }
@@ -148,14 +199,30 @@ impl ExampleResult {
     pub fn output_str(&self) -> String {
         match self {
             Self::Timeout => "Timeout".to_string(),
+            Self::Unsupported(what) => format!("Unsupported ({})", what),
             Self::Debug(res) => format!("{:?}", res.output),
             Self::Release(res) => format!("{:?}", res.output),
         }
     }
 }
 
-fn infile_path(basename: &str) -> PathBuf {
-    PathBuf::from(format!("{}{}", basename, EXAMPLE_IN_EXT))
+// File format for the example input, determined by the file extension
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InputFormat {
+    Json,
+    Binary,
+}
+impl InputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => EXAMPLE_IN_EXT,
+            Self::Binary => EXAMPLE_IN_BIN_EXT,
+        }
+    }
+}
+
+fn infile_path(basename: &str, format: InputFormat) -> PathBuf {
+    PathBuf::from(format!("{}{}", basename, format.extension()))
 }
 fn expectfile_path(basename: &str) -> PathBuf {
     PathBuf::from(format!("{}{}", basename, EXAMPLE_EXPECT_EXT))
@@ -164,6 +231,7 @@ pub struct Example {
     pub basename: String, // path to example, without the extension
     pub input: ExampleInput,
     pub expected: Option<ExampleOutput>,
+    pub format: InputFormat, // format of the input file
 }
 impl Example {
     pub fn new(
@@ -172,7 +240,8 @@ impl Example {
         expected: Option<ExampleOutput>,
     ) -> Self {
         let basename = basename.to_string();
-        Self { basename, input, expected }
+        let format = InputFormat::Json;
+        Self { basename, input, expected, format }
     }
     pub fn name(&self) -> &str {
         &self.basename
@@ -184,21 +253,53 @@ impl Example {
         self.input.0.is_empty()
     }
     pub fn load_from(basename: &str) -> Self {
-        // Panics if infile (self.infile_path()) does not exist
-        let infile = infile_path(basename);
+        // Loads the JSON infile if it exists, otherwise the binary one.
+        // Panics if neither exists.
+        let format =
+            if util::file_exists(infile_path(basename, InputFormat::Binary))
+                && !util::file_exists(infile_path(basename, InputFormat::Json))
+            {
+                InputFormat::Binary
+            } else {
+                InputFormat::Json
+            };
+        let infile = infile_path(basename, format);
         let expectfile = expectfile_path(basename);
-        let input = util::from_json_file(&infile);
+        let input = match format {
+            InputFormat::Json => util::from_json_file(&infile),
+            InputFormat::Binary => {
+                ExampleInput::read_binary(util::path_reader(&infile))
+                    .unwrap_or_else(|err| {
+                        panic!(
+                            "Could not read binary from {:?} -- {}",
+                            infile, err
+                        )
+                    })
+            }
+        };
         let expected = if util::file_exists(&expectfile) {
             util::from_json_file(&expectfile)
         } else {
             None
         };
-        Self::new(basename, input, expected)
+        let mut result = Self::new(basename, input, expected);
+        result.format = format;
+        result
     }
     pub fn save(&self) {
-        util::to_json_file(infile_path(&self.basename), &self.input);
+        // Saves the input in self.format; expected output is always JSON
+        let infile = infile_path(&self.basename, self.format);
+        match self.format {
+            InputFormat::Json => util::to_json_file(infile, &self.input),
+            InputFormat::Binary => self
+                .input
+                .write_binary(util::path_writer(&infile))
+                .unwrap_or_else(|err| {
+                    panic!("Could not write binary to {:?} -- {}", infile, err)
+                }),
+        }
         if let Some(expect) = &self.expected {
-            util::to_json_file(expectfile_path(&self.basename), &expect);
+            util::to_json_file(expectfile_path(&self.basename), expect);
         }
     }
 
@@ -211,19 +312,78 @@ impl Example {
         graph: &mut G,
         timeout: Duration,
     ) -> ExampleResult {
-        let start = SystemTime::now();
+        let mut replay = Replay::new(graph, timeout);
         for &t in &self.input.0 {
-            let time_elapsed = util::time_since(&start);
-            if time_elapsed > timeout {
-                return ExampleResult::Timeout;
+            if !replay.feed(t) {
+                break;
+            }
+        }
+        replay.finish(self.expected.as_ref())
+    }
+}
+
+/*
+    Streaming replay
+
+    Runs transactions on a graph one at a time as they arrive, e.g. while
+    reading them from a file or stdin, without materializing the
+    ExampleInput. Only the set of states seen is kept (for the output), so
+    memory stays proportional to the graph rather than the trace.
+*/
+
+pub struct Replay<'a, G: StateGraph> {
+    graph: &'a mut G,
+    states: HashSet<usize>,
+    start: SystemTime,
+    timeout: Duration,
+    timed_out: bool,
+}
+impl<'a, G: StateGraph> Replay<'a, G> {
+    pub fn new(graph: &'a mut G, timeout: Duration) -> Self {
+        let states = HashSet::new();
+        let start = SystemTime::now();
+        Self { graph, states, start, timeout, timed_out: false }
+    }
+    pub fn is_timed_out(&self) -> bool {
+        self.timed_out
+    }
+    // Process one transaction. Returns false (and ignores the transaction)
+    // once the timeout has been exceeded.
+    pub fn feed(&mut self, t: Transaction) -> bool {
+        if self.timed_out || util::time_since(&self.start) > self.timeout {
+            self.timed_out = true;
+            return false;
+        }
+        match t {
+            Transaction::Add(v1, v2)
+            | Transaction::AddLabeled(v1, v2, _)
+            | Transaction::NotReachable(v1, v2)
+            | Transaction::Merge(v1, v2)
+            | Transaction::Subsumed(v1, v2) => {
+                self.states.insert(v1);
+                self.states.insert(v2);
+            }
+            Transaction::Close(v1)
+            | Transaction::Live(v1)
+            | Transaction::Initial(v1)
+            | Transaction::Universal(v1)
+            | Transaction::Accepting(v1)
+            | Transaction::LiveFor(v1, _) => {
+                self.states.insert(v1);
             }
-            graph.process(t);
         }
-        let total_elapsed = util::time_since(&start);
-        let (output, correct) = self.collect_output(graph);
+        self.graph.process(t);
+        true
+    }
+    pub fn finish(self, expected: Option<&ExampleOutput>) -> ExampleResult {
+        if self.timed_out {
+            return ExampleResult::Timeout;
+        }
+        let total_elapsed = util::time_since(&self.start);
+        let (output, correct) = self.collect_output(expected);
         if cfg!(debug_assertions) {
-            let time = graph.get_time();
-            let space = graph.get_space();
+            let time = self.graph.get_time();
+            let space = self.graph.get_space();
             ExampleResult::Debug(DebugStats { output, correct, time, space })
         } else {
             ExampleResult::Release(ReleaseStats {
@@ -233,16 +393,16 @@ impl Example {
             })
         }
     }
-    fn collect_output<G: StateGraph>(
+    fn collect_output(
         &self,
-        graph: &mut G,
+        expected: Option<&ExampleOutput>,
     ) -> (ExampleOutput, bool) {
         let mut output = ExampleOutput::new();
-        for &v in self.input.get_states().iter() {
-            output.add(v, graph.get_status(v).unwrap_or(Status::Open));
+        for &v in self.states.iter() {
+            output.add(v, self.graph.get_status(v).unwrap_or(Status::Open));
         }
         output.finalize();
-        if let Some(expect) = &self.expected {
+        if let Some(expect) = expected {
             let correct = &output == expect;
             (output, correct)
         } else {
@@ -250,4 +410,48 @@ impl Example {
             (output, true)
         }
     }
+
+    // Replay a whole transaction log from a reader, in either format
+    // (detected from the first byte: binary logs start with a magic header,
+    // JSON with '[' or whitespace).
+    pub fn replay_from<R: BufRead>(
+        mut self,
+        mut reader: R,
+        expected: Option<&ExampleOutput>,
+    ) -> io::Result<ExampleResult> {
+        let is_binary = reader.fill_buf()?.first() == Some(&binlog::MAGIC[0]);
+        if is_binary {
+            for t in TransactionReader::new(reader)? {
+                if !self.feed(t?) {
+                    break;
+                }
+            }
+        } else {
+            let mut de = serde_json::Deserializer::from_reader(reader);
+            match de.deserialize_seq(ReplayVisitor(&mut self)) {
+                Ok(()) => de.end()?,
+                Err(_) if self.timed_out => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(self.finish(expected))
+    }
+}
+
+// Feeds the elements of a JSON array to a Replay as they are parsed
+struct ReplayVisitor<'r, 'a, G: StateGraph>(&'r mut Replay<'a, G>);
+impl<'de, 'r, 'a, G: StateGraph> Visitor<'de> for ReplayVisitor<'r, 'a, G> {
+    type Value = ();
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of transactions")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(t) = seq.next_element()? {
+            if !self.0.feed(t) {
+                // Stop parsing early
+                return Err(de::Error::custom("timeout"));
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,445 @@
+/*
+    C API (foreign function interface)
+
+    Exposes the StateGraph interface to C/C++ through an opaque handle,
+    with the algorithm selected at creation. States are identified by
+    size_t. The header include/state_graph.h is generated from this file
+    with cbindgen (see cbindgen.toml for the command).
+
+    Every transaction is validated (as in the try_ functions) and returns a
+    result code; invalid inputs leave the graph unchanged rather than
+    panicking across the FFI boundary. As in Rust, SG_RESULT_SELF_LOOP and
+    SG_RESULT_ALREADY_LIVE are redundant rather than errors: the input has
+    no effect, but is legal.
+
+    A panic inside the library (a bug) is caught rather than unwinding into
+    C: transactions return SG_RESULT_INTERNAL_ERROR, and getters return
+    what they return for a null handle. The graph may then be inconsistent
+    and should only be freed.
+*/
+
+use super::algorithm::{
+    JumpStateGraph, NaiveStateGraph, SimpleStateGraph, TarjanStateGraph,
+};
+use super::interface::{
+    Probability, StateGraph, StateGraphError, Status, Transaction,
+};
+use std::panic::{self, AssertUnwindSafe};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SgAlgorithm {
+    Naive = 0,
+    Simple = 1,
+    Tarjan = 2,
+    Jump = 3,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SgStatus {
+    Live = 0,
+    Dead = 1,
+    Unknown = 2,
+    Open = 3,
+    Unseen = 4,
+}
+impl From<Option<Status>> for SgStatus {
+    fn from(status: Option<Status>) -> Self {
+        match status {
+            Some(Status::Live) => Self::Live,
+            Some(Status::Dead) => Self::Dead,
+            Some(Status::Unknown) => Self::Unknown,
+            Some(Status::Open) => Self::Open,
+            None => Self::Unseen,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SgProbability {
+    Zero = 0,
+    Positive = 1,
+    One = 2,
+    Unknown = 3,
+}
+impl From<Option<Probability>> for SgProbability {
+    fn from(probability: Option<Probability>) -> Self {
+        match probability {
+            Some(Probability::Zero) => Self::Zero,
+            Some(Probability::Positive) => Self::Positive,
+            Some(Probability::One) => Self::One,
+            None => Self::Unknown,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SgResult {
+    Ok = 0,
+    AlreadyClosed = 1,
+    SelfLoop = 2,
+    AlreadyLive = 3,
+    ContradictoryNotReachable = 4,
+    NullHandle = 5,
+    UniversalUnsupported = 6,
+    NotBuchi = 7,
+    MergeUnsupported = 8,
+    ContradictoryMerge = 9,
+    SubsumedUnsupported = 10,
+    ContradictorySubsumed = 11,
+    InternalError = 12,
+}
+impl From<Result<(), StateGraphError>> for SgResult {
+    fn from(result: Result<(), StateGraphError>) -> Self {
+        match result {
+            Ok(()) => Self::Ok,
+            Err(StateGraphError::AlreadyClosed(_)) => Self::AlreadyClosed,
+            Err(StateGraphError::SelfLoop(_)) => Self::SelfLoop,
+            Err(StateGraphError::AlreadyLive(_)) => Self::AlreadyLive,
+            Err(StateGraphError::ContradictoryNotReachable(_, _)) => {
+                Self::ContradictoryNotReachable
+            }
+            Err(StateGraphError::UniversalUnsupported(_)) => {
+                Self::UniversalUnsupported
+            }
+            Err(StateGraphError::NotBuchi(_)) => Self::NotBuchi,
+            Err(StateGraphError::MergeUnsupported(_, _)) => {
+                Self::MergeUnsupported
+            }
+            Err(StateGraphError::ContradictoryMerge(_, _)) => {
+                Self::ContradictoryMerge
+            }
+            Err(StateGraphError::SubsumedUnsupported(_, _)) => {
+                Self::SubsumedUnsupported
+            }
+            Err(StateGraphError::ContradictorySubsumed(_, _)) => {
+                Self::ContradictorySubsumed
+            }
+        }
+    }
+}
+
+/*
+    The handle: a type-erased StateGraph
+
+    (StateGraph itself isn't object safe, so wrap the parts we need.)
+*/
+
+trait DynStateGraph {
+    fn try_process(&mut self, t: Transaction) -> Result<(), StateGraphError>;
+    fn get_status(&self, v: usize) -> Option<Status>;
+    fn get_status_for(&self, v: usize, class: usize) -> Option<Status>;
+    fn get_probability(&self, v: usize) -> Option<Probability>;
+    fn is_reachable_from_initial(&self, v: usize) -> bool;
+}
+impl<G: StateGraph> DynStateGraph for G {
+    fn try_process(&mut self, t: Transaction) -> Result<(), StateGraphError> {
+        StateGraph::try_process(self, t)
+    }
+    fn get_status(&self, v: usize) -> Option<Status> {
+        StateGraph::get_status(self, v)
+    }
+    fn get_status_for(&self, v: usize, class: usize) -> Option<Status> {
+        StateGraph::get_status_for(self, v, class)
+    }
+    fn get_probability(&self, v: usize) -> Option<Probability> {
+        StateGraph::get_probability(self, v)
+    }
+    fn is_reachable_from_initial(&self, v: usize) -> bool {
+        StateGraph::is_reachable_from_initial(self, v)
+    }
+}
+
+// Opaque to C
+pub struct SgStateGraph(Box<dyn DynStateGraph>);
+
+// Safety: graph must be null or a live handle
+unsafe fn process(graph: *mut SgStateGraph, t: Transaction) -> SgResult {
+    match graph.as_mut() {
+        Some(graph) => catch_panic(|| graph.0.try_process(t).into())
+            .unwrap_or(SgResult::InternalError),
+        None => SgResult::NullHandle,
+    }
+}
+
+// Don't unwind into C; None if f panicked
+fn catch_panic<R>(f: impl FnOnce() -> R) -> Option<R> {
+    panic::catch_unwind(AssertUnwindSafe(f)).ok()
+}
+
+/*
+    Exported functions
+
+    Safety: every graph argument must be null or a handle returned by sg_new
+    that has not been freed.
+*/
+
+/// Create a new, empty state graph, using the given SgAlgorithm.
+/// Returns null if algorithm is out of range. Free with sg_free.
+#[no_mangle]
+pub extern "C" fn sg_new(algorithm: u32) -> *mut SgStateGraph {
+    let graph: Box<dyn DynStateGraph> = match algorithm {
+        0 => Box::new(NaiveStateGraph::new()),
+        1 => Box::new(SimpleStateGraph::new()),
+        2 => Box::new(TarjanStateGraph::new()),
+        3 => Box::new(JumpStateGraph::new()),
+        _ => return std::ptr::null_mut(),
+    };
+    Box::into_raw(Box::new(SgStateGraph(graph)))
+}
+
+/// Create a new, empty state graph in Büchi mode, using the given
+/// SgAlgorithm. Returns null if algorithm is out of range or doesn't support
+/// Büchi mode (Jump). Free with sg_free.
+#[no_mangle]
+pub extern "C" fn sg_new_buchi(algorithm: u32) -> *mut SgStateGraph {
+    let graph: Option<Box<dyn DynStateGraph>> = match algorithm {
+        0 => NaiveStateGraph::new_buchi().map(|g| Box::new(g) as _),
+        1 => SimpleStateGraph::new_buchi().map(|g| Box::new(g) as _),
+        2 => TarjanStateGraph::new_buchi().map(|g| Box::new(g) as _),
+        3 => JumpStateGraph::new_buchi().map(|g| Box::new(g) as _),
+        _ => None,
+    };
+    match graph {
+        Some(graph) => Box::into_raw(Box::new(SgStateGraph(graph))),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Create a new, empty state graph in Markov mode, using the given
+/// SgAlgorithm. Returns null if algorithm is out of range. Free with sg_free.
+#[no_mangle]
+pub extern "C" fn sg_new_markov(algorithm: u32) -> *mut SgStateGraph {
+    let graph: Box<dyn DynStateGraph> = match algorithm {
+        0 => Box::new(NaiveStateGraph::new_markov()),
+        1 => Box::new(SimpleStateGraph::new_markov()),
+        2 => Box::new(TarjanStateGraph::new_markov()),
+        3 => Box::new(JumpStateGraph::new_markov()),
+        _ => return std::ptr::null_mut(),
+    };
+    Box::into_raw(Box::new(SgStateGraph(graph)))
+}
+
+/// Free a state graph. Does nothing if graph is null.
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_free(graph: *mut SgStateGraph) {
+    if !graph.is_null() {
+        drop(Box::from_raw(graph));
+    }
+}
+
+/// Add a transition from an open state v1 to any state v2.
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_add_transition(
+    graph: *mut SgStateGraph,
+    v1: usize,
+    v2: usize,
+) -> SgResult {
+    process(graph, Transaction::Add(v1, v2))
+}
+
+/// Add a transition from an open state v1 to any state v2, labeled with
+/// label (e.g. a symbol ID).
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_add_labeled_transition(
+    graph: *mut SgStateGraph,
+    v1: usize,
+    v2: usize,
+    label: usize,
+) -> SgResult {
+    process(graph, Transaction::AddLabeled(v1, v2, label))
+}
+
+/// Mark an open state as closed.
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_mark_closed(
+    graph: *mut SgStateGraph,
+    v: usize,
+) -> SgResult {
+    process(graph, Transaction::Close(v))
+}
+
+/// Mark an open state as live.
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_mark_live(
+    graph: *mut SgStateGraph,
+    v: usize,
+) -> SgResult {
+    process(graph, Transaction::Live(v))
+}
+
+/// Mark a state as live for a target class (target 0 is the default class,
+/// as in sg_mark_live). The state must be open for the class.
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_mark_live_for(
+    graph: *mut SgStateGraph,
+    v: usize,
+    target: usize,
+) -> SgResult {
+    process(graph, Transaction::LiveFor(v, target))
+}
+
+/// Indicate that v2 is not reachable from v1.
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_not_reachable(
+    graph: *mut SgStateGraph,
+    v1: usize,
+    v2: usize,
+) -> SgResult {
+    process(graph, Transaction::NotReachable(v1, v2))
+}
+
+/// Declare two states equal: from then on they are one state. Not
+/// supported in Büchi or Markov mode, or for universal states.
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_merge(
+    graph: *mut SgStateGraph,
+    v1: usize,
+    v2: usize,
+) -> SgResult {
+    process(graph, Transaction::Merge(v1, v2))
+}
+
+/// Indicate the language of v1 is included in that of v2: if v1 is live
+/// then so is v2, and if v2 is dead then so is v1. Not supported in Markov
+/// mode.
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_subsumed(
+    graph: *mut SgStateGraph,
+    v1: usize,
+    v2: usize,
+) -> SgResult {
+    process(graph, Transaction::Subsumed(v1, v2))
+}
+
+/// Mark a state (open or not) as initial.
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_mark_initial(
+    graph: *mut SgStateGraph,
+    v: usize,
+) -> SgResult {
+    process(graph, Transaction::Initial(v))
+}
+
+/// Mark an open state as universal: live only if all of its successors are.
+/// Only supported by the Naive and Simple algorithms.
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_mark_universal(
+    graph: *mut SgStateGraph,
+    v: usize,
+) -> SgResult {
+    process(graph, Transaction::Universal(v))
+}
+
+/// Mark an open state as accepting (in Büchi mode only).
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_mark_accepting(
+    graph: *mut SgStateGraph,
+    v: usize,
+) -> SgResult {
+    process(graph, Transaction::Accepting(v))
+}
+
+/// Get the status of a state (SG_STATUS_UNSEEN if it has not been seen).
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_get_status(
+    graph: *const SgStateGraph,
+    v: usize,
+) -> SgStatus {
+    graph
+        .as_ref()
+        .and_then(|graph| catch_panic(|| graph.0.get_status(v)).flatten())
+        .into()
+}
+
+/// Get the status of a state for a target class (SG_STATUS_UNSEEN if it has
+/// not been seen).
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_get_status_for(
+    graph: *const SgStateGraph,
+    v: usize,
+    target: usize,
+) -> SgStatus {
+    graph
+        .as_ref()
+        .and_then(|graph| {
+            catch_panic(|| graph.0.get_status_for(v, target)).flatten()
+        })
+        .into()
+}
+
+/// Whether a state is reachable from an initial state (false if graph is
+/// null).
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_is_reachable_from_initial(
+    graph: *const SgStateGraph,
+    v: usize,
+) -> bool {
+    graph.as_ref().is_some_and(|graph| {
+        catch_panic(|| graph.0.is_reachable_from_initial(v)).unwrap_or(false)
+    })
+}
+
+/// In Markov mode, the probability that a state reaches a state marked live
+/// (SG_PROBABILITY_UNKNOWN if it is not known yet, or graph is null).
+///
+/// # Safety
+/// graph must be null or a handle from sg_new that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sg_get_probability(
+    graph: *const SgStateGraph,
+    v: usize,
+) -> SgProbability {
+    graph
+        .as_ref()
+        .and_then(|graph| catch_panic(|| graph.0.get_probability(v)).flatten())
+        .into()
+}
@@ -0,0 +1,724 @@
+/*
+    Differential fuzzing of the state graph algorithms.
+
+    - Generates random transaction sequences that are always valid: edges
+      are only added from open states, NotReachable hints are true in the
+      final graph, and closed states are only marked live until found Dead.
+    - Runs every algorithm on the sequence and compares what it observes of
+      each state (statuses for every target class, distances, witnesses,
+      and so on) against Naive after every transaction, not just at the
+      end, also processing in batches and with push/pop scopes.
+    - Shrinks failing sequences to a small example, which can then be saved
+      to examples/ like any other.
+*/
+
+use super::algorithm::{
+    JumpStateGraph, NaiveStateGraph, SimpleStateGraph, TarjanStateGraph,
+};
+use super::driver::Algorithm;
+use super::example::ExampleInput;
+use super::interface::{Probability, StateGraph, Status, Transaction};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/*
+    A small deterministic PRNG (SplitMix64), so that runs are reproducible
+    from a seed without any extra dependencies
+*/
+
+pub struct Rng(u64);
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    // Uniform in 0..n (n must be positive)
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % (n as u64)) as usize
+    }
+}
+
+/*
+    Random generation of valid transaction sequences
+
+    Picks a random final graph first (edges, and whether each state ends up
+    closed, live, closed and then live, or open), then interleaves the
+    transactions for each state in a random order, sprinkling in
+    NotReachable hints that hold in the final graph. A state closed before
+    it is marked live may be found Dead in between (if everything it
+    reaches is closed first), in which case the live mark is dropped.
+
+    gen_transactions also uses the rest of the interface: labeled
+    transitions, live marks for target classes, initial states and merges
+    (which count as transitions both ways for the NotReachable hints), and
+    Subsumed hints. gen_plain_transactions leaves those out, for tests that
+    add their own features on top (e.g. Büchi or Markov mode, which don't
+    support merges).
+*/
+
+// Labels and target classes (other than the default class 0) used
+pub const NUM_LABELS: usize = 3;
+pub const NUM_CLASSES: usize = 2;
+
+pub fn gen_transactions(rng: &mut Rng, num_states: usize) -> Vec<Transaction> {
+    let transactions = gen_with(rng, num_states, true);
+    gen_subsumed(rng, num_states, transactions)
+}
+
+pub fn gen_plain_transactions(
+    rng: &mut Rng,
+    num_states: usize,
+) -> Vec<Transaction> {
+    gen_with(rng, num_states, false)
+}
+
+fn gen_with(
+    rng: &mut Rng,
+    num_states: usize,
+    extended: bool,
+) -> Vec<Transaction> {
+    // Vary the shape of the graph from one sequence to the next
+    let max_degree = 1 + rng.below(3);
+    let pct_live = rng.below(20);
+    let pct_open = rng.below(30);
+    let pct_hint = rng.below(50);
+    let pct_late = rng.below(30);
+    // (Only drawn if extended, so that plain sequences stay the same)
+    let (pct_label, pct_live_for, pct_extra) = if extended {
+        (rng.below(50), rng.below(30), rng.below(30))
+    } else {
+        (0, 0, 0)
+    };
+
+    let mut succs: Vec<Vec<usize>> = vec![Vec::new(); num_states];
+    let mut events: Vec<VecDeque<Transaction>> = Vec::new();
+    for (v, v_succs) in succs.iter_mut().enumerate() {
+        let mut queue = VecDeque::new();
+        for _ in 0..rng.below(max_degree + 1) {
+            let w = rng.below(num_states);
+            if w != v && !v_succs.contains(&w) {
+                v_succs.push(w);
+                if extended && rng.below(100) < pct_label {
+                    let label = rng.below(NUM_LABELS);
+                    queue.push_back(Transaction::AddLabeled(v, w, label));
+                } else {
+                    queue.push_back(Transaction::Add(v, w));
+                }
+            }
+        }
+        if extended && rng.below(100) < pct_live_for {
+            let class = 1 + rng.below(NUM_CLASSES);
+            queue.push_back(Transaction::LiveFor(v, class));
+        }
+        let fate = rng.below(100);
+        if fate < pct_live {
+            queue.push_back(Transaction::Live(v));
+        } else if fate >= pct_live + pct_open {
+            queue.push_back(Transaction::Close(v));
+            if rng.below(100) < pct_late {
+                queue.push_back(Transaction::Live(v));
+            }
+        }
+        events.push(queue);
+    }
+    // Merges and initial states, at any time
+    let mut extras = Vec::new();
+    if extended {
+        for _ in 0..rng.below(3) {
+            let v1 = rng.below(num_states);
+            let v2 = rng.below(num_states);
+            if v1 != v2 {
+                succs[v1].push(v2);
+                succs[v2].push(v1);
+                extras.push(Transaction::Merge(v1, v2));
+            }
+        }
+        for _ in 0..rng.below(3) {
+            extras.push(Transaction::Initial(rng.below(num_states)));
+        }
+    }
+    let reachable: Vec<HashSet<usize>> =
+        (0..num_states).map(|v| reachable_from(&succs, v)).collect();
+
+    let mut result = Vec::new();
+    let mut pending: Vec<usize> =
+        (0..num_states).filter(|&v| !events[v].is_empty()).collect();
+    while !pending.is_empty() {
+        if rng.below(100) < pct_hint {
+            let v1 = rng.below(num_states);
+            let v2 = rng.below(num_states);
+            if !reachable[v1].contains(&v2) {
+                result.push(Transaction::NotReachable(v1, v2));
+            }
+        }
+        if !extras.is_empty() && rng.below(100) < pct_extra {
+            result.push(extras.swap_remove(rng.below(extras.len())));
+        }
+        let i = rng.below(pending.len());
+        let v = pending[i];
+        result.push(events[v].pop_front().unwrap());
+        if events[v].is_empty() {
+            pending.swap_remove(i);
+        }
+    }
+    result.extend(extras);
+    drop_invalid(result)
+}
+
+fn gen_subsumed(
+    rng: &mut Rng,
+    num_states: usize,
+    transactions: Vec<Transaction>,
+) -> Vec<Transaction> {
+    // Subsumed(v1, v2) holds if v2 ends up Live whenever v1 does. Pick
+    // such hints from the final graph and insert them anywhere; since they
+    // can make states Dead earlier, so that other transactions are
+    // dropped, keep them only if they still hold afterwards.
+    let live = final_live(&transactions);
+    let mut result = transactions.clone();
+    for _ in 0..rng.below(3) {
+        let v1 = rng.below(num_states);
+        let v2 = rng.below(num_states);
+        if v1 != v2 && (!live.contains(&v1) || live.contains(&v2)) {
+            let i = rng.below(result.len() + 1);
+            result.insert(i, Transaction::Subsumed(v1, v2));
+        }
+    }
+    let result = drop_invalid(result);
+    let without: Vec<Transaction> = result
+        .iter()
+        .copied()
+        .filter(|t| !matches!(t, Transaction::Subsumed(_, _)))
+        .collect();
+    let live = final_live(&without);
+    let hold = result.iter().all(|&t| match t {
+        Transaction::Subsumed(v1, v2) => {
+            !live.contains(&v1) || live.contains(&v2)
+        }
+        _ => true,
+    });
+    if hold {
+        result
+    } else {
+        transactions
+    }
+}
+
+fn final_live(transactions: &[Transaction]) -> HashSet<usize> {
+    let mut graph = NaiveStateGraph::new();
+    for &t in transactions {
+        graph.process(t);
+    }
+    graph.iter_status(Status::Live).collect()
+}
+
+// Drop the transactions Naive rejects (other than as redundant), e.g. live
+// marks on closed states found Dead first once the sequence is modified.
+// (In Büchi mode if there are accepting states, as in check.)
+pub fn drop_invalid(transactions: Vec<Transaction>) -> Vec<Transaction> {
+    let mut graph: NaiveStateGraph = new_graph(&transactions, false).unwrap();
+    transactions
+        .into_iter()
+        .filter(|&t| match graph.try_process(t) {
+            Ok(()) => true,
+            Err(err) => err.is_redundant(),
+        })
+        .collect()
+}
+
+fn reachable_from(succs: &[Vec<usize>], v: usize) -> HashSet<usize> {
+    // Includes v itself
+    let mut result = HashSet::new();
+    let mut stack = vec![v];
+    while let Some(u) = stack.pop() {
+        if result.insert(u) {
+            stack.extend(succs[u].iter().copied());
+        }
+    }
+    result
+}
+
+/*
+    Differential checking
+
+    Every algorithm is compared against Naive, processing the transactions
+    in each Mode:
+    - Step: one at a time, comparing after each
+    - Batch: with process_batch, in chunks of random size, comparing after
+      each chunk
+    - PushPop: one at a time, but before some of them, push, process a
+      random number of the following transactions (nesting further scopes),
+      pop and check that nothing changed, then carry on
+    The chunks and scopes are chosen by a fixed seed, so that each check is
+    deterministic (as shrinking needs).
+
+    Graphs are created as in the driver: in Büchi mode if there are
+    accepting states, and algorithms that can't run the sequence (Büchi
+    mode or universal states) are skipped. Markov mode isn't part of the
+    transactions, so check_markov checks in Markov mode instead.
+*/
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    Step,
+    Batch,
+    PushPop,
+}
+pub const MODES: &[Mode] = &[Mode::Step, Mode::Batch, Mode::PushPop];
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Step => write!(f, "step by step"),
+            Self::Batch => write!(f, "in batches"),
+            Self::PushPop => write!(f, "with push/pop"),
+        }
+    }
+}
+
+const MAX_BATCH: usize = 8;
+const MAX_DEPTH: usize = 2;
+
+const STATUSES: [Status; 4] =
+    [Status::Live, Status::Dead, Status::Unknown, Status::Open];
+
+fn new_graph<G: StateGraph>(
+    transactions: &[Transaction],
+    markov: bool,
+) -> Option<G> {
+    let input = ExampleInput(transactions.to_vec());
+    if input.is_alternating() && !G::ALTERNATING {
+        None
+    } else if input.is_buchi() {
+        G::new_buchi()
+    } else if markov {
+        Some(G::new_markov())
+    } else {
+        Some(G::new())
+    }
+}
+
+// What is compared for each state; unseen states count as Open
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Observation {
+    pub status: Status,
+    pub classes: [Status; NUM_CLASSES],
+    pub initial: bool,
+    pub distance: Option<usize>,
+    pub probability: Option<Probability>,
+    // Length of witness_live
+    pub witness: Option<usize>,
+    // Whether iter_status lists the state under its status (and nowhere
+    // else, or nowhere at all if unseen)
+    pub indexed: bool,
+}
+impl Observation {
+    fn of<G: StateGraph>(
+        graph: &G,
+        v: usize,
+        index: &HashMap<usize, Vec<Status>>,
+    ) -> Self {
+        let mut classes = [Status::Open; NUM_CLASSES];
+        for (i, status) in classes.iter_mut().enumerate() {
+            *status = graph.get_status_for(v, i + 1).unwrap_or(Status::Open);
+        }
+        let status = graph.get_status(v);
+        let indexed = match (status, index.get(&v)) {
+            (None, None) => true,
+            (Some(status), Some(listed)) => listed == &[status],
+            _ => false,
+        };
+        Self {
+            status: status.unwrap_or(Status::Open),
+            classes,
+            initial: graph.is_reachable_from_initial(v),
+            distance: graph.distance_to_live(v),
+            probability: graph.get_probability(v),
+            witness: graph.witness_live(v).map(|word| word.len()),
+            indexed,
+        }
+    }
+}
+
+// What is compared for the whole graph
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Summary {
+    // Whether count_status agrees with iter_status for every status
+    pub counted: bool,
+    pub initial_decided: bool,
+}
+impl Summary {
+    fn of<G: StateGraph>(graph: &G) -> Self {
+        let counted = STATUSES.iter().all(|&status| {
+            graph.count_status(status) == graph.iter_status(status).count()
+        });
+        Self { counted, initial_decided: graph.is_initial_decided() }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Failure {
+    // After processing the first `processed` transactions, algorithm
+    // disagrees with Naive on state
+    Mismatch {
+        mode: Mode,
+        processed: usize,
+        algorithm: Algorithm,
+        state: usize,
+        expected: Observation,
+        actual: Observation,
+    },
+    // Or on the whole graph
+    SummaryMismatch {
+        mode: Mode,
+        processed: usize,
+        algorithm: Algorithm,
+        expected: Summary,
+        actual: Summary,
+    },
+    // Algorithm panicked while processing transaction index (or the batch
+    // starting there)
+    Panic {
+        mode: Mode,
+        index: usize,
+        algorithm: Algorithm,
+        message: String,
+    },
+}
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Mismatch {
+                mode,
+                processed,
+                algorithm,
+                state,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "{}, after {} transactions, {} gives {:?} for state {} \
+                    (expected {:?})",
+                    mode, processed, algorithm, actual, state, expected
+                )
+            }
+            Self::SummaryMismatch {
+                mode,
+                processed,
+                algorithm,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "{}, after {} transactions, {} gives {:?} (expected {:?})",
+                    mode, processed, algorithm, actual, expected
+                )
+            }
+            Self::Panic { mode, index, algorithm, message } => write!(
+                f,
+                "{}, {} panicked on transaction {}: {}",
+                mode, algorithm, index, message
+            ),
+        }
+    }
+}
+
+// The observations after some number of transactions
+struct Checkpoint {
+    processed: usize,
+    observations: Vec<Observation>,
+    summary: Summary,
+}
+
+struct Run<'a, G> {
+    graph: G,
+    transactions: &'a [Transaction],
+    states: &'a [usize],
+    rng: Rng,
+    // Transaction being processed, to report panics
+    index: usize,
+    checkpoints: Vec<Checkpoint>,
+}
+impl<'a, G: StateGraph> Run<'a, G> {
+    fn observe(&mut self, processed: usize) {
+        let graph = &self.graph;
+        let mut index: HashMap<usize, Vec<Status>> = HashMap::new();
+        for &status in &STATUSES {
+            for v in graph.iter_status(status) {
+                index.entry(v).or_default().push(status);
+            }
+        }
+        let observations = self
+            .states
+            .iter()
+            .map(|&v| Observation::of(graph, v, &index))
+            .collect();
+        let summary = Summary::of(graph);
+        self.checkpoints.push(Checkpoint { processed, observations, summary });
+    }
+    fn run(&mut self, mode: Mode) {
+        self.observe(0);
+        let ts = self.transactions;
+        match mode {
+            Mode::Step => {
+                for (i, &t) in ts.iter().enumerate() {
+                    self.index = i;
+                    self.graph.process(t);
+                    self.observe(i + 1);
+                }
+            }
+            Mode::Batch => {
+                let mut i = 0;
+                while i < ts.len() {
+                    let j = (i + 1 + self.rng.below(MAX_BATCH)).min(ts.len());
+                    self.index = i;
+                    self.graph.process_batch(&ts[i..j]);
+                    self.observe(j);
+                    i = j;
+                }
+            }
+            Mode::PushPop => self.run_scoped(0, ts.len(), 0),
+        }
+    }
+    fn run_scoped(&mut self, from: usize, to: usize, depth: usize) {
+        for i in from..to {
+            if depth < MAX_DEPTH && self.rng.below(4) == 0 {
+                let j = i + 1 + self.rng.below(to - i);
+                self.graph.push();
+                self.run_scoped(i, j, depth + 1);
+                self.graph.pop();
+                self.observe(i);
+            }
+            self.index = i;
+            self.graph.process(self.transactions[i]);
+            self.observe(i + 1);
+        }
+    }
+}
+
+// None if the algorithm can't run the sequence
+fn run_checkpoints<G: StateGraph>(
+    algorithm: Algorithm,
+    mode: Mode,
+    transactions: &[Transaction],
+    states: &[usize],
+    markov: bool,
+) -> Result<Option<Vec<Checkpoint>>, Failure> {
+    let graph = match new_graph::<G>(transactions, markov) {
+        Some(graph) => graph,
+        None => return Ok(None),
+    };
+    let mut run = Run {
+        graph,
+        transactions,
+        states,
+        rng: Rng::new(0),
+        index: 0,
+        checkpoints: Vec::new(),
+    };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| run.run(mode)));
+    match result {
+        Ok(()) => Ok(Some(run.checkpoints)),
+        Err(err) => {
+            let message = err
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| err.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_default();
+            Err(Failure::Panic { mode, index: run.index, algorithm, message })
+        }
+    }
+}
+
+fn check_algorithm<G: StateGraph>(
+    algorithm: Algorithm,
+    mode: Mode,
+    transactions: &[Transaction],
+    states: &[usize],
+    markov: bool,
+    expected: &[Checkpoint],
+) -> Result<(), Failure> {
+    let actual = match run_checkpoints::<G>(
+        algorithm,
+        mode,
+        transactions,
+        states,
+        markov,
+    )? {
+        Some(actual) => actual,
+        None => return Ok(()),
+    };
+    for act in actual {
+        let processed = act.processed;
+        let exp = &expected[processed];
+        for (i, &state) in states.iter().enumerate() {
+            let (expected, actual) = (exp.observations[i], act.observations[i]);
+            if expected != actual {
+                return Err(Failure::Mismatch {
+                    mode,
+                    processed,
+                    algorithm,
+                    state,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        if exp.summary != act.summary {
+            return Err(Failure::SummaryMismatch {
+                mode,
+                processed,
+                algorithm,
+                expected: exp.summary,
+                actual: act.summary,
+            });
+        }
+    }
+    Ok(())
+}
+
+// Check that every algorithm agrees with Naive (processing step by step) in
+// the given mode. Returns the first failure found.
+pub fn check_mode(
+    transactions: &[Transaction],
+    mode: Mode,
+    markov: bool,
+) -> Result<(), Failure> {
+    let input = ExampleInput(transactions.to_vec());
+    let mut states: Vec<usize> = input.get_states().into_iter().collect();
+    states.sort_unstable();
+
+    let (ts, st) = (transactions, &states);
+    let naive = Algorithm::Naive;
+    let expected =
+        run_checkpoints::<NaiveStateGraph>(naive, Mode::Step, ts, st, markov)?
+            .expect("Naive runs every sequence");
+    let exp = &expected;
+    if mode != Mode::Step {
+        check_algorithm::<NaiveStateGraph>(naive, mode, ts, st, markov, exp)?;
+    }
+    let simple = Algorithm::Simple;
+    check_algorithm::<SimpleStateGraph>(simple, mode, ts, st, markov, exp)?;
+    let tarjan = Algorithm::Tarjan;
+    check_algorithm::<TarjanStateGraph>(tarjan, mode, ts, st, markov, exp)?;
+    let jump = Algorithm::Jump;
+    check_algorithm::<JumpStateGraph>(jump, mode, ts, st, markov, exp)?;
+    Ok(())
+}
+
+// Check every mode in turn
+pub fn check(transactions: &[Transaction]) -> Result<(), Failure> {
+    for &mode in MODES {
+        check_mode(transactions, mode, false)?;
+    }
+    Ok(())
+}
+pub fn check_markov(transactions: &[Transaction]) -> Result<(), Failure> {
+    for &mode in MODES {
+        check_mode(transactions, mode, true)?;
+    }
+    Ok(())
+}
+
+/*
+    Shrinking
+
+    Any subsequence of a valid sequence is still valid (states only stay
+    open longer, and hints only become more true), so we can shrink by
+    removing arbitrary chunks of transactions, as long as the result still
+    fails. The exceptions:
+    - A closed state marked live may be found Dead before that once other
+      transactions are removed; such live marks are dropped from each
+      candidate, as in gen_transactions.
+    - Subsumed hints may stop holding once the transactions making the
+      second state live are removed; a shrunk failure involving them
+      should be double-checked.
+*/
+
+pub fn shrink<F>(transactions: &[Transaction], mut fails: F) -> Vec<Transaction>
+where
+    F: FnMut(&[Transaction]) -> bool,
+{
+    debug_assert!(fails(transactions));
+    let mut current = transactions.to_vec();
+    loop {
+        let len_before = current.len();
+        let mut chunk = (current.len() / 2).max(1);
+        loop {
+            let mut i = 0;
+            while i < current.len() {
+                let mut candidate = current[..i].to_vec();
+                candidate.extend_from_slice(
+                    &current[(i + chunk).min(current.len())..],
+                );
+                let candidate = drop_invalid(candidate);
+                if fails(&candidate) {
+                    current = candidate;
+                } else {
+                    i += chunk;
+                }
+            }
+            if chunk == 1 {
+                break;
+            }
+            chunk /= 2;
+        }
+        if current.len() == len_before {
+            break;
+        }
+    }
+    // Finally, try renaming states to 0, 1, 2, ... in order of appearance
+    let renamed = rename_states(&current);
+    if fails(&renamed) {
+        renamed
+    } else {
+        current
+    }
+}
+
+fn rename_states(transactions: &[Transaction]) -> Vec<Transaction> {
+    let mut names = HashMap::new();
+    let mut rename = |v: usize| {
+        let next = names.len();
+        *names.entry(v).or_insert(next)
+    };
+    transactions
+        .iter()
+        .map(|&t| match t {
+            Transaction::Add(v1, v2) => {
+                let v1 = rename(v1);
+                Transaction::Add(v1, rename(v2))
+            }
+            Transaction::AddLabeled(v1, v2, l) => {
+                let v1 = rename(v1);
+                Transaction::AddLabeled(v1, rename(v2), l)
+            }
+            Transaction::Close(v) => Transaction::Close(rename(v)),
+            Transaction::Live(v) => Transaction::Live(rename(v)),
+            Transaction::Initial(v) => Transaction::Initial(rename(v)),
+            Transaction::Universal(v) => Transaction::Universal(rename(v)),
+            Transaction::Accepting(v) => Transaction::Accepting(rename(v)),
+            Transaction::LiveFor(v, c) => Transaction::LiveFor(rename(v), c),
+            Transaction::NotReachable(v1, v2) => {
+                let v1 = rename(v1);
+                Transaction::NotReachable(v1, rename(v2))
+            }
+            Transaction::Merge(v1, v2) => {
+                let v1 = rename(v1);
+                Transaction::Merge(v1, rename(v2))
+            }
+            Transaction::Subsumed(v1, v2) => {
+                let v1 = rename(v1);
+                Transaction::Subsumed(v1, rename(v2))
+            }
+        })
+        .collect()
+}
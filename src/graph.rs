@@ -4,30 +4,45 @@
 
     Supports:
     - Adding vertices with names of type V, labeled by type T
-    - Adding edges either in forward or backward direction.
+    - Adding edges either in forward or backward direction, each with an
+      optional edge label (e.g. a symbol ID).
       Although the forward and backward may correspond in some implementations,
       for some of the algorithms we want to support (jump and tarjan)
       it is more flexible to add them separately.
     - Merging vertices in O(1) time (the two vertex names are now aliases)
       (requires a merge function T x T -> T)
       Note: this is a simple graph. self-loops are ignored after a merge.
+    - Iterating through the aliases of a vertex (O(1) per alias)
     - Iterating through the edges at a vertex (O(1) per edge)
       Note: this iterates over original edges; currently doesn't
       support "cleaning" edges by removing duplicates and self-loops
       in case of merged vertices.
+      Edges are also indexed by their original endpoints, so it is also
+      possible to iterate over edges ignoring merges (O(1) per such edge, see
+      iter_bck_edges_unmerged).
     - Generic search functions: DFS forward and backward, or topological
       search backward. For more documentation on these, see search.rs.
+    - Optionally, indexing vertices by a key computed from their label (e.g.
+      a status), to iterate over all vertices with a given key in time
+      proportional to the output (see new_indexed)
+    - Push/pop scopes: pop undoes every change since the matching push,
+      including merges. Each change is undone in O(1), except that the
+      first change to a label in a scope saves a clone of it.
 
     If T implements Default, additionally supports "ensure" functionality
     (i.e. add a vertex default if it doesn't exist already).
 */
 
 use super::debug_counter::DebugCounter;
-use super::search::{DepthFirstSearch, TopologicalSearch};
-use disjoint_sets::UnionFind;
-use std::collections::{HashMap, LinkedList};
+use super::search::{self, DepthFirstSearch, TopologicalSearch};
+use super::union_find::{Union, UnionFind};
+use std::collections::{HashMap, HashSet, LinkedList};
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::iter;
+
+// Default bound on the number of vertices visited by is_reachable_bounded
+pub const REACHABLE_SEARCH_MAX: usize = 1000;
 
 // Newtypes to keep different types of ID straight
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
@@ -35,14 +50,61 @@ struct UniqueID(usize);
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
 struct CanonicalID(usize);
 
+// An edge between two original vertices (kept in the edge lists of the
+// canonical vertex of one end), with an optional label
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Edge {
+    source: UniqueID,
+    target: UniqueID,
+    label: Option<usize>,
+}
+
+// Changes that are recorded while inside a scope, in order to undo them
+#[derive(Debug)]
+enum Undo<T> {
+    AddVertex(UniqueID),
+    AddEdgeFwd(CanonicalID, UniqueID),
+    AddEdgeBck(CanonicalID, UniqueID),
+    Label(CanonicalID, T),
+    Merge {
+        union: Union,
+        old: CanonicalID,
+        new: CanonicalID,
+        old_label: T,
+        new_label: T,
+        fwd_len: usize,
+        bck_len: usize,
+        aliases_len: usize,
+    },
+}
+#[derive(Debug)]
+struct Scope {
+    trail_len: usize,
+    // Labels already saved in this scope
+    saved_labels: HashSet<CanonicalID>,
+}
+
 #[derive(Debug)]
 pub struct DiGraph<V, T> {
     vertex_ids: HashMap<V, UniqueID>,
     id_vertices: HashMap<UniqueID, V>,
-    id_find: UnionFind<usize>,
+    id_find: UnionFind,
     labels: HashMap<CanonicalID, T>,
-    fwd_edges: HashMap<CanonicalID, LinkedList<UniqueID>>,
-    bck_edges: HashMap<CanonicalID, LinkedList<UniqueID>>,
+    fwd_edges: HashMap<CanonicalID, LinkedList<Edge>>,
+    bck_edges: HashMap<CanonicalID, LinkedList<Edge>>,
+    aliases: HashMap<CanonicalID, LinkedList<UniqueID>>,
+    // The same edges by original source (fwd) and original target (bck),
+    // unaffected by merges
+    fwd_unmerged: HashMap<UniqueID, Vec<Edge>>,
+    bck_unmerged: HashMap<UniqueID, Vec<Edge>>,
+    // If indexed: canonical vertices by key, and the number of original
+    // vertices (counting aliases) for each key
+    index_key: Option<fn(&T) -> usize>,
+    index: Vec<HashSet<CanonicalID>>,
+    index_counts: Vec<usize>,
+    // Undo trail, only recorded while there is at least one scope
+    trail: Vec<Undo<T>>,
+    scopes: Vec<Scope>,
     // Debug mode statistics
     space: DebugCounter,
     time: DebugCounter,
@@ -58,15 +120,32 @@ impl<V, T> Default for DiGraph<V, T> {
             labels: Default::default(),
             fwd_edges: Default::default(),
             bck_edges: Default::default(),
+            aliases: Default::default(),
+            fwd_unmerged: Default::default(),
+            bck_unmerged: Default::default(),
+            index_key: None,
+            index: Default::default(),
+            index_counts: Default::default(),
+            trail: Default::default(),
+            scopes: Default::default(),
             space: Default::default(),
             time: Default::default(),
         }
     }
 }
+impl<V, T> DiGraph<V, T> {
+    // Without trait bounds on V, so that it can be used in Default impls
+    pub fn new_indexed(key: fn(&T) -> usize) -> Self {
+        // Index vertices by key(label). Labels should then only be changed
+        // in ways that change the key using overwrite_vertex, update_label,
+        // or merge, and not through get_label_mut.
+        Self { index_key: Some(key), ..Default::default() }
+    }
+}
 impl<V, T> DiGraph<V, T>
 where
     V: Copy + Clone + Debug + Eq + Hash + PartialEq,
-    T: Debug + PartialEq,
+    T: Clone + Debug + PartialEq,
 {
     /*
         Exposed API
@@ -84,13 +163,31 @@ where
     }
     pub fn get_label_mut(&mut self, v: V) -> Option<&mut T> {
         self.time.inc();
-        self.get_canon_id(v).and_then(move |id| self.labels.get_mut(&id))
+        let canon_id = self.get_canon_id(v)?;
+        self.save_label(canon_id);
+        self.labels.get_mut(&canon_id)
+    }
+    pub fn update_label<F>(&mut self, v: V, update_fun: F)
+    where
+        F: FnOnce(&mut T),
+    {
+        // Panics if v isn't seen
+        // Same as modifying get_label_mut, but keeps the index up to date
+        self.time.inc();
+        let canon_id = self.get_canon_id_unwrapped(v);
+        self.save_label(canon_id);
+        self.index_remove(canon_id);
+        update_fun(self.labels.get_mut(&canon_id).unwrap());
+        self.index_insert(canon_id);
     }
     pub fn overwrite_vertex(&mut self, v: V, label: T) {
         // overwrites if already seen
         if self.is_seen(v) {
             let canon_id = self.get_canon_id_unwrapped(v);
+            self.save_label(canon_id);
+            self.index_remove(canon_id);
             self.labels.insert(canon_id, label);
+            self.index_insert(canon_id);
             self.time.inc();
         } else {
             self.add_vertex_core(v, label);
@@ -123,19 +220,85 @@ where
         // Includes every original vertex even when merged
         self.vertex_ids.keys().copied()
     }
+    pub fn iter_aliases(&self, v: V) -> impl Iterator<Item = V> + '_ {
+        // Every original vertex merged with v, including v itself
+        assert!(self.is_seen(v));
+        let canon = self.get_canon_id_unwrapped(v);
+        self.aliases[&canon]
+            .iter()
+            .inspect(move |_| self.time.inc())
+            .map(move |id| self.id_vertices[id])
+    }
+    pub fn iter_indexed(&self, key: usize) -> impl Iterator<Item = V> + '_ {
+        // Every original vertex (including aliases) whose label has the key.
+        // Costs O(1) per vertex returned.
+        self.index
+            .get(key)
+            .into_iter()
+            .flatten()
+            .flat_map(move |canon| self.aliases[canon].iter())
+            .inspect(move |_| self.time.inc())
+            .map(move |id| self.id_vertices[id])
+    }
+    pub fn count_indexed(&self, key: usize) -> usize {
+        // Number of original vertices (including aliases) returned by
+        // iter_indexed
+        self.index_counts.get(key).copied().unwrap_or(0)
+    }
     pub fn iter_fwd_edges(&self, v: V) -> impl Iterator<Item = V> + '_ {
         // Note that when vertices are merged, edges aren't. So the same vertex
         // could appear more than once in the iterator; but iter_edges enforces
         // that self-loops are filtered out.
         assert!(self.is_seen(v));
-        self.iter_edges(v, &self.fwd_edges)
+        self.iter_edges(v, &self.fwd_edges, |e| e.target)
     }
     pub fn iter_bck_edges(&self, v: V) -> impl Iterator<Item = V> + '_ {
         // Note that when vertices are merged, edges aren't. So the same vertex
         // could appear more than once in the iterator; but iter_edges enforces
         // that self-loops are filtered out.
         assert!(self.is_seen(v));
-        self.iter_edges(v, &self.bck_edges)
+        self.iter_edges(v, &self.bck_edges, |e| e.source)
+    }
+    pub fn iter_bck_edges_unmerged(
+        &self,
+        v: V,
+    ) -> impl Iterator<Item = V> + '_ {
+        // Back edges into v as they were originally added, between original
+        // vertices rather than merged ones. Costs O(1) per back edge into v.
+        assert!(self.is_seen(v));
+        self.bck_unmerged[&self.vertex_ids[&v]]
+            .iter()
+            .inspect(move |_| self.time.inc())
+            .map(move |e| self.id_vertices[&e.source])
+    }
+    pub fn iter_fwd_edges_unmerged(
+        &self,
+        v: V,
+    ) -> impl Iterator<Item = V> + '_ {
+        // Forward edges out of v as they were originally added (see
+        // iter_bck_edges_unmerged).
+        assert!(self.is_seen(v));
+        self.fwd_unmerged[&self.vertex_ids[&v]]
+            .iter()
+            .inspect(move |_| self.time.inc())
+            .map(move |e| self.id_vertices[&e.target])
+    }
+    pub fn path_labels_unmerged(&self, path: &[V]) -> Vec<usize> {
+        // The edge labels along a path of original edges (e.g. from
+        // shortest_path_unmerged), skipping unlabeled edges. Where there are
+        // several edges between the same two vertices, prefers a labeled
+        // one. Costs O(1) per back edge into the vertices of the path.
+        path.windows(2)
+            .filter_map(|pair| {
+                let (source, target) = (pair[0], pair[1]);
+                let source_id = self.vertex_ids[&source];
+                self.bck_unmerged[&self.vertex_ids[&target]]
+                    .iter()
+                    .inspect(|_| self.time.inc())
+                    .filter(|e| e.source == source_id)
+                    .find_map(|e| e.label)
+            })
+            .collect()
     }
     pub fn merge_using<F>(&mut self, v1: V, v2: V, merge_fun: F)
     where
@@ -150,20 +313,37 @@ where
         let canon1 = self.get_canon_id_unwrapped(v1);
         let canon2 = self.get_canon_id_unwrapped(v2);
         if canon1 != canon2 {
-            self.id_find.union(canon1.0, canon2.0);
+            let union = self.id_find.union(canon1.0, canon2.0).unwrap();
             let new = CanonicalID(self.id_find.find(canon1.0));
             debug_assert_eq!(new.0, self.id_find.find(canon2.0));
             debug_assert!(new == canon1 || new == canon2);
             let old = if new == canon1 { canon2 } else { canon1 };
+            self.index_remove(old);
+            self.index_remove(new);
             // Merge labels using merge_fun
             let label1 = self.labels.remove(&old).unwrap();
             let label2 = self.labels.remove(&new).unwrap();
+            if !self.scopes.is_empty() {
+                self.trail.push(Undo::Merge {
+                    union,
+                    old,
+                    new,
+                    old_label: label1.clone(),
+                    new_label: label2.clone(),
+                    fwd_len: self.fwd_edges[&new].len(),
+                    bck_len: self.bck_edges[&new].len(),
+                    aliases_len: self.aliases[&new].len(),
+                });
+            }
             self.labels.insert(new, merge_fun(label1, label2));
             // Merge edges -- note the following are O(1)
             let mut old_fwd = self.fwd_edges.remove(&old).unwrap();
             let mut old_bck = self.bck_edges.remove(&old).unwrap();
+            let mut old_aliases = self.aliases.remove(&old).unwrap();
             self.fwd_edges.get_mut(&new).unwrap().append(&mut old_fwd);
             self.bck_edges.get_mut(&new).unwrap().append(&mut old_bck);
+            self.aliases.get_mut(&new).unwrap().append(&mut old_aliases);
+            self.index_insert(new);
         }
         // Could return new vertex here; for now we return nothing.
     }
@@ -207,6 +387,71 @@ where
             },
         )
     }
+    pub fn reachable_unmerged(&self, v: V) -> Vec<V> {
+        // All original vertices reachable from v, including v and its
+        // aliases.
+        // Precondition: v should be seen
+        let v = self.get_canon_vertex(v);
+        iter::once(v)
+            .chain(self.dfs_fwd(iter::once(v), |_| true))
+            .flat_map(|u| self.iter_aliases(u))
+            .collect()
+    }
+    pub fn is_reachable_bounded(
+        &self,
+        v: V,
+        w: V,
+        limit: usize,
+        include: impl (Fn(V) -> bool) + Clone,
+        complete: impl Fn(V) -> bool,
+    ) -> Option<bool> {
+        // Search forward from v for w, visiting at most 'limit' vertices
+        // and excluding vertices not satisfying 'include'.
+        // Returns Some(true) if w is found, and Some(false) if the search
+        // finishes and every vertex visited satisfies 'complete'. So the
+        // caller should ensure that excluded vertices can't reach w, and that
+        // complete vertices have all their fwd-edges in the graph for good.
+        // Otherwise (or if the limit is hit) returns None.
+        if self.is_same_vertex(v, w) {
+            return Some(true);
+        } else if !self.is_seen(v) {
+            return None;
+        }
+        let mut all_complete = true;
+        let v = self.get_canon_vertex(v);
+        let search = iter::once(v).chain(self.dfs_fwd(iter::once(v), include));
+        for (count, u) in search.enumerate() {
+            if self.is_same_vertex(u, w) {
+                return Some(true);
+            } else if count == limit {
+                return None;
+            }
+            all_complete &= complete(u);
+        }
+        if all_complete {
+            Some(false)
+        } else {
+            None
+        }
+    }
+    pub fn shortest_path_unmerged(
+        &self,
+        v: V,
+        targets: impl Iterator<Item = V>,
+        include: impl Fn(V) -> bool,
+    ) -> Option<Vec<V>> {
+        // Shortest path of original edges (between original vertices, rather
+        // than merged ones) from v to one of 'targets', through vertices
+        // satisfying 'include'. Returns the path including both endpoints.
+        // Searches backward from 'targets', so only needs back edges.
+        let include = &include;
+        let path = search::shortest_path(
+            targets.filter(|&w| self.is_seen(w) && include(w)),
+            |u| u == v,
+            |u| self.iter_bck_edges_unmerged(u).filter(move |&w| include(w)),
+        )?;
+        Some(path.into_iter().rev().collect())
+    }
     pub fn topo_search_bck<'a>(
         &'a self,
         candidate_starts: impl Iterator<Item = V> + 'a,
@@ -236,6 +481,109 @@ where
             },
         )
     }
+    pub fn and_or_search_bck(
+        &self,
+        sources: impl Iterator<Item = V>,
+        include: impl Fn(V) -> bool,
+        universal: impl Fn(V) -> bool,
+    ) -> HashSet<V> {
+        // Search backward from 'sources', NOT including 'sources', and
+        // excluding vertices not satisfying 'include' -- like dfs_bck,
+        // except that a 'universal' vertex is only visited once all of its
+        // forward vertices have been (whether included or not).
+        // The result is the least set of vertices closed under these rules.
+        // Precondition: everything in 'sources' should be seen
+        let mut visited: HashSet<V> =
+            sources.map(|v| self.get_canon_vertex(v)).collect();
+        let mut stack: Vec<V> = visited.iter().copied().collect();
+        let mut waiting: HashMap<V, HashSet<V>> = HashMap::new();
+        let mut result = HashSet::new();
+        while let Some(v) = stack.pop() {
+            for u in self.iter_bck_edges(v) {
+                if visited.contains(&u) || !include(u) {
+                    continue;
+                }
+                if universal(u) {
+                    let remaining = waiting.entry(u).or_insert_with(|| {
+                        self.iter_fwd_edges(u)
+                            .filter(|w| !visited.contains(w))
+                            .collect()
+                    });
+                    remaining.remove(&v);
+                    if !remaining.is_empty() {
+                        continue;
+                    }
+                }
+                visited.insert(u);
+                result.insert(u);
+                stack.push(u);
+            }
+        }
+        result
+    }
+    pub fn sure_search_bck_unmerged(
+        &self,
+        sources: impl Iterator<Item = V>,
+        candidate: impl Fn(V) -> bool,
+        sure: impl Fn(V) -> bool,
+    ) -> HashSet<V> {
+        // Search backward from 'sources', including 'sources', through
+        // 'candidate' vertices, and return the greatest set of the
+        // candidates found whose forward vertices are all either 'sure' or
+        // in the set. (In a Markov chain, if the sure vertices reach some
+        // target with probability 1, then so do those in the set.)
+        // Follows original edges between original vertices: vertices merged
+        // into one can differ, since paths through a 'sure' vertex stop there.
+        // Precondition: everything in 'sources' should be seen
+        let mut stack: Vec<V> = sources.collect();
+        let mut result: HashSet<V> =
+            stack.iter().copied().filter(|&v| candidate(v)).collect();
+        while let Some(v) = stack.pop() {
+            for u in self.iter_bck_edges_unmerged(v) {
+                if candidate(u) && result.insert(u) {
+                    stack.push(u);
+                }
+            }
+        }
+        // Remove the vertices with a forward vertex not sure or in the set,
+        // and then backward from them
+        let mut stack: Vec<V> = result
+            .iter()
+            .copied()
+            .filter(|&v| {
+                self.iter_fwd_edges_unmerged(v)
+                    .any(|w| !sure(w) && !result.contains(&w))
+            })
+            .collect();
+        for v in &stack {
+            result.remove(v);
+        }
+        while let Some(v) = stack.pop() {
+            for u in self.iter_bck_edges_unmerged(v) {
+                if result.remove(&u) {
+                    stack.push(u);
+                }
+            }
+        }
+        result
+    }
+
+    pub fn push(&mut self) {
+        let trail_len = self.trail.len();
+        let saved_labels = HashSet::new();
+        self.scopes.push(Scope { trail_len, saved_labels });
+    }
+    pub fn pop(&mut self) {
+        // Panics if there is no matching push
+        let scope = self.scopes.pop().expect("pop without matching push");
+        while self.trail.len() > scope.trail_len {
+            let undo = self.trail.pop().unwrap();
+            self.undo(undo);
+        }
+    }
+    pub fn num_scopes(&self) -> usize {
+        self.scopes.len()
+    }
 
     /*
         Debug mode statistics
@@ -263,46 +611,153 @@ where
         debug_assert!(!self.labels.contains_key(&canon_id));
         debug_assert!(!self.fwd_edges.contains_key(&canon_id));
         debug_assert!(!self.bck_edges.contains_key(&canon_id));
+        debug_assert!(!self.aliases.contains_key(&canon_id));
+        debug_assert!(!self.fwd_unmerged.contains_key(&unique_id));
+        debug_assert!(!self.bck_unmerged.contains_key(&unique_id));
         self.vertex_ids.insert(v, unique_id);
         self.id_vertices.insert(unique_id, v);
         self.labels.insert(canon_id, label);
         self.fwd_edges.insert(canon_id, LinkedList::new());
         self.bck_edges.insert(canon_id, LinkedList::new());
+        self.aliases.insert(canon_id, iter::once(unique_id).collect());
+        self.fwd_unmerged.insert(unique_id, Vec::new());
+        self.bck_unmerged.insert(unique_id, Vec::new());
+        self.index_insert(canon_id);
+        if let Some(scope) = self.scopes.last_mut() {
+            // No need to save the label, undo removes it entirely
+            scope.saved_labels.insert(canon_id);
+            self.trail.push(Undo::AddVertex(unique_id));
+        }
         self.time.inc();
         self.space.inc();
     }
-    fn add_edge_fwd_core(&mut self, v1: V, v2: V) {
+    fn add_edge_fwd_core(&mut self, v1: V, v2: V, label: Option<usize>) {
         // Add fwd-edge v1 -> v2
         // Precondition: v1 and v2 are seen
         debug_assert!(self.is_seen(v1));
         debug_assert!(self.is_seen(v2));
+        // Also between vertices already merged (see add_edge_bck_core)
         let canon1 = self.get_canon_id_unwrapped(v1);
-        let canon2 = self.get_canon_id_unwrapped(v2);
-        if canon1 != canon2 {
-            self.fwd_edges
-                .get_mut(&canon1)
-                .unwrap()
-                .push_back(UniqueID(canon2.0));
-            self.space.inc();
+        let edge = self.get_edge(v1, v2, label);
+        self.fwd_edges.get_mut(&canon1).unwrap().push_back(edge);
+        self.fwd_unmerged.get_mut(&edge.source).unwrap().push(edge);
+        if !self.scopes.is_empty() {
+            self.trail.push(Undo::AddEdgeFwd(canon1, edge.source));
         }
+        self.space.inc();
         self.time.inc();
     }
-    fn add_edge_bck_core(&mut self, v1: V, v2: V) {
+    fn add_edge_bck_core(&mut self, v1: V, v2: V, label: Option<usize>) {
         // Add back-edge v2 -> v1 corresponding to fwd-edge v1 -> v2
         // Precondition: v1 and v2 are seen
         debug_assert!(self.is_seen(v1));
         debug_assert!(self.is_seen(v2));
-        let canon1 = self.get_canon_id_unwrapped(v1);
+        // Also between vertices already merged: iter_edges filters those
+        // out, but the unmerged edges (e.g. for distances) still need them
         let canon2 = self.get_canon_id_unwrapped(v2);
-        if canon1 != canon2 {
-            self.bck_edges
-                .get_mut(&canon2)
-                .unwrap()
-                .push_back(UniqueID(canon1.0));
-            self.space.inc();
+        let edge = self.get_edge(v1, v2, label);
+        self.bck_edges.get_mut(&canon2).unwrap().push_back(edge);
+        self.bck_unmerged.get_mut(&edge.target).unwrap().push(edge);
+        if !self.scopes.is_empty() {
+            self.trail.push(Undo::AddEdgeBck(canon2, edge.target));
         }
+        self.space.inc();
         self.time.inc();
     }
+    fn save_label(&mut self, canon_id: CanonicalID) {
+        // Save the label before modifying it, if in a scope and not saved
+        // already. (Restoring the first saved copy is enough on pop, since
+        // undo is applied in reverse order.)
+        if let Some(scope) = self.scopes.last_mut() {
+            if scope.saved_labels.insert(canon_id) {
+                let label = self.labels[&canon_id].clone();
+                self.trail.push(Undo::Label(canon_id, label));
+            }
+        }
+    }
+    fn index_insert(&mut self, canon_id: CanonicalID) {
+        // Precondition: canon_id has a label and aliases, and isn't indexed
+        if let Some(key_fun) = self.index_key {
+            let key = key_fun(&self.labels[&canon_id]);
+            if self.index.len() <= key {
+                self.index.resize_with(key + 1, Default::default);
+                self.index_counts.resize(key + 1, 0);
+            }
+            let inserted = self.index[key].insert(canon_id);
+            debug_assert!(inserted);
+            self.index_counts[key] += self.aliases[&canon_id].len();
+        }
+    }
+    fn index_remove(&mut self, canon_id: CanonicalID) {
+        // Precondition: canon_id is indexed, with its current label and
+        // aliases
+        if let Some(key_fun) = self.index_key {
+            let key = key_fun(&self.labels[&canon_id]);
+            let removed = self.index[key].remove(&canon_id);
+            debug_assert!(removed);
+            self.index_counts[key] -= self.aliases[&canon_id].len();
+        }
+    }
+    fn undo(&mut self, undo: Undo<T>) {
+        self.time.inc();
+        match undo {
+            Undo::AddVertex(unique_id) => {
+                let canon_id = CanonicalID(unique_id.0);
+                self.index_remove(canon_id);
+                let v = self.id_vertices.remove(&unique_id).unwrap();
+                self.vertex_ids.remove(&v);
+                self.labels.remove(&canon_id);
+                self.fwd_edges.remove(&canon_id);
+                self.bck_edges.remove(&canon_id);
+                self.aliases.remove(&canon_id);
+                self.fwd_unmerged.remove(&unique_id);
+                self.bck_unmerged.remove(&unique_id);
+                debug_assert_eq!(unique_id.0 + 1, self.id_find.len());
+                self.id_find.undo_alloc();
+            }
+            Undo::AddEdgeFwd(canon_id, source) => {
+                self.fwd_edges.get_mut(&canon_id).unwrap().pop_back();
+                self.fwd_unmerged.get_mut(&source).unwrap().pop();
+            }
+            Undo::AddEdgeBck(canon_id, target) => {
+                self.bck_edges.get_mut(&canon_id).unwrap().pop_back();
+                self.bck_unmerged.get_mut(&target).unwrap().pop();
+            }
+            Undo::Label(canon_id, label) => {
+                self.index_remove(canon_id);
+                self.labels.insert(canon_id, label);
+                self.index_insert(canon_id);
+            }
+            Undo::Merge {
+                union,
+                old,
+                new,
+                old_label,
+                new_label,
+                fwd_len,
+                bck_len,
+                aliases_len,
+            } => {
+                self.index_remove(new);
+                self.id_find.undo_union(union);
+                debug_assert_eq!(self.id_find.find(new.0), new.0);
+                debug_assert_eq!(self.id_find.find(old.0), old.0);
+                self.labels.insert(old, old_label);
+                self.labels.insert(new, new_label);
+                let old_fwd = self.fwd_edges.get_mut(&new).unwrap();
+                let old_fwd = old_fwd.split_off(fwd_len);
+                self.fwd_edges.insert(old, old_fwd);
+                let old_bck = self.bck_edges.get_mut(&new).unwrap();
+                let old_bck = old_bck.split_off(bck_len);
+                self.bck_edges.insert(old, old_bck);
+                let old_aliases = self.aliases.get_mut(&new).unwrap();
+                let old_aliases = old_aliases.split_off(aliases_len);
+                self.aliases.insert(old, old_aliases);
+                self.index_insert(old);
+                self.index_insert(new);
+            }
+        }
+    }
     fn get_canon_id(&self, v: V) -> Option<CanonicalID> {
         self.vertex_ids
             .get(&v)
@@ -313,17 +768,22 @@ where
         let id = self.vertex_ids.get(&v).unwrap();
         CanonicalID(self.id_find.find(id.0))
     }
+    fn get_edge(&self, v1: V, v2: V, label: Option<usize>) -> Edge {
+        let source = self.vertex_ids[&v1];
+        Edge { source, target: self.vertex_ids[&v2], label }
+    }
     fn iter_edges<'a>(
         &'a self,
         v: V,
-        edges: &'a HashMap<CanonicalID, LinkedList<UniqueID>>,
+        edges: &'a HashMap<CanonicalID, LinkedList<Edge>>,
+        other_end: fn(&Edge) -> UniqueID,
     ) -> impl Iterator<Item = V> + 'a {
         self.time.inc();
         let canon = self.get_canon_id_unwrapped(v);
         edges[&canon]
             .iter()
             .inspect(move |_| self.time.inc())
-            .map(move |id| self.id_find.find(id.0))
+            .map(move |e| self.id_find.find(other_end(e).0))
             .filter(move |&id| id != canon.0)
             .map(move |id| self.id_vertices.get(&UniqueID(id)).unwrap())
             .copied()
@@ -336,7 +796,7 @@ where
 impl<V, T> DiGraph<V, T>
 where
     V: Copy + Clone + Debug + Eq + Hash + PartialEq,
-    T: Debug + Default + PartialEq,
+    T: Clone + Debug + Default + PartialEq,
 {
     pub fn ensure_vertex(&mut self, v: V) {
         // if not already seen, adds the default value
@@ -347,24 +807,24 @@ where
             self.add_vertex_core(v, Default::default());
         }
     }
-    pub fn ensure_edge_fwd(&mut self, v1: V, v2: V) {
+    pub fn ensure_edge_fwd(&mut self, v1: V, v2: V, label: Option<usize>) {
         // add a fwd-edge, ensuring the vertices exist first
         self.ensure_vertex(v1);
         self.ensure_vertex(v2);
-        self.add_edge_fwd_core(v1, v2);
+        self.add_edge_fwd_core(v1, v2, label);
     }
-    pub fn ensure_edge_bck(&mut self, v1: V, v2: V) {
+    pub fn ensure_edge_bck(&mut self, v1: V, v2: V, label: Option<usize>) {
         // add a bck-edge corresponding to fwd-edge from v1 to v2,
         // ensuring the vertices exist first
         self.ensure_vertex(v1);
         self.ensure_vertex(v2);
-        self.add_edge_bck_core(v1, v2);
+        self.add_edge_bck_core(v1, v2, label);
     }
-    pub fn ensure_edge(&mut self, v1: V, v2: V) {
+    pub fn ensure_edge(&mut self, v1: V, v2: V, label: Option<usize>) {
         // add an edge, ensuring the vertices exist first
         self.ensure_vertex(v1);
         self.ensure_vertex(v2);
-        self.add_edge_fwd_core(v1, v2);
-        self.add_edge_bck_core(v1, v2);
+        self.add_edge_fwd_core(v1, v2, label);
+        self.add_edge_bck_core(v1, v2, label);
     }
 }
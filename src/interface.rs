@@ -10,67 +10,388 @@
 */
 
 use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/*
+    State identifiers
+
+    Any hashable, copyable type can be used to name states, e.g. usize
+    (the default) or a solver's own interned term IDs.
+*/
+
+pub trait StateID: Copy + Clone + Debug + Eq + Hash + PartialEq {}
+impl<V> StateID for V where V: Copy + Clone + Debug + Eq + Hash + PartialEq {}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub enum Status {
     Live,
     Dead,
     Unknown,
+    #[default]
     Open,
 }
-impl Default for Status {
-    fn default() -> Self {
-        Status::Open
-    }
+
+// Existential states (the default) are live if some successor is live;
+// universal states are live only if they are closed and all of their
+// successors are live. Liveness is the least fixed point, so a cycle of
+// universal states is not live unless some state on it is marked live.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Kind {
+    #[default]
+    Existential,
+    Universal,
+}
+
+// In Markov mode, the probability that a closed state reaches a state
+// marked live, if it is known: Positive for Live states (One if it is known
+// to be 1), and Zero for Dead states.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Probability {
+    Zero,
+    Positive,
+    One,
+}
+
+// Notification that a state became Live or Dead.
+// Both are final, so each state is reported at most once.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StatusChange<V = usize> {
+    pub state: V,
+    pub status: Status,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub enum Transaction {
-    Add(usize, usize),
-    Close(usize),
-    Live(usize),
-    NotReachable(usize, usize),
+pub enum Transaction<V = usize> {
+    Add(V, V),
+    // A transition with a label (e.g. a symbol ID), used for witnesses
+    AddLabeled(V, V, usize),
+    Close(V),
+    Live(V),
+    NotReachable(V, V),
+    // An initial state (the root of the query), at any time
+    Initial(V),
+    // A universal state (see Kind), while it is still open
+    Universal(V),
+    // An accepting state, while it is still open (only in Büchi mode)
+    Accepting(V),
+    // A state that is live for a target class (see StateGraph::mark_live_for)
+    LiveFor(V, usize),
+    // Two states that are equal (see StateGraph::merge)
+    Merge(V, V),
+    // The language of the first state is included in that of the second
+    // (see StateGraph::subsumed)
+    Subsumed(V, V),
 }
 
+/*
+    Errors returned by the fallible (try_) versions of the interface
+
+    Some errors are only redundant: the input is legal but has no effect
+    (e.g. a self-loop, or closing a state that is already live). The checked
+    (non-try) versions silently ignore these, and assert against the rest.
+*/
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StateGraphError<V = usize> {
+    // The state (or source of a transition) is closed and not live
+    AlreadyClosed(V),
+    // A transition from a state to itself, outside of Büchi mode
+    SelfLoop(V),
+    // The state (or source of a transition) is already live (for every
+    // class in use, see StateGraph::is_live_for_all)
+    AlreadyLive(V),
+    // NotReachable(v1, v2) contradicts what is known about the graph
+    ContradictoryNotReachable(V, V),
+    // The implementation doesn't support universal states
+    UniversalUnsupported(V),
+    // An accepting state, but the graph is not in Büchi mode
+    NotBuchi(V),
+    // Merge isn't supported in Büchi or Markov mode, or for universal states
+    MergeUnsupported(V, V),
+    // Merge of a Dead state with a state that isn't, or of a state that can
+    // only reach closed states with one that can reach an open state
+    ContradictoryMerge(V, V),
+    // Subsumed isn't supported in Markov mode
+    SubsumedUnsupported(V, V),
+    // Subsumed(v1, v2) where v1 is Live and v2 is Dead
+    ContradictorySubsumed(V, V),
+}
+impl<V> StateGraphError<V> {
+    pub fn is_redundant(&self) -> bool {
+        matches!(self, Self::SelfLoop(_) | Self::AlreadyLive(_))
+    }
+    // Whether the transaction needs support the graph doesn't have, as
+    // opposed to contradicting what is known about the graph
+    pub fn is_unsupported(&self) -> bool {
+        matches!(
+            self,
+            Self::UniversalUnsupported(_)
+                | Self::NotBuchi(_)
+                | Self::MergeUnsupported(_, _)
+                | Self::SubsumedUnsupported(_, _)
+        )
+    }
+}
+impl<V: Debug> fmt::Display for StateGraphError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::AlreadyClosed(v) => {
+                write!(f, "state already closed: {:?}", v)
+            }
+            Self::SelfLoop(v) => write!(f, "self-loop transition: {:?}", v),
+            Self::AlreadyLive(v) => write!(f, "state already live: {:?}", v),
+            Self::ContradictoryNotReachable(v1, v2) => write!(
+                f,
+                "contradictory NotReachable: {:?} can reach {:?}",
+                v1, v2
+            ),
+            Self::UniversalUnsupported(v) => {
+                write!(f, "universal states not supported: {:?}", v)
+            }
+            Self::NotBuchi(v) => {
+                write!(f, "accepting state outside of Büchi mode: {:?}", v)
+            }
+            Self::MergeUnsupported(v1, v2) => {
+                write!(f, "merge not supported: {:?} and {:?}", v1, v2)
+            }
+            Self::ContradictoryMerge(v1, v2) => write!(
+                f,
+                "contradictory merge: only one of {:?} and {:?} is dead",
+                v1, v2
+            ),
+            Self::SubsumedUnsupported(v1, v2) => {
+                write!(f, "subsumption not supported: {:?} by {:?}", v1, v2)
+            }
+            Self::ContradictorySubsumed(v1, v2) => write!(
+                f,
+                "contradictory subsumption: live {:?} by dead {:?}",
+                v1, v2
+            ),
+        }
+    }
+}
+impl<V: Debug> Error for StateGraphError<V> {}
+
 /*
     The main interface
 */
 
-pub trait StateGraph: Sized {
+pub trait StateGraph<V: StateID = usize>: Sized {
     /*
         Functions that need to be implemented.
 
         For convenience, the main functions are unchecked:
-        - add_transition_unchecked can assume both its vertices are distinct
-          and that the source is Open.
-        - mark_closed_unchecked can assume that its vertex is Open.
-        - mark_live_unchecked can assume that its vertex is Open.
+        - add_labeled_transition_unchecked can assume both its vertices are
+          distinct (except in Büchi mode) and that the source is Open (or
+          Live and not yet closed, see can_add_from).
+        - mark_closed_unchecked can assume that its vertex is Open (or Live
+          and not yet closed, see can_add_from).
+        - mark_live_unchecked can assume that its vertex is Open or
+          Unknown.
         - not_reachable_unchecked can assume that the two vertices are distinct.
+        - mark_initial_unchecked can assume nothing: any state can be marked
+          initial at any time, and marking it again has no effect.
+        - mark_universal_unchecked can assume that its vertex is Open. Only
+          implementations with ALTERNATING true implement it; the default
+          reports UniversalUnsupported.
+        - mark_accepting_unchecked can assume that its vertex is Open, and
+          is only called in Büchi mode. Only implementations with
+          new_buchi implement it; the default reports NotBuchi.
+        - mark_live_for_unchecked can assume that the class is not the
+          default class, and that its vertex is Open for the class.
+        - merge_unchecked can assume that the two vertices are distinct, not
+          universal, not in Büchi or Markov mode, and that they are either
+          both Dead or neither is (and the same for only_reaches_closed).
+        - subsumed_unchecked can assume that the two vertices are distinct,
+          not in Markov mode, and that v1 isn't Live if v2 is Dead.
 
-        Derived checked versions are then provided as safer wrappers around
-        these.
+        Derived checked (and fallible, try_) versions are then provided as
+        safer wrappers around these.
     */
 
+    // Whether universal states are supported (see Kind)
+    const ALTERNATING: bool = false;
+
     // Constructor
     fn new() -> Self;
 
-    // Add a new transition to the graph from an Open state to any state.
+    // Constructor for Büchi mode: a state is Live once it can reach a
+    // cycle of closed states through an accepting state (or a state marked
+    // live), and Dead once it is closed and can't reach one, now or later.
+    // None if the implementation doesn't support Büchi mode (the default).
+    fn new_buchi() -> Option<Self> {
+        None
+    }
+    fn is_buchi(&self) -> bool;
+
+    // Constructor for Markov mode: each closed state moves to each of its
+    // successors with some positive probability. Then a closed state is
+    // Live iff it reaches a state marked live with positive probability,
+    // and it reaches one almost surely (with probability 1) iff every state
+    // it can reach without going through a state marked live is closed and
+    // Live. The answer depends on all the transitions from a state, so
+    // states that are found Live while still Open stay open: transitions
+    // can be added from them until they are closed.
+    // Not combined with Büchi mode or universal states.
+    fn new_markov() -> Self;
+    fn is_markov(&self) -> bool;
+
+    // Add a new transition to the graph from an Open state to any state,
+    // with an optional label.
     // (If the vertex doesn't exist yet, create it and mark it open.)
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize);
+    fn add_labeled_transition_unchecked(
+        &mut self,
+        v1: V,
+        v2: V,
+        label: Option<usize>,
+    );
 
     // Mark an open state as closed.
-    fn mark_closed_unchecked(&mut self, v: usize);
+    fn mark_closed_unchecked(&mut self, v: V);
 
-    // Mark an open state as live.
-    fn mark_live_unchecked(&mut self, v: usize);
+    // Mark an open state as live, or a closed state that is still Unknown
+    // (e.g. whether a state is accepting may only be determined after it
+    // is closed). A Dead state can't be: it can't reach a live state, now
+    // or later.
+    fn mark_live_unchecked(&mut self, v: V);
 
     // Indicate non-reachability between two nodes.
-    fn not_reachable_unchecked(&mut self, v1: usize, v2: usize);
+    fn not_reachable_unchecked(&mut self, v1: V, v2: V);
+
+    // Mark a state as initial. (If the vertex doesn't exist yet, create it
+    // and mark it open.)
+    fn mark_initial_unchecked(&mut self, v: V);
+
+    // Mark an open state as universal. (If the vertex doesn't exist yet,
+    // create it and mark it open.)
+    fn mark_universal_unchecked(
+        &mut self,
+        v: V,
+    ) -> Result<(), StateGraphError<V>> {
+        Err(StateGraphError::UniversalUnsupported(v))
+    }
+
+    // Mark an open state as accepting. (If the vertex doesn't exist yet,
+    // create it and mark it open.)
+    fn mark_accepting_unchecked(
+        &mut self,
+        v: V,
+    ) -> Result<(), StateGraphError<V>> {
+        Err(StateGraphError::NotBuchi(v))
+    }
+
+    // Target classes: a state can be marked live for any number of classes,
+    // identified by number, and has a status for each class. Class 0 is the
+    // default class, used by mark_live and get_status.
+    // The other classes only track reachability: a state is Live for a
+    // class if it can reach a state marked live for it, and Dead if it is
+    // closed and can only reach closed states. Universal and accepting
+    // states only affect the default class.
+    // All classes share the same graph, so states that are Live for the
+    // default class may still be Open, Unknown or Dead for another: adding
+    // transitions from them, or closing them, is only redundant once they
+    // are Live for every class in use (see is_live_for_all).
+    fn mark_live_for_unchecked(&mut self, v: V, class: usize);
+
+    // Declare two states equal, e.g. once a solver finds that they are the
+    // same term: from then on they are one state, with the transitions of
+    // both. It is closed once either was, Live if either was, and Dead only
+    // if both were. (If the vertices don't exist yet, create them.)
+    fn merge_unchecked(&mut self, v1: V, v2: V);
+
+    // Subsumption hint: every path from v1 to a state marked live can be
+    // matched by one from v2 (e.g. the language of v1 is included in that
+    // of v2), even if there is no transition between them. So v2 is Live
+    // once v1 is, as if marked live, and v1 is Dead once v2 is and v1 is
+    // closed, whatever v1 can still reach; then so is every state reachable
+    // from v1, once it is closed. The hint is trusted, like NotReachable.
+    // (If the vertices don't exist yet, create them.)
+    fn subsumed_unchecked(&mut self, v1: V, v2: V);
 
     // Return whether v is Open, or v is Closed but there is a path from
     // v to an Open state (Unknown), or there is no such path (Dead).
+    // (With universal states: Unknown if v would be Live were every Open
+    // state marked live, and Dead otherwise.)
     // If the state is not seen, return None.
-    fn get_status(&self, v: usize) -> Option<Status>;
+    fn get_status(&self, v: V) -> Option<Status>;
+    // The status for a class (see mark_live_for_unchecked)
+    fn get_status_for(&self, v: V, class: usize) -> Option<Status>;
+    // The classes other than the default in use: those some state was
+    // marked live for
+    fn iter_classes(&self) -> impl Iterator<Item = usize> + '_;
+    // Whether the state is closed and can only reach closed states: then
+    // it is Dead for every class other than the default it isn't Live for
+    fn only_reaches_closed(&self, v: V) -> bool;
+
+    // Whether the state was closed: Live states may or may not have been
+    fn is_marked_closed(&self, v: V) -> bool;
+
+    // In Markov mode, whether the state reaches a state marked live with
+    // probability 1 (including the states marked live). Always false
+    // outside of Markov mode.
+    fn is_almost_sure(&self, v: V) -> bool;
+
+    // Existential unless marked universal (including if not seen)
+    fn get_kind(&self, v: V) -> Kind;
+
+    // Iterate over the states with a given status, in time proportional to
+    // the number of states returned, or count them in O(1). As for
+    // get_status, states that are not seen are not included; every alias of
+    // a merged state is.
+    fn iter_status(&self, status: Status) -> impl Iterator<Item = V> + '_;
+    fn count_status(&self, status: Status) -> usize;
+
+    // Initial states, and whether v is reachable from one of them (through
+    // the transitions so far, including from open states). States that
+    // aren't reachable can't affect whether an initial state is Live.
+    fn iter_initial(&self) -> impl Iterator<Item = V> + '_;
+    fn is_reachable_from_initial(&self, v: V) -> bool;
+
+    // Explanations.
+    // If v is Live, return a path of transitions from v to a state that was
+    // marked live (including both ends). If v is Dead, return a certificate:
+    // the states reachable from v (including v), which are all closed and
+    // have no transitions leaving the set. Otherwise, return None.
+    // (With universal states or Subsumed hints, Dead states may reach Open
+    // ones, so only the explanations for Live states are checkable this
+    // way.)
+    fn explain_live(&self, v: V) -> Option<Vec<V>>;
+    fn explain_dead(&self, v: V) -> Option<Vec<V>>;
+
+    // Witness word: if v is Live, return the labels along a shortest path of
+    // transitions from v to a state that was marked live (the same path as
+    // explain_live), skipping unlabeled transitions. Otherwise, return None.
+    fn witness_live(&self, v: V) -> Option<Vec<usize>>;
+
+    // Distance to live: if v is Live, the number of transitions on a shortest
+    // path from v to a state that was marked live (0 if v was marked live).
+    // Otherwise, return None. Maintained incrementally, so this is O(1).
+    fn distance_to_live(&self, v: V) -> Option<usize>;
+
+    // Reachability between two states.
+    // Return Some(true) if there is a path of transitions from v to w
+    // (including when v and w are the same state), Some(false) if there
+    // never will be (given the transactions so far, including NotReachable
+    // hints, but not future merges), and None if this can't be determined
+    // yet. Naive is the reference; the other algorithms answer quickly using
+    // their own data structures and a bounded search, so they may return
+    // None more often.
+    fn is_reachable(&self, v: V, w: V) -> Option<bool>;
+
+    // Return the states that became Live or Dead since the last call, in the
+    // order they were found (including every alias of a merged state).
+    // Changes are buffered until drained; since each state is reported at
+    // most once, the buffer stays proportional to the graph.
+    fn drain_status_changes(&mut self) -> Vec<StatusChange<V>>;
+
+    // Push and pop scopes, for backtracking: pop undoes every transaction
+    // since the matching push, so get_status returns exactly what it did
+    // before the push. Undrained status changes since the push are
+    // discarded. pop panics if there is no matching push.
+    fn push(&mut self);
+    fn pop(&mut self);
 
     // Statistics -- only work in debug mode
     // space should be true memory, up to a constant, and time should be true
@@ -84,64 +405,314 @@ pub trait StateGraph: Sized {
         Derived (default) functions
     */
 
-    // The safe add_transition and mark_closed, etc. should generally be used
-    // over the unchecked versions as they validate that the sequence of
-    // inputs is correct.
-    // They also remove redundant additions that don't do anything, such
-    // as a self-loop edge or marking a live state closed.
-    fn add_transition(&mut self, v1: usize, v2: usize) {
-        debug_assert!(self.is_open(v1) || self.is_live(v1));
-        if self.is_open(v1) && v1 != v2 {
-            self.add_transition_unchecked(v1, v2);
+    fn add_transition_unchecked(&mut self, v1: V, v2: V) {
+        self.add_labeled_transition_unchecked(v1, v2, None);
+    }
+
+    // The fallible try_add_transition, try_mark_closed, etc. validate that
+    // the sequence of inputs is correct, and leave the graph unchanged if
+    // not. They also reject redundant additions that don't do anything, such
+    // as a self-loop edge or marking a live state closed; see
+    // StateGraphError::is_redundant.
+    fn try_add_transition(
+        &mut self,
+        v1: V,
+        v2: V,
+    ) -> Result<(), StateGraphError<V>> {
+        self.try_add_labeled_transition(v1, v2, None)
+    }
+    fn try_add_labeled_transition(
+        &mut self,
+        v1: V,
+        v2: V,
+        label: Option<usize>,
+    ) -> Result<(), StateGraphError<V>> {
+        if self.is_live_for_all(v1) && !self.is_markov() {
+            Err(StateGraphError::AlreadyLive(v1))
+        } else if !self.can_add_from(v1) {
+            Err(StateGraphError::AlreadyClosed(v1))
+        } else if v1 == v2 && !self.is_buchi() {
+            // (In Büchi mode, a self-loop is a cycle, see new_buchi)
+            Err(StateGraphError::SelfLoop(v1))
+        } else {
+            self.add_labeled_transition_unchecked(v1, v2, label);
+            Ok(())
         }
     }
-    fn mark_closed(&mut self, v: usize) {
-        debug_assert!(self.is_open(v) || self.is_live(v));
-        if self.is_open(v) {
+    fn try_mark_closed(&mut self, v: V) -> Result<(), StateGraphError<V>> {
+        if self.is_live_for_all(v) && !self.is_markov() {
+            Err(StateGraphError::AlreadyLive(v))
+        } else if !self.can_add_from(v) {
+            Err(StateGraphError::AlreadyClosed(v))
+        } else {
             self.mark_closed_unchecked(v);
+            Ok(())
         }
     }
-    fn mark_live(&mut self, v: usize) {
-        debug_assert!(self.is_open(v) || self.is_live(v));
-        if self.is_open(v) {
+    fn try_mark_live(&mut self, v: V) -> Result<(), StateGraphError<V>> {
+        if self.is_live(v) {
+            Err(StateGraphError::AlreadyLive(v))
+        } else if self.is_dead(v) {
+            Err(StateGraphError::AlreadyClosed(v))
+        } else {
             self.mark_live_unchecked(v);
+            Ok(())
+        }
+    }
+    fn try_not_reachable(
+        &mut self,
+        v1: V,
+        v2: V,
+    ) -> Result<(), StateGraphError<V>> {
+        // (Every algorithm finds at least a transition from v1 to v2, or v1
+        // and v2 merged; Naive finds any path)
+        if v1 == v2 || self.is_reachable(v1, v2) == Some(true) {
+            Err(StateGraphError::ContradictoryNotReachable(v1, v2))
+        } else {
+            self.not_reachable_unchecked(v1, v2);
+            Ok(())
+        }
+    }
+
+    fn try_mark_initial(&mut self, v: V) -> Result<(), StateGraphError<V>> {
+        self.mark_initial_unchecked(v);
+        Ok(())
+    }
+    fn try_mark_universal(&mut self, v: V) -> Result<(), StateGraphError<V>> {
+        if !Self::ALTERNATING {
+            Err(StateGraphError::UniversalUnsupported(v))
+        } else if self.is_live(v) {
+            Err(StateGraphError::AlreadyLive(v))
+        } else if self.is_closed(v) {
+            Err(StateGraphError::AlreadyClosed(v))
+        } else {
+            self.mark_universal_unchecked(v)
+        }
+    }
+    fn try_mark_accepting(&mut self, v: V) -> Result<(), StateGraphError<V>> {
+        if !self.is_buchi() {
+            Err(StateGraphError::NotBuchi(v))
+        } else if self.is_live(v) {
+            Err(StateGraphError::AlreadyLive(v))
+        } else if self.is_closed(v) {
+            Err(StateGraphError::AlreadyClosed(v))
+        } else {
+            self.mark_accepting_unchecked(v)
+        }
+    }
+    fn try_mark_live_for(
+        &mut self,
+        v: V,
+        class: usize,
+    ) -> Result<(), StateGraphError<V>> {
+        if class == 0 {
+            self.try_mark_live(v)
+        } else if self.is_live_for(v, class) {
+            Err(StateGraphError::AlreadyLive(v))
+        } else if self
+            .get_status_for(v, class)
+            .is_some_and(|st| st != Status::Open)
+        {
+            Err(StateGraphError::AlreadyClosed(v))
+        } else {
+            self.mark_live_for_unchecked(v, class);
+            Ok(())
+        }
+    }
+
+    fn try_merge(&mut self, v1: V, v2: V) -> Result<(), StateGraphError<V>> {
+        if v1 == v2 {
+            Ok(())
+        } else if self.is_buchi()
+            || self.is_markov()
+            || self.is_universal(v1)
+            || self.is_universal(v2)
+        {
+            Err(StateGraphError::MergeUnsupported(v1, v2))
+        } else if self.is_dead(v1) != self.is_dead(v2)
+            || self.only_reaches_closed(v1) != self.only_reaches_closed(v2)
+        {
+            Err(StateGraphError::ContradictoryMerge(v1, v2))
+        } else {
+            self.merge_unchecked(v1, v2);
+            Ok(())
         }
     }
-    fn not_reachable(&mut self, v1: usize, v2: usize) {
-        debug_assert!(v1 != v2);
-        self.not_reachable_unchecked(v1, v2);
+    fn try_subsumed(&mut self, v1: V, v2: V) -> Result<(), StateGraphError<V>> {
+        if v1 == v2 {
+            Ok(())
+        } else if self.is_markov() {
+            Err(StateGraphError::SubsumedUnsupported(v1, v2))
+        } else if self.is_live(v1) && self.is_dead(v2) {
+            Err(StateGraphError::ContradictorySubsumed(v1, v2))
+        } else {
+            self.subsumed_unchecked(v1, v2);
+            Ok(())
+        }
+    }
+
+    // The safe add_transition and mark_closed, etc. should generally be used
+    // over the unchecked versions. They ignore redundant inputs, and
+    // debug_assert that the rest of the sequence of inputs is correct
+    // (ignoring incorrect inputs in release mode).
+    fn add_transition(&mut self, v1: V, v2: V) {
+        ignore_redundant(self.try_add_transition(v1, v2));
+    }
+    fn add_labeled_transition(&mut self, v1: V, v2: V, label: usize) {
+        ignore_redundant(self.try_add_labeled_transition(v1, v2, Some(label)));
+    }
+    fn mark_closed(&mut self, v: V) {
+        ignore_redundant(self.try_mark_closed(v));
+    }
+    fn mark_live(&mut self, v: V) {
+        ignore_redundant(self.try_mark_live(v));
+    }
+    fn not_reachable(&mut self, v1: V, v2: V) {
+        ignore_redundant(self.try_not_reachable(v1, v2));
+    }
+    fn mark_initial(&mut self, v: V) {
+        ignore_redundant(self.try_mark_initial(v));
+    }
+    fn mark_universal(&mut self, v: V) {
+        ignore_redundant(self.try_mark_universal(v));
+    }
+    fn mark_accepting(&mut self, v: V) {
+        ignore_redundant(self.try_mark_accepting(v));
+    }
+    fn mark_live_for(&mut self, v: V, class: usize) {
+        ignore_redundant(self.try_mark_live_for(v, class));
+    }
+    fn merge(&mut self, v1: V, v2: V) {
+        ignore_redundant(self.try_merge(v1, v2));
+    }
+    fn subsumed(&mut self, v1: V, v2: V) {
+        ignore_redundant(self.try_subsumed(v1, v2));
     }
 
     // Some conveniences
-    fn is_seen(&self, v: usize) -> bool {
+    fn is_seen(&self, v: V) -> bool {
         self.get_status(v).is_some()
     }
-    fn is_live(&self, v: usize) -> bool {
+    fn is_live(&self, v: V) -> bool {
         self.get_status(v) == Some(Status::Live)
     }
-    fn is_dead(&self, v: usize) -> bool {
+    fn is_dead(&self, v: V) -> bool {
         self.get_status(v) == Some(Status::Dead)
     }
-    fn is_unknown(&self, v: usize) -> bool {
+    fn is_unknown(&self, v: V) -> bool {
         self.get_status(v) == Some(Status::Unknown)
     }
-    fn is_open(&self, v: usize) -> bool {
-        self.get_status(v).map_or(true, |st| st == Status::Open)
+    fn is_open(&self, v: V) -> bool {
+        self.get_status(v).is_none_or(|st| st == Status::Open)
     }
-    fn is_closed(&self, v: usize) -> bool {
+    fn is_closed(&self, v: V) -> bool {
         !self.is_open(v)
     }
-    fn is_u_or_d(&self, v: usize) -> bool {
+    fn is_u_or_d(&self, v: V) -> bool {
         self.is_dead(v) || self.is_unknown(v)
     }
+    fn is_universal(&self, v: V) -> bool {
+        self.get_kind(v) == Kind::Universal
+    }
+    // Whether the state is Live for the default class and every class in
+    // use: then transitions from it, or closing it, are redundant (except
+    // in Markov mode)
+    fn is_live_for_all(&self, v: V) -> bool {
+        self.is_live(v) && self.iter_classes().all(|c| self.is_live_for(v, c))
+    }
+    // Whether transitions can be added from the state: it is Open, or Live
+    // and not yet closed, in Markov mode or if it isn't Live for every class
+    fn can_add_from(&self, v: V) -> bool {
+        self.is_open(v)
+            || (self.is_live(v)
+                && !self.is_marked_closed(v)
+                && (self.is_markov() || !self.is_live_for_all(v)))
+    }
+    // In Markov mode (see new_markov), the probability of reaching a state
+    // marked live, if it is known. Only closed states (and the states
+    // marked live) have one.
+    fn get_probability(&self, v: V) -> Option<Probability> {
+        match self.get_status(v)? {
+            _ if self.is_almost_sure(v) => Some(Probability::One),
+            _ if self.can_add_from(v) => None,
+            Status::Live => Some(Probability::Positive),
+            Status::Dead => Some(Probability::Zero),
+            Status::Unknown | Status::Open => None,
+        }
+    }
+    fn is_live_for(&self, v: V, class: usize) -> bool {
+        self.get_status_for(v, class) == Some(Status::Live)
+    }
+    // Whether the query is answered: some initial state is Live, or there
+    // is at least one initial state and they are all Dead.
+    fn is_initial_decided(&self) -> bool {
+        let mut initial = self.iter_initial().peekable();
+        initial.peek().is_some()
+            && (self.iter_initial().any(|v| self.is_live(v))
+                || initial.all(|v| self.is_dead(v)))
+    }
 
     // Same as the above but using the Transaction enum
-    fn process(&mut self, t: Transaction) {
+    fn process(&mut self, t: Transaction<V>) {
         match t {
             Transaction::Add(v1, v2) => self.add_transition(v1, v2),
+            Transaction::AddLabeled(v1, v2, l) => {
+                self.add_labeled_transition(v1, v2, l)
+            }
             Transaction::Close(v1) => self.mark_closed(v1),
             Transaction::Live(v1) => self.mark_live(v1),
             Transaction::NotReachable(v1, v2) => self.not_reachable(v1, v2),
+            Transaction::Initial(v1) => self.mark_initial(v1),
+            Transaction::Universal(v1) => self.mark_universal(v1),
+            Transaction::Accepting(v1) => self.mark_accepting(v1),
+            Transaction::LiveFor(v1, c) => self.mark_live_for(v1, c),
+            Transaction::Merge(v1, v2) => self.merge(v1, v2),
+            Transaction::Subsumed(v1, v2) => self.subsumed(v1, v2),
+        }
+    }
+    // Process a batch of transactions. The result is the same as calling
+    // process on each in order, but algorithms may override this to share
+    // work across the batch (e.g. one dead state propagation pass at the
+    // end, rather than one per mark_closed).
+    fn process_batch(&mut self, ts: &[Transaction<V>]) {
+        for &t in ts {
+            self.process(t);
+        }
+    }
+    fn process_and_report(
+        &mut self,
+        t: Transaction<V>,
+    ) -> Vec<StatusChange<V>> {
+        self.process(t);
+        self.drain_status_changes()
+    }
+    fn try_process(
+        &mut self,
+        t: Transaction<V>,
+    ) -> Result<(), StateGraphError<V>> {
+        match t {
+            Transaction::Add(v1, v2) => self.try_add_transition(v1, v2),
+            Transaction::AddLabeled(v1, v2, l) => {
+                self.try_add_labeled_transition(v1, v2, Some(l))
+            }
+            Transaction::Close(v1) => self.try_mark_closed(v1),
+            Transaction::Live(v1) => self.try_mark_live(v1),
+            Transaction::NotReachable(v1, v2) => self.try_not_reachable(v1, v2),
+            Transaction::Initial(v1) => self.try_mark_initial(v1),
+            Transaction::Universal(v1) => self.try_mark_universal(v1),
+            Transaction::Accepting(v1) => self.try_mark_accepting(v1),
+            Transaction::LiveFor(v1, c) => self.try_mark_live_for(v1, c),
+            Transaction::Merge(v1, v2) => self.try_merge(v1, v2),
+            Transaction::Subsumed(v1, v2) => self.try_subsumed(v1, v2),
         }
     }
 }
+
+fn ignore_redundant<V: Debug>(result: Result<(), StateGraphError<V>>) {
+    // Unsupported transactions are reported in release builds too, rather
+    // than silently dropped
+    if let Err(err) = result {
+        assert!(!err.is_unsupported(), "{}", err);
+        debug_assert!(err.is_redundant(), "{}", err);
+    }
+}
@@ -1,9 +1,16 @@
 pub mod algorithm;
+pub mod binlog;
 pub mod constants;
 pub mod debug_counter;
 pub mod driver;
 pub mod example;
+pub mod ffi;
+pub mod fuzz;
 pub mod graph;
 pub mod interface;
+pub mod repl;
 pub mod search;
+pub mod status_log;
+pub mod union_find;
 pub mod util;
+pub mod validate;
@@ -0,0 +1,201 @@
+/*
+    A line protocol for driving a state graph interactively, e.g. over a
+    pipe from another process.
+
+    Each line of input is one command:
+        add V1 V2 [L]   add a transition, optionally labeled L
+        close V         mark a state closed
+        live V [C]      mark a state live, optionally for target class C
+        unreach V1 V2   indicate V2 is not reachable from V1
+        merge V1 V2     declare two states equal
+        subsumed V1 V2  indicate the language of V1 is included in V2's
+        init V          mark a state initial
+        univ V          mark an open state universal
+        accept V        mark an open state accepting (Büchi mode only)
+        status V [C]    query the status of a state, optionally for class C
+        dump            query the status of every state seen so far
+    Blank lines and lines starting with '#' are ignored.
+
+    Every other line gets exactly one line of response, so that a client
+    can read it back before sending the next command:
+    - "ok" for a transaction (including redundant ones, such as a self-loop);
+    - "live", "dead", "unknown", or "open" for status (unseen states are
+      reported as open);
+    - a JSON ExampleOutput on a single line for dump;
+    - "error: <message>" if the command can't be parsed or the transaction
+      is invalid (in which case the graph is unchanged).
+*/
+
+use super::example::ExampleOutput;
+use super::interface::{StateGraph, Status, Transaction};
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Command {
+    Process(Transaction),
+    Status(usize),
+    StatusFor(usize, usize),
+    Dump,
+}
+impl FromStr for Command {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let args = words
+            .iter()
+            .skip(1)
+            .map(|w| {
+                w.parse::<usize>()
+                    .map_err(|_| format!("could not parse as state: {}", w))
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
+        let cmd = words.first().copied().unwrap_or("");
+        match (cmd, args.as_slice()) {
+            ("add", &[v1, v2]) => Ok(Self::Process(Transaction::Add(v1, v2))),
+            ("add", &[v1, v2, l]) => {
+                Ok(Self::Process(Transaction::AddLabeled(v1, v2, l)))
+            }
+            ("close", &[v]) => Ok(Self::Process(Transaction::Close(v))),
+            ("live", &[v]) => Ok(Self::Process(Transaction::Live(v))),
+            ("live", &[v, c]) => Ok(Self::Process(Transaction::LiveFor(v, c))),
+            ("init", &[v]) => Ok(Self::Process(Transaction::Initial(v))),
+            ("univ", &[v]) => Ok(Self::Process(Transaction::Universal(v))),
+            ("accept", &[v]) => Ok(Self::Process(Transaction::Accepting(v))),
+            ("unreach", &[v1, v2]) => {
+                Ok(Self::Process(Transaction::NotReachable(v1, v2)))
+            }
+            ("merge", &[v1, v2]) => {
+                Ok(Self::Process(Transaction::Merge(v1, v2)))
+            }
+            ("subsumed", &[v1, v2]) => {
+                Ok(Self::Process(Transaction::Subsumed(v1, v2)))
+            }
+            ("status", &[v]) => Ok(Self::Status(v)),
+            ("status", &[v, c]) => Ok(Self::StatusFor(v, c)),
+            ("dump", &[]) => Ok(Self::Dump),
+            ("add", _) => Err(format!(
+                "expected 2 states and an optional label: {}",
+                s.trim()
+            )),
+            ("unreach", _) | ("merge", _) | ("subsumed", _) => {
+                Err(format!("expected 2 states: {}", s.trim()))
+            }
+            ("live", _) | ("status", _) => Err(format!(
+                "expected 1 state and an optional class: {}",
+                s.trim()
+            )),
+            ("close", _) | ("init", _) | ("univ", _) | ("accept", _) => {
+                Err(format!("expected 1 state: {}", s.trim()))
+            }
+            ("dump", _) => Err(format!("expected no arguments: {}", s.trim())),
+            _ => Err(format!("unknown command: {}", s.trim())),
+        }
+    }
+}
+
+fn status_str(status: Status) -> &'static str {
+    match status {
+        Status::Live => "live",
+        Status::Dead => "dead",
+        Status::Unknown => "unknown",
+        Status::Open => "open",
+    }
+}
+
+pub struct Repl<G: StateGraph> {
+    graph: G,
+    states: HashSet<usize>,
+}
+impl<G: StateGraph> Default for Repl<G> {
+    // Can't derive automatically because we don't want to assume G: Default
+    fn default() -> Self {
+        Self { graph: G::new(), states: HashSet::new() }
+    }
+}
+impl<G: StateGraph> Repl<G> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    // Respond to a single line of input; None if the line is ignored
+    pub fn respond(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let response = match line.parse::<Command>() {
+            Ok(cmd) => self.execute(cmd),
+            Err(err) => format!("error: {}", err),
+        };
+        Some(response)
+    }
+    pub fn execute(&mut self, cmd: Command) -> String {
+        match cmd {
+            Command::Process(t) => match self.graph.try_process(t) {
+                Err(err) if !err.is_redundant() => format!("error: {}", err),
+                _ => {
+                    self.see(t);
+                    "ok".to_string()
+                }
+            },
+            Command::Status(v) => {
+                status_str(self.graph.get_status(v).unwrap_or(Status::Open))
+                    .to_string()
+            }
+            Command::StatusFor(v, c) => status_str(
+                self.graph.get_status_for(v, c).unwrap_or(Status::Open),
+            )
+            .to_string(),
+            Command::Dump => {
+                let mut output = ExampleOutput::new();
+                for &v in &self.states {
+                    let status = self.graph.get_status(v);
+                    output.add(v, status.unwrap_or(Status::Open));
+                }
+                output.finalize();
+                serde_json::to_string(&output).unwrap()
+            }
+        }
+    }
+    fn see(&mut self, t: Transaction) {
+        match t {
+            Transaction::Add(v1, v2)
+            | Transaction::AddLabeled(v1, v2, _)
+            | Transaction::NotReachable(v1, v2)
+            | Transaction::Merge(v1, v2)
+            | Transaction::Subsumed(v1, v2) => {
+                self.states.insert(v1);
+                self.states.insert(v2);
+            }
+            Transaction::Close(v1)
+            | Transaction::Live(v1)
+            | Transaction::Initial(v1)
+            | Transaction::Universal(v1)
+            | Transaction::Accepting(v1)
+            | Transaction::LiveFor(v1, _) => {
+                self.states.insert(v1);
+            }
+        }
+    }
+
+    // Serve commands until the end of the input, flushing after each
+    // response.
+    pub fn run<R: BufRead, W: Write>(
+        &mut self,
+        reader: R,
+        mut writer: W,
+    ) -> io::Result<()> {
+        for line in reader.lines() {
+            if let Some(response) = self.respond(&line?) {
+                writeln!(writer, "{}", response)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -2,10 +2,11 @@
     Generic search functions
 */
 
-use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::iter::Peekable;
+use std::iter::{self, Peekable};
 
 /*
     Iterator for visiting items of type V in a DFS order.
@@ -147,7 +148,7 @@ where
         };
         while let Some(u) = iter_bck.peek() {
             // println!("[topsearch] peeking at {:?}", u);
-            if !self.visited.contains(&u) {
+            if !self.visited.contains(u) {
                 // println!("[topsearch] not ready to visit {:?}", v);
                 // println!("[topsearch] (must first visit {:?})", u);
                 return false;
@@ -200,3 +201,45 @@ where
         }
     }
 }
+
+/*
+    Shortest path search (BFS).
+
+    Given a set of source nodes 'start', a predicate 'is_end', and a
+    'next_nodes' function which is an abstract edge relation, returns a
+    shortest path from some node in 'start' to some node satisfying 'is_end'
+    (including both endpoints), or None if there is no such path.
+*/
+pub fn shortest_path<V, I, F, G>(
+    start: impl Iterator<Item = V>,
+    is_end: G,
+    next_nodes: F,
+) -> Option<Vec<V>>
+where
+    V: Copy + Debug + Eq + Hash + PartialEq,
+    I: Iterator<Item = V>,
+    F: Fn(V) -> I,
+    G: Fn(V) -> bool,
+{
+    // parent: None for start nodes
+    let mut parent: HashMap<V, Option<V>> = HashMap::new();
+    let mut frontier = VecDeque::new();
+    for v in start {
+        if parent.insert(v, None).is_none() {
+            frontier.push_back(v);
+        }
+    }
+    while let Some(v) = frontier.pop_front() {
+        if is_end(v) {
+            let path = iter::successors(Some(v), |u| parent[u]);
+            return Some(path.collect::<Vec<V>>().into_iter().rev().collect());
+        }
+        for w in next_nodes(v) {
+            if let Entry::Vacant(entry) = parent.entry(w) {
+                entry.insert(Some(v));
+                frontier.push_back(w);
+            }
+        }
+    }
+    None
+}
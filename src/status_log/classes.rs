@@ -0,0 +1,192 @@
+/*
+    Target classes other than the default (see StateGraph::mark_live_for):
+    the classes each state is live for, and the classes in use (marked live
+    for some state). Like the reachable set, each class only grows, so it is
+    maintained by a DFS backward from each state that becomes live for it.
+
+    Also the closed states that can only reach closed states, which are
+    Dead for every class they aren't live for. That set only grows as well:
+    transitions are only added from states that aren't closed. So it is
+    maintained as states are closed, by a DFS forward from each state that
+    may have joined it, looking for a state that isn't closed. To keep those
+    searches short, the states on the path to the state found remember it
+    (as their witness), and later searches stop at a state whose witness is
+    still not closed. Popping a scope forgets the witnesses, since the
+    transitions they were found through may be gone.
+*/
+
+use super::scoped::{ScopedMultiMap, ScopedSet};
+use crate::interface::{StateID, Status};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct Classes<V> {
+    classes: ScopedMultiMap<V, usize>,
+    used: ScopedSet<usize>,
+    dead: ScopedSet<V>,
+    witnesses: HashMap<V, V>,
+}
+impl<V> Default for Classes<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            classes: Default::default(),
+            used: Default::default(),
+            dead: Default::default(),
+            witnesses: Default::default(),
+        }
+    }
+}
+impl<V: StateID> Classes<V> {
+    pub fn is_live_for(&self, v: V, class: usize) -> bool {
+        self.classes.contains(v, class)
+    }
+    pub fn iter_classes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.used.iter()
+    }
+    pub fn mark_live_for<I>(
+        &mut self,
+        v: V,
+        class: usize,
+        preds: impl Fn(V) -> I,
+    ) where
+        I: Iterator<Item = V>,
+    {
+        // Mark v and every state that can reach it as live for class (DFS).
+        // preds(u) should return the predecessors of u over the original
+        // (unmerged) transitions.
+        self.used.insert(class);
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            if self.is_live_for(u, class) {
+                continue;
+            }
+            self.classes.insert(u, class);
+            stack.extend(preds(u).filter(|&p| !self.is_live_for(p, class)));
+        }
+    }
+    pub fn add_transition_for<I>(
+        &mut self,
+        v1: V,
+        v2: V,
+        preds: impl Fn(V) -> I,
+    ) where
+        I: Iterator<Item = V>,
+    {
+        // v1 is now live for every class that v2 is live for
+        let classes: Vec<usize> = self.classes.get(v2).collect();
+        for class in classes {
+            self.mark_live_for(v1, class, &preds);
+        }
+    }
+    pub fn close<I, J, K>(
+        &mut self,
+        v: V,
+        is_closed: impl Fn(V) -> bool,
+        succs: impl Fn(V) -> I,
+        preds: impl Fn(V) -> J,
+        aliases: impl Fn(V) -> K,
+    ) where
+        I: Iterator<Item = V>,
+        J: Iterator<Item = V>,
+        K: Iterator<Item = V>,
+    {
+        // v was just closed: it and the closed states that can reach it
+        // may now only reach closed states. Search forward from v, and from
+        // the predecessors of each state found to only reach closed states.
+        // succs(u) should return every successor of u, including those
+        // through transitions not yet added to the graph, preds(u) every
+        // predecessor, and aliases(u) every state merged with u (including
+        // u).
+        let mut todo = vec![v];
+        while let Some(u) = todo.pop() {
+            if !is_closed(u) || self.dead.contains(u) {
+                continue;
+            }
+            let Some(found) = self.search_not_closed(u, &is_closed, &succs)
+            else {
+                continue;
+            };
+            for w in found {
+                if self.dead.contains(w) {
+                    // (An alias of a state found before)
+                    continue;
+                }
+                for x in aliases(w) {
+                    self.dead.insert(x);
+                }
+                todo.extend(preds(w).filter(|&p| !self.dead.contains(p)));
+            }
+        }
+    }
+    fn search_not_closed<I>(
+        &mut self,
+        v: V,
+        is_closed: impl Fn(V) -> bool,
+        succs: impl Fn(V) -> I,
+    ) -> Option<Vec<V>>
+    where
+        I: Iterator<Item = V>,
+    {
+        // DFS forward from the closed state v, skipping the states known
+        // to only reach closed states. If it finds a state that isn't
+        // closed, directly or through a witness, record it as the witness
+        // of the states on the path to it and return None. Otherwise return
+        // the states visited, which only reach closed states.
+        let mut parents = HashMap::from([(v, v)]);
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            for w in succs(u) {
+                if self.dead.contains(w) {
+                    continue;
+                }
+                let witness = if is_closed(w) {
+                    self.witnesses.get(&w).copied().filter(|&x| !is_closed(x))
+                } else {
+                    Some(w)
+                };
+                if let Some(x) = witness {
+                    let mut u = u;
+                    self.witnesses.insert(u, x);
+                    while u != v {
+                        u = parents[&u];
+                        self.witnesses.insert(u, x);
+                    }
+                    return None;
+                }
+                if let Entry::Vacant(e) = parents.entry(w) {
+                    e.insert(u);
+                    stack.push(w);
+                }
+            }
+        }
+        Some(parents.into_keys().collect())
+    }
+    pub fn only_reaches_closed(&self, v: V) -> bool {
+        self.dead.contains(v)
+    }
+    pub fn status_for(&self, v: V, class: usize, is_closed: bool) -> Status {
+        // The status of a seen state v for a class other than the default
+        if self.is_live_for(v, class) {
+            Status::Live
+        } else if !is_closed {
+            Status::Open
+        } else if self.dead.contains(v) {
+            Status::Dead
+        } else {
+            Status::Unknown
+        }
+    }
+    pub fn push(&mut self) {
+        self.classes.push();
+        self.used.push();
+        self.dead.push();
+    }
+    pub fn pop(&mut self) {
+        self.classes.pop();
+        self.used.pop();
+        self.dead.pop();
+        self.witnesses.clear();
+    }
+}
@@ -0,0 +1,67 @@
+/*
+    NotReachable hints, which the searches for cycles use to skip states
+    that can't be on one. (Jump keeps its own, see jump.rs.)
+*/
+
+use super::scoped::ScopedMultiMap;
+use crate::graph::DiGraph;
+use crate::interface::StateID;
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+#[derive(Debug)]
+pub struct Hints<V> {
+    // For each state, the states known not to be reachable from it and the
+    // states known not to reach it
+    not_reachable: ScopedMultiMap<V, V>,
+    not_reaching: ScopedMultiMap<V, V>,
+}
+impl<V> Default for Hints<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            not_reachable: Default::default(),
+            not_reaching: Default::default(),
+        }
+    }
+}
+impl<V: StateID> Hints<V> {
+    pub fn add_not_reachable(&mut self, v1: V, v2: V) {
+        // v2 is not reachable from v1
+        self.not_reachable.insert(v1, v2);
+        self.not_reaching.insert(v2, v1);
+    }
+    pub fn has_not_reachable(&self) -> bool {
+        !self.not_reachable.is_empty()
+    }
+    pub fn iter_not_reachable(&self, v: V) -> impl Iterator<Item = V> + '_ {
+        self.not_reachable.get(v)
+    }
+    pub fn iter_not_reaching(&self, v: V) -> impl Iterator<Item = V> + '_ {
+        self.not_reaching.get(v)
+    }
+    pub fn not_reaching<T>(&self, graph: &DiGraph<V, T>, v: V) -> HashSet<V>
+    where
+        T: Clone + Debug + PartialEq,
+    {
+        // The (canonical) vertices of graph that the hints say can't reach
+        // v, or a state merged with it
+        if !self.has_not_reachable() {
+            return HashSet::new();
+        }
+        graph
+            .iter_aliases(v)
+            .flat_map(|u| self.iter_not_reaching(u))
+            .filter(|&u| graph.is_seen(u))
+            .map(|u| graph.get_canon_vertex(u))
+            .collect()
+    }
+    pub fn push(&mut self) {
+        self.not_reachable.push();
+        self.not_reaching.push();
+    }
+    pub fn pop(&mut self) {
+        self.not_reachable.pop();
+        self.not_reaching.pop();
+    }
+}
@@ -0,0 +1,79 @@
+/*
+    The kinds of states beyond existential ones: universal states (see
+    interface::Kind), and in Büchi mode, accepting states (see
+    StateGraph::new_buchi) and the states with a self-loop, which is a
+    cycle by itself.
+*/
+
+use super::scoped::ScopedSet;
+use crate::interface::{Kind, StateID};
+
+#[derive(Debug)]
+pub struct Kinds<V> {
+    universal: ScopedSet<V>,
+    buchi: bool,
+    accepting: ScopedSet<V>,
+    self_loops: ScopedSet<V>,
+}
+impl<V> Default for Kinds<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            universal: Default::default(),
+            buchi: false,
+            accepting: Default::default(),
+            self_loops: Default::default(),
+        }
+    }
+}
+impl<V: StateID> Kinds<V> {
+    pub fn new_buchi() -> Self {
+        Self { buchi: true, ..Default::default() }
+    }
+    pub fn mark_universal(&mut self, v: V) {
+        self.universal.insert(v);
+    }
+    pub fn is_universal(&self, v: V) -> bool {
+        self.universal.contains(v)
+    }
+    pub fn kind(&self, v: V) -> Kind {
+        if self.is_universal(v) {
+            Kind::Universal
+        } else {
+            Kind::Existential
+        }
+    }
+    pub fn has_universal(&self) -> bool {
+        !self.universal.is_empty()
+    }
+    pub fn is_buchi(&self) -> bool {
+        self.buchi
+    }
+    pub fn mark_accepting(&mut self, v: V) {
+        debug_assert!(self.buchi);
+        self.accepting.insert(v);
+    }
+    pub fn is_accepting(&self, v: V) -> bool {
+        self.accepting.contains(v)
+    }
+    pub fn iter_accepting(&self) -> impl Iterator<Item = V> + '_ {
+        self.accepting.iter()
+    }
+    pub fn mark_self_loop(&mut self, v: V) {
+        debug_assert!(self.buchi);
+        self.self_loops.insert(v);
+    }
+    pub fn has_self_loop(&self, v: V) -> bool {
+        self.self_loops.contains(v)
+    }
+    pub fn push(&mut self) {
+        self.universal.push();
+        self.accepting.push();
+        self.self_loops.push();
+    }
+    pub fn pop(&mut self) {
+        self.universal.pop();
+        self.accepting.pop();
+        self.self_loops.pop();
+    }
+}
@@ -0,0 +1,47 @@
+/*
+    Markov mode (see StateGraph::new_markov): whether the graph is in it,
+    and the states that reach a state marked live with probability 1.
+*/
+
+use super::scoped::ScopedSet;
+use super::StatusLog;
+use crate::interface::StateID;
+
+#[derive(Debug)]
+pub struct Markov<V> {
+    markov: bool,
+    almost_sure: ScopedSet<V>,
+}
+impl<V> Default for Markov<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self { markov: false, almost_sure: Default::default() }
+    }
+}
+impl<V: StateID> Markov<V> {
+    pub fn new_markov() -> Self {
+        Self { markov: true, ..Default::default() }
+    }
+    pub fn is_markov(&self) -> bool {
+        self.markov
+    }
+    pub fn is_almost_sure(&self, v: V) -> bool {
+        self.almost_sure.contains(v)
+    }
+    pub fn is_sure(&self, v: V, log: &StatusLog<V>) -> bool {
+        // Reaches a state marked live with probability 1: it is one (the
+        // only states at distance 0), or is almost surely live.
+        // Only tracked in Markov mode.
+        self.markov && (log.distance(v) == Some(0) || self.is_almost_sure(v))
+    }
+    pub fn mark_almost_sure(&mut self, v: V) {
+        debug_assert!(self.markov);
+        self.almost_sure.insert(v);
+    }
+    pub fn push(&mut self) {
+        self.almost_sure.push();
+    }
+    pub fn pop(&mut self) {
+        self.almost_sure.pop();
+    }
+}
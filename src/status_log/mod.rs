@@ -0,0 +1,186 @@
+/*
+    status_log
+
+    Bookkeeping shared by the implementations of the state graph interface,
+    split by concern. Each algorithm owns the parts it needs:
+    - StatusLog (below): status changes, live marks, closed states and
+      distances to live
+    - Reachability (reach.rs): initial states and the states reachable from
+      them
+    - Kinds (kinds.rs): universal states, and accepting states in Büchi mode
+    - Classes (classes.rs): the states live for each target class
+    - Markov (markov.rs): Markov mode and the almost surely live states
+    - Subsumption (subsumed.rs): Subsumed hints and the states they doom
+    - Hints (hints.rs): NotReachable hints
+
+    Every part supports push/pop scopes on its own, mostly through the
+    containers in scoped.rs; the algorithm pushes and pops each part it
+    owns.
+*/
+
+mod classes;
+mod hints;
+mod kinds;
+mod markov;
+mod reach;
+mod scoped;
+mod subsumed;
+
+pub use classes::Classes;
+pub use hints::Hints;
+pub use kinds::Kinds;
+pub use markov::Markov;
+pub use reach::Reachability;
+pub use subsumed::Subsumption;
+
+use super::interface::{StateID, Status, StatusChange};
+use scoped::ScopedSet;
+use std::collections::{HashMap, VecDeque};
+
+/*
+    The core log, which every algorithm owns:
+    - Buffer of StatusChange notifications, not yet drained
+    - The states that were marked live directly (as opposed to found live),
+      which are the endpoints of explanations for Live states
+    - The states that were marked closed (Live states may or may not be)
+    - For each Live state, its distance to live: the length of a shortest
+      path of transitions to a state marked live. Distances only decrease as
+      transitions and live marks are added, so they are maintained
+      incrementally by a BFS backward from the state whose distance
+      decreased, over the original (unmerged) transitions.
+
+    Popping a scope discards the undrained changes that were recorded since
+    the matching push (since pop undoes them), and restores the distances.
+*/
+
+// Lengths of the logs at the time of a push
+#[derive(Debug)]
+struct Scope {
+    changes: usize,
+    distances_trail: usize,
+}
+
+#[derive(Debug)]
+pub struct StatusLog<V> {
+    changes: Vec<StatusChange<V>>,
+    marked_live: ScopedSet<V>,
+    closed: ScopedSet<V>,
+    distances: HashMap<V, usize>,
+    // Previous distances, recorded while there is at least one scope
+    distances_trail: Vec<(V, Option<usize>)>,
+    scopes: Vec<Scope>,
+}
+impl<V> Default for StatusLog<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            changes: Default::default(),
+            marked_live: Default::default(),
+            closed: Default::default(),
+            distances: Default::default(),
+            distances_trail: Default::default(),
+            scopes: Default::default(),
+        }
+    }
+}
+impl<V: StateID> StatusLog<V> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn record(&mut self, states: impl Iterator<Item = V>, status: Status) {
+        self.changes.extend(states.map(|state| StatusChange { state, status }));
+    }
+    pub fn drain(&mut self) -> Vec<StatusChange<V>> {
+        // Every scope now starts after the remaining (no) changes
+        for scope in self.scopes.iter_mut() {
+            scope.changes = 0;
+        }
+        self.changes.drain(..).collect()
+    }
+    pub fn mark_live(&mut self, v: V) {
+        self.marked_live.insert(v);
+    }
+    pub fn iter_marked_live(&self) -> impl Iterator<Item = V> + '_ {
+        self.marked_live.iter()
+    }
+    pub fn mark_closed(&mut self, v: V) {
+        self.closed.insert(v);
+    }
+    pub fn is_closed(&self, v: V) -> bool {
+        self.closed.contains(v)
+    }
+    pub fn distance(&self, v: V) -> Option<usize> {
+        self.distances.get(&v).copied()
+    }
+    pub fn lower_distance<I>(
+        &mut self,
+        v: V,
+        dist: usize,
+        preds: impl Fn(V) -> I,
+        is_universal: impl Fn(V) -> bool,
+    ) -> Vec<V>
+    where
+        I: Iterator<Item = V>,
+    {
+        // Lower the distance of v to dist (if it is an improvement), then
+        // propagate to the predecessors of v in BFS order, so that each
+        // state is updated at most once.
+        // Returns the states that didn't have a distance before, i.e. the
+        // states that are newly Live.
+        // Universal predecessors without a distance are skipped: whether
+        // they are Live depends on all of their successors, so the caller
+        // checks them separately.
+        let mut new_live = Vec::new();
+        let mut queue = VecDeque::new();
+        if self.set_distance(v, dist, &mut new_live) {
+            queue.push_back((v, dist));
+        }
+        while let Some((u, dist)) = queue.pop_front() {
+            for p in preds(u) {
+                if is_universal(p) && self.distance(p).is_none() {
+                    continue;
+                }
+                if self.set_distance(p, dist + 1, &mut new_live) {
+                    queue.push_back((p, dist + 1));
+                }
+            }
+        }
+        new_live
+    }
+    pub fn push(&mut self) {
+        self.scopes.push(Scope {
+            changes: self.changes.len(),
+            distances_trail: self.distances_trail.len(),
+        });
+        self.marked_live.push();
+        self.closed.push();
+    }
+    pub fn pop(&mut self) {
+        let scope = self.scopes.pop().expect("pop without matching push");
+        self.changes.truncate(scope.changes);
+        let trail = self.distances_trail.drain(scope.distances_trail..);
+        for (v, dist) in trail.rev() {
+            match dist {
+                Some(dist) => self.distances.insert(v, dist),
+                None => self.distances.remove(&v),
+            };
+        }
+        self.marked_live.pop();
+        self.closed.pop();
+    }
+    fn set_distance(&mut self, v: V, dist: usize, new: &mut Vec<V>) -> bool {
+        // Returns whether the distance decreased
+        let old = self.distance(v);
+        if old.is_some_and(|old| old <= dist) {
+            return false;
+        }
+        if old.is_none() {
+            new.push(v);
+        }
+        if !self.scopes.is_empty() {
+            self.distances_trail.push((v, old));
+        }
+        self.distances.insert(v, dist);
+        true
+    }
+}
@@ -0,0 +1,65 @@
+/*
+    Initial states (see StateGraph::mark_initial_unchecked), and the states
+    reachable from them. The reachable set only grows, so it is maintained
+    incrementally by a DFS forward from each state that becomes reachable,
+    visiting each state once.
+*/
+
+use super::scoped::ScopedSet;
+use crate::interface::StateID;
+
+#[derive(Debug)]
+pub struct Reachability<V> {
+    initial: ScopedSet<V>,
+    reached: ScopedSet<V>,
+}
+impl<V> Default for Reachability<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self { initial: Default::default(), reached: Default::default() }
+    }
+}
+impl<V: StateID> Reachability<V> {
+    pub fn mark_initial(&mut self, v: V) {
+        self.initial.insert(v);
+    }
+    pub fn iter_initial(&self) -> impl Iterator<Item = V> + '_ {
+        self.initial.iter()
+    }
+    pub fn is_reached(&self, v: V) -> bool {
+        self.reached.contains(v)
+    }
+    pub fn reach<I, J>(
+        &mut self,
+        v: V,
+        succs: impl Fn(V) -> I,
+        aliases: impl Fn(V) -> J,
+    ) where
+        I: Iterator<Item = V>,
+        J: Iterator<Item = V>,
+    {
+        // Mark v and every state reachable from it as reached (DFS).
+        // succs(u) should return every successor of u, including those
+        // through transitions not yet added to the graph, and aliases(u)
+        // every state merged with u (including u).
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            if self.reached.contains(u) {
+                continue;
+            }
+            // States merged with u are reachable from each other
+            for w in aliases(u) {
+                self.reached.insert(w);
+            }
+            stack.extend(succs(u).filter(|&w| !self.reached.contains(w)));
+        }
+    }
+    pub fn push(&mut self) {
+        self.initial.push();
+        self.reached.push();
+    }
+    pub fn pop(&mut self) {
+        self.initial.pop();
+        self.reached.pop();
+    }
+}
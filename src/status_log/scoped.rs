@@ -0,0 +1,98 @@
+/*
+    Containers with push/pop scopes, for the bookkeeping in status_log.
+
+    Between scopes they only grow, so popping a scope removes exactly what
+    was added since the matching push (in reverse order).
+*/
+
+use crate::interface::StateID;
+use std::collections::{HashMap, HashSet};
+
+// A set that also remembers the order elements were added
+#[derive(Debug)]
+pub struct ScopedSet<V> {
+    set: HashSet<V>,
+    order: Vec<V>,
+    scopes: Vec<usize>,
+}
+impl<V> Default for ScopedSet<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            set: Default::default(),
+            order: Default::default(),
+            scopes: Default::default(),
+        }
+    }
+}
+impl<V: StateID> ScopedSet<V> {
+    // Returns whether v is new
+    pub fn insert(&mut self, v: V) -> bool {
+        let new = self.set.insert(v);
+        if new {
+            self.order.push(v);
+        }
+        new
+    }
+    pub fn contains(&self, v: V) -> bool {
+        self.set.contains(&v)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = V> + '_ {
+        self.order.iter().copied()
+    }
+    pub fn push(&mut self) {
+        self.scopes.push(self.order.len());
+    }
+    pub fn pop(&mut self) {
+        let len = self.scopes.pop().expect("pop without matching push");
+        for v in self.order.drain(len..) {
+            self.set.remove(&v);
+        }
+    }
+}
+
+// A map from each key to a list of values
+#[derive(Debug)]
+pub struct ScopedMultiMap<K, T> {
+    map: HashMap<K, Vec<T>>,
+    // The key of each value added, in order
+    order: Vec<K>,
+    scopes: Vec<usize>,
+}
+impl<K, T> Default for ScopedMultiMap<K, T> {
+    // Can't derive automatically because we don't want to assume K: Default
+    fn default() -> Self {
+        Self {
+            map: Default::default(),
+            order: Default::default(),
+            scopes: Default::default(),
+        }
+    }
+}
+impl<K: StateID, T: Copy + PartialEq> ScopedMultiMap<K, T> {
+    pub fn insert(&mut self, k: K, t: T) {
+        self.map.entry(k).or_default().push(t);
+        self.order.push(k);
+    }
+    pub fn get(&self, k: K) -> impl Iterator<Item = T> + '_ {
+        self.map.get(&k).into_iter().flatten().copied()
+    }
+    pub fn contains(&self, k: K, t: T) -> bool {
+        self.map.get(&k).is_some_and(|ts| ts.contains(&t))
+    }
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+    pub fn push(&mut self) {
+        self.scopes.push(self.order.len());
+    }
+    pub fn pop(&mut self) {
+        let len = self.scopes.pop().expect("pop without matching push");
+        for k in self.order.drain(len..).rev() {
+            self.map.get_mut(&k).unwrap().pop();
+        }
+    }
+}
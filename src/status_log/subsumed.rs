@@ -0,0 +1,101 @@
+/*
+    Subsumed hints (see StateGraph::subsumed_unchecked), and the states
+    reachable from a state found Dead by one (doomed). Like the reachable
+    set, the doomed set only grows, so it is maintained by a DFS forward
+    from each state that becomes doomed.
+
+    Also queues the states to check for being Dead by a hint, once closed:
+    those subsumed by a state since found Dead, and those newly doomed.
+    Popping a scope clears the queue.
+*/
+
+use super::scoped::{ScopedMultiMap, ScopedSet};
+use crate::interface::StateID;
+
+#[derive(Debug)]
+pub struct Subsumption<V> {
+    // For each state, the states subsuming it and the states it subsumes
+    supersets: ScopedMultiMap<V, V>,
+    subsets: ScopedMultiMap<V, V>,
+    doomed: ScopedSet<V>,
+    // States to check for being Dead by a hint, not yet checked
+    queue: Vec<V>,
+}
+impl<V> Default for Subsumption<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            supersets: Default::default(),
+            subsets: Default::default(),
+            doomed: Default::default(),
+            queue: Default::default(),
+        }
+    }
+}
+impl<V: StateID> Subsumption<V> {
+    pub fn subsume(&mut self, v1: V, v2: V) {
+        // v1 is subsumed by v2
+        self.supersets.insert(v1, v2);
+        self.subsets.insert(v2, v1);
+    }
+    pub fn has_subsumed(&self) -> bool {
+        !self.supersets.is_empty()
+    }
+    pub fn iter_supersets(&self, v: V) -> impl Iterator<Item = V> + '_ {
+        self.supersets.get(v)
+    }
+    pub fn record_dead(&mut self, states: impl Iterator<Item = V>) {
+        // The states subsumed by newly Dead states may be Dead too
+        if !self.has_subsumed() {
+            return;
+        }
+        for v in states {
+            self.queue.extend(self.subsets.get(v));
+        }
+    }
+    pub fn is_doomed(&self, v: V) -> bool {
+        self.doomed.contains(v)
+    }
+    pub fn doom<I, J>(
+        &mut self,
+        v: V,
+        succs: impl Fn(V) -> I,
+        aliases: impl Fn(V) -> J,
+    ) where
+        I: Iterator<Item = V>,
+        J: Iterator<Item = V>,
+    {
+        // Mark v and every state reachable from it as doomed (DFS), as in
+        // Reachability::reach, and queue the newly doomed states to be
+        // checked
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            if self.doomed.contains(u) {
+                continue;
+            }
+            for w in aliases(u) {
+                if self.doomed.insert(w) {
+                    self.queue.push(w);
+                }
+            }
+            stack.extend(succs(u).filter(|&w| !self.doomed.contains(w)));
+        }
+    }
+    pub fn push_queue(&mut self, v: V) {
+        self.queue.push(v);
+    }
+    pub fn pop_queue(&mut self) -> Option<V> {
+        self.queue.pop()
+    }
+    pub fn push(&mut self) {
+        self.supersets.push();
+        self.subsets.push();
+        self.doomed.push();
+    }
+    pub fn pop(&mut self) {
+        self.supersets.pop();
+        self.subsets.pop();
+        self.doomed.pop();
+        self.queue.clear();
+    }
+}
@@ -11,7 +11,7 @@ use std::io::{BufReader, BufWriter, Write};
 use std::path::Path;
 use std::time::{Duration, SystemTime};
 
-fn path_reader<P>(path: P) -> BufReader<File>
+pub fn path_reader<P>(path: P) -> BufReader<File>
 where
     P: AsRef<Path> + Debug,
 {
@@ -20,7 +20,7 @@ where
     }))
 }
 
-fn path_writer<P>(path: P) -> BufWriter<File>
+pub fn path_writer<P>(path: P) -> BufWriter<File>
 where
     P: AsRef<Path> + Debug,
 {
@@ -56,7 +56,7 @@ where
         panic!("Could not write JSON to {:?} -- {}", path, err)
     });
     writeln!(&mut writer).unwrap_or_else(|err| {
-        format!("Could not append newline to file: {:?} -- {}", &path, err);
+        panic!("Could not append newline to file: {:?} -- {}", &path, err)
     });
 }
 
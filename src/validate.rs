@@ -0,0 +1,205 @@
+/*
+    Validation of NotReachable hints
+
+    The implementations trust NotReachable hints (Simple, Tarjan and Jump
+    use them to cut their searches short), so a wrong hint can silently lead
+    to a wrong status.
+    HintValidator wraps a state graph and records every hint, along with
+    the states reachable from its source; as soon as the transitions seen so
+    far contain a path contradicting a hint, it returns an error naming the
+    hint and the path.
+
+    Like the states reachable from initial ones (see status_log::reach), the
+    states reachable from each hint source only grow, so they are
+    maintained by a DFS forward from each state that becomes reachable,
+    visiting each state once. Merged states are reachable from each other.
+    Meant for debugging a client: it keeps its own copy of the transitions,
+    and the cost grows with the number of distinct hint sources.
+*/
+
+use super::interface::{StateGraph, StateGraphError, StateID, Transaction};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Debug};
+
+// A hint NotReachable(v1, v2), and a path of transitions from v1 to v2
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HintViolation<V = usize> {
+    pub hint: (V, V),
+    pub path: Vec<V>,
+}
+impl<V: Debug> fmt::Display for HintViolation<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "NotReachable({:?}, {:?}) contradicted by path: {:?}",
+            self.hint.0, self.hint.1, self.path
+        )
+    }
+}
+impl<V: Debug> Error for HintViolation<V> {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValidationError<V = usize> {
+    // Returned by the wrapped graph
+    Graph(StateGraphError<V>),
+    // A hint contradicted by the transitions
+    Hint(HintViolation<V>),
+}
+impl<V> From<StateGraphError<V>> for ValidationError<V> {
+    fn from(err: StateGraphError<V>) -> Self {
+        Self::Graph(err)
+    }
+}
+impl<V: Debug> fmt::Display for ValidationError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Graph(err) => write!(f, "{}", err),
+            Self::Hint(err) => write!(f, "{}", err),
+        }
+    }
+}
+impl<V: Debug> Error for ValidationError<V> {}
+
+#[derive(Debug)]
+pub struct HintValidator<G, V = usize> {
+    graph: G,
+    // Transitions seen so far (merges count as transitions both ways)
+    succs: HashMap<V, Vec<V>>,
+    // Hint sources in order, and the states claimed not reachable from each
+    sources: Vec<V>,
+    hints: HashMap<V, Vec<V>>,
+    // For each hint source, the states reachable from it, each with the
+    // previous state on a path from the source
+    reached: HashMap<V, HashMap<V, V>>,
+}
+impl<G, V: StateID> HintValidator<G, V>
+where
+    G: StateGraph<V>,
+{
+    pub fn new(graph: G) -> Self {
+        Self {
+            graph,
+            succs: Default::default(),
+            sources: Default::default(),
+            hints: Default::default(),
+            reached: Default::default(),
+        }
+    }
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+    pub fn into_inner(self) -> G {
+        self.graph
+    }
+
+    // Process a transaction on the wrapped graph, checking the hints.
+    // A contradictory hint is returned as an error without being processed
+    // (so the graph is unchanged), and so is one of the graph's errors;
+    // a transition is processed before returning the hint it contradicts,
+    // since it is part of the graph even if the hint isn't.
+    // (Transitions redundant for the graph, e.g. from Live states, are
+    // still checked.)
+    pub fn try_process(
+        &mut self,
+        t: Transaction<V>,
+    ) -> Result<(), ValidationError<V>> {
+        match t {
+            Transaction::NotReachable(v1, v2) => {
+                let reached = self.reach_from(v1);
+                if reached.contains_key(&v2) {
+                    let path = trace_path(&reached, v1, v2);
+                    let hint = (v1, v2);
+                    return Err(ValidationError::Hint(HintViolation {
+                        hint,
+                        path,
+                    }));
+                }
+                self.graph.try_process(t)?;
+                if !self.reached.contains_key(&v1) {
+                    self.sources.push(v1);
+                    self.reached.insert(v1, reached);
+                }
+                self.hints.entry(v1).or_default().push(v2);
+                Ok(())
+            }
+            Transaction::Add(v1, v2) | Transaction::AddLabeled(v1, v2, _) => {
+                let result = self.graph.try_process(t);
+                match result {
+                    Err(err) if !err.is_redundant() => (),
+                    _ => self.add_edge(v1, v2)?,
+                }
+                Ok(result?)
+            }
+            Transaction::Merge(v1, v2) => {
+                self.graph.try_process(t)?;
+                let result = self.add_edge(v1, v2);
+                self.add_edge(v2, v1).and(result)
+            }
+            _ => Ok(self.graph.try_process(t)?),
+        }
+    }
+
+    fn reach_from(&self, v: V) -> HashMap<V, V> {
+        // States reachable from v, each with its previous state on a path
+        let mut reached = HashMap::from([(v, v)]);
+        self.extend_reached(&mut reached, v);
+        reached
+    }
+    fn extend_reached(&self, reached: &mut HashMap<V, V>, v: V) -> Vec<V> {
+        // DFS from v (already reached), skipping the states reached before.
+        // Returns the states newly reached.
+        let mut result = Vec::new();
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            for &w in self.succs.get(&u).into_iter().flatten() {
+                if let Entry::Vacant(entry) = reached.entry(w) {
+                    entry.insert(u);
+                    result.push(w);
+                    stack.push(w);
+                }
+            }
+        }
+        result
+    }
+    fn add_edge(&mut self, v1: V, v2: V) -> Result<(), ValidationError<V>> {
+        // Returns the first hint contradicted by the new edge, if any, after
+        // updating the states reachable from every source.
+        // (So each hint is reported at most once.)
+        self.succs.entry(v1).or_default().push(v2);
+        let mut violation = None;
+        for &source in &self.sources {
+            let mut reached = self.reached.remove(&source).unwrap();
+            if reached.contains_key(&v1) && !reached.contains_key(&v2) {
+                reached.insert(v2, v1);
+                let mut new = self.extend_reached(&mut reached, v2);
+                new.push(v2);
+                let targets = &self.hints[&source];
+                if let Some(&target) = new.iter().find(|w| targets.contains(w))
+                {
+                    let path = trace_path(&reached, source, target);
+                    let hint = (source, target);
+                    violation.get_or_insert(HintViolation { hint, path });
+                }
+            }
+            self.reached.insert(source, reached);
+        }
+        match violation {
+            Some(violation) => Err(ValidationError::Hint(violation)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn trace_path<V: StateID>(reached: &HashMap<V, V>, v1: V, v2: V) -> Vec<V> {
+    // Path from v1 to v2, following the previous states back from v2
+    let mut path = vec![v2];
+    let mut u = v2;
+    while u != v1 {
+        u = reached[&u];
+        path.push(u);
+    }
+    path.reverse();
+    path
+}
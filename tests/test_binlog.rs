@@ -0,0 +1,109 @@
+/*
+    Test the compact binary format for example inputs, against the JSON
+    examples in examples/.
+*/
+
+use state_graph::algorithm::JumpStateGraph;
+use state_graph::binlog::TransactionReader;
+use state_graph::constants::*;
+use state_graph::driver;
+use state_graph::example::{Example, ExampleInput, InputFormat};
+use state_graph::interface::{StateGraph, Transaction};
+use std::fs;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+fn to_bytes(input: &ExampleInput) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    input.write_binary(&mut bytes).unwrap();
+    bytes
+}
+
+fn check_round_trip(basename: &str) {
+    let example = Example::load_from(basename);
+    let bytes = to_bytes(&example.input);
+    let input = ExampleInput::read_binary(bytes.as_slice()).unwrap();
+    assert_eq!(input.0, example.input.0);
+}
+
+#[test]
+fn test_round_trip() {
+    let mut basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "loop_10000"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "nestedloop2_sat"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_DATE, "date_minimal_sat"));
+    for basename in &basenames {
+        check_round_trip(basename);
+    }
+}
+
+#[test]
+fn test_large_ids() {
+    let input = ExampleInput(vec![
+        Transaction::Add(0, usize::MAX),
+        Transaction::AddLabeled(usize::MAX, 3, 1 << 20),
+        Transaction::NotReachable(127, 128),
+        Transaction::Live(1 << 40),
+        Transaction::Close(0),
+    ]);
+    let bytes = to_bytes(&input);
+    assert_eq!(ExampleInput::read_binary(bytes.as_slice()).unwrap().0, input.0);
+}
+
+#[test]
+fn test_compact() {
+    let basename = format!("{}/{}", EX_DIR_GENERATED, "line_10000");
+    let example = Example::load_from(&basename);
+    let json = fs::read(format!("{}{}", basename, EXAMPLE_IN_EXT)).unwrap();
+    assert!(to_bytes(&example.input).len() * 5 < json.len());
+}
+
+#[test]
+fn test_malformed() {
+    let input = ExampleInput(vec![Transaction::Add(1000, 2000)]);
+    let bytes = to_bytes(&input);
+
+    // Truncated in the middle of a record
+    let mut reader = TransactionReader::new(&bytes[..bytes.len() - 1]).unwrap();
+    let err = reader.next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert!(reader.next().is_none());
+
+    // Bad header
+    let err = ExampleInput::read_binary(&b"{\"0\": []}"[..]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // Unknown opcode
+    let mut bytes = bytes[..5].to_vec();
+    bytes.push(11);
+    let err = ExampleInput::read_binary(bytes.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_load_save_binary() {
+    // Save a copy of an example in binary format, then load it back by
+    // basename and run it.
+    let dir = std::env::temp_dir().join("state_graph_test_binlog");
+    fs::create_dir_all(&dir).unwrap();
+    let basename = dir.join("unkloop_20").into_os_string().into_string();
+    let basename = basename.unwrap();
+
+    let original =
+        Example::load_from(&format!("{}/{}", EX_DIR_GENERATED, "unkloop_20"));
+    let mut example =
+        Example::new(&basename, original.input, original.expected);
+    example.format = InputFormat::Binary;
+    example.save();
+    assert!(!dir.join(format!("unkloop_20{}", EXAMPLE_IN_EXT)).exists());
+    assert!(dir.join(format!("unkloop_20{}", EXAMPLE_IN_BIN_EXT)).exists());
+
+    let loaded = Example::load_from(&basename);
+    assert_eq!(loaded.format, InputFormat::Binary);
+    assert_eq!(loaded.input.0, example.input.0);
+    let mut graph = JumpStateGraph::new();
+    let result = loaded.run_with_timeout(&mut graph, Duration::from_secs(10));
+    assert!(result.is_correct());
+    assert!(driver::example_basenames_in_dir(dir.to_str().unwrap())
+        .contains(&basename));
+}
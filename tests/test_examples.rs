@@ -112,10 +112,46 @@ fn test_20() {
     assert_one(EX_DIR_HANDWRITTEN, "20");
 }
 #[test]
+fn test_21() {
+    assert_one(EX_DIR_HANDWRITTEN, "21");
+}
+#[test]
+fn test_22() {
+    assert_one(EX_DIR_HANDWRITTEN, "22");
+}
+#[test]
 fn test_tree() {
     assert_one(EX_DIR_HANDWRITTEN, "tree_3");
 }
 
+/*
+    Universal states (only Naive and Simple)
+*/
+
+#[test]
+fn test_alternating_all() {
+    assert_all(EX_DIR_ALTERNATING);
+}
+
+/*
+    Büchi mode (all but Jump)
+*/
+
+#[test]
+fn test_buchi_all() {
+    assert_all(EX_DIR_BUCHI);
+}
+
+/*
+    Failures found by differential fuzzing (see src/bin/fuzz.rs)
+    (No expected output -- compares for agreement across algorithms)
+*/
+
+#[test]
+fn test_fuzz_all() {
+    assert_all(EX_DIR_FUZZ);
+}
+
 /*
     Auto-generated examples
 */
@@ -184,6 +220,22 @@ fn test_reverseunkloop() {
     assert_one(EX_DIR_GENERATED, "reverseunkloop_100");
 }
 
+#[test]
+fn test_hintunkline() {
+    assert_one(EX_DIR_GENERATED, "hintunkline_3");
+    assert_one(EX_DIR_GENERATED, "hintunkline_10");
+    assert_one(EX_DIR_GENERATED, "hintunkline_20");
+    assert_one(EX_DIR_GENERATED, "hintunkline_100");
+}
+
+#[test]
+fn test_hintreverseunkline() {
+    assert_one(EX_DIR_GENERATED, "hintreverseunkline_3");
+    assert_one(EX_DIR_GENERATED, "hintreverseunkline_10");
+    assert_one(EX_DIR_GENERATED, "hintreverseunkline_20");
+    assert_one(EX_DIR_GENERATED, "hintreverseunkline_100");
+}
+
 #[test]
 #[ignore]
 fn test_generated_expensive() {
@@ -0,0 +1,198 @@
+/*
+    Test the C API (ffi.rs) by calling it through the C ABI, with the
+    functions declared as they are in include/state_graph.h.
+*/
+
+use state_graph::algorithm::NaiveStateGraph;
+use state_graph::constants::*;
+use state_graph::example::Example;
+use state_graph::ffi::{SgAlgorithm, SgProbability, SgResult, SgStatus};
+use state_graph::interface::{StateGraph, Status, Transaction};
+
+// Opaque, as seen from C
+#[repr(C)]
+struct SgStateGraph {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn sg_new(algorithm: u32) -> *mut SgStateGraph;
+    fn sg_new_buchi(algorithm: u32) -> *mut SgStateGraph;
+    fn sg_new_markov(algorithm: u32) -> *mut SgStateGraph;
+    fn sg_free(graph: *mut SgStateGraph);
+    fn sg_add_transition(
+        graph: *mut SgStateGraph,
+        v1: usize,
+        v2: usize,
+    ) -> SgResult;
+    fn sg_add_labeled_transition(
+        graph: *mut SgStateGraph,
+        v1: usize,
+        v2: usize,
+        label: usize,
+    ) -> SgResult;
+    fn sg_mark_closed(graph: *mut SgStateGraph, v: usize) -> SgResult;
+    fn sg_mark_live(graph: *mut SgStateGraph, v: usize) -> SgResult;
+    fn sg_mark_live_for(
+        graph: *mut SgStateGraph,
+        v: usize,
+        target: usize,
+    ) -> SgResult;
+    fn sg_mark_initial(graph: *mut SgStateGraph, v: usize) -> SgResult;
+    fn sg_mark_universal(graph: *mut SgStateGraph, v: usize) -> SgResult;
+    fn sg_mark_accepting(graph: *mut SgStateGraph, v: usize) -> SgResult;
+    fn sg_not_reachable(
+        graph: *mut SgStateGraph,
+        v1: usize,
+        v2: usize,
+    ) -> SgResult;
+    fn sg_merge(graph: *mut SgStateGraph, v1: usize, v2: usize) -> SgResult;
+    fn sg_subsumed(graph: *mut SgStateGraph, v1: usize, v2: usize) -> SgResult;
+    fn sg_get_status(graph: *const SgStateGraph, v: usize) -> SgStatus;
+    fn sg_get_status_for(
+        graph: *const SgStateGraph,
+        v: usize,
+        target: usize,
+    ) -> SgStatus;
+    fn sg_get_probability(
+        graph: *const SgStateGraph,
+        v: usize,
+    ) -> SgProbability;
+    fn sg_is_reachable_from_initial(
+        graph: *const SgStateGraph,
+        v: usize,
+    ) -> bool;
+}
+
+const ALGORITHMS: &[SgAlgorithm] = &[
+    SgAlgorithm::Naive,
+    SgAlgorithm::Simple,
+    SgAlgorithm::Tarjan,
+    SgAlgorithm::Jump,
+];
+
+fn to_sg_status(status: Option<Status>) -> SgStatus {
+    match status {
+        Some(Status::Live) => SgStatus::Live,
+        Some(Status::Dead) => SgStatus::Dead,
+        Some(Status::Unknown) => SgStatus::Unknown,
+        Some(Status::Open) => SgStatus::Open,
+        None => SgStatus::Unseen,
+    }
+}
+
+fn check_ffi(basename: &str, algorithm: SgAlgorithm) {
+    // Run the example through the C API, and compare statuses against the
+    // naive algorithm after every transaction
+    let example = Example::load_from(basename);
+    let states = example.input.get_states();
+    let mut expected = NaiveStateGraph::new();
+    unsafe {
+        let graph = sg_new(algorithm as u32);
+        assert!(!graph.is_null());
+        for &t in &example.input.0 {
+            let result = match t {
+                Transaction::Add(v1, v2) => sg_add_transition(graph, v1, v2),
+                Transaction::AddLabeled(v1, v2, l) => {
+                    sg_add_labeled_transition(graph, v1, v2, l)
+                }
+                Transaction::Close(v) => sg_mark_closed(graph, v),
+                Transaction::Live(v) => sg_mark_live(graph, v),
+                Transaction::Initial(v) => sg_mark_initial(graph, v),
+                Transaction::Universal(v) => sg_mark_universal(graph, v),
+                Transaction::Accepting(v) => sg_mark_accepting(graph, v),
+                Transaction::LiveFor(v, c) => sg_mark_live_for(graph, v, c),
+                Transaction::NotReachable(v1, v2) => {
+                    sg_not_reachable(graph, v1, v2)
+                }
+                Transaction::Merge(v1, v2) => sg_merge(graph, v1, v2),
+                Transaction::Subsumed(v1, v2) => sg_subsumed(graph, v1, v2),
+            };
+            assert_eq!(result, expected.try_process(t).into());
+            for &v in &states {
+                let status = to_sg_status(expected.get_status(v));
+                assert_eq!(sg_get_status(graph, v), status);
+                let status = to_sg_status(expected.get_status_for(v, 1));
+                assert_eq!(sg_get_status_for(graph, v, 1), status);
+                assert_eq!(
+                    sg_is_reachable_from_initial(graph, v),
+                    expected.is_reachable_from_initial(v)
+                );
+            }
+        }
+        sg_free(graph);
+    }
+}
+
+#[test]
+fn test_ffi_examples() {
+    let basenames = [
+        format!("{}/{}", EX_DIR_HANDWRITTEN, "3"),
+        format!("{}/{}", EX_DIR_HANDWRITTEN, "22"),
+        format!("{}/{}", EX_DIR_GENERATED, "unkloop_20"),
+        format!("{}/{}", EX_DIR_REGEX_LOOP, "nestedloop2_sat"),
+    ];
+    for basename in &basenames {
+        for &algorithm in ALGORITHMS {
+            check_ffi(basename, algorithm);
+        }
+    }
+}
+
+#[test]
+fn test_ffi_results() {
+    unsafe {
+        assert!(sg_new(4).is_null());
+        let graph = sg_new(SgAlgorithm::Jump as u32);
+        assert_eq!(sg_get_status(graph, 0), SgStatus::Unseen);
+        assert_eq!(sg_add_transition(graph, 0, 1), SgResult::Ok);
+        assert_eq!(sg_add_transition(graph, 0, 0), SgResult::SelfLoop);
+        assert_eq!(sg_get_status(graph, 0), SgStatus::Open);
+        assert_eq!(sg_mark_closed(graph, 0), SgResult::Ok);
+        assert_eq!(sg_mark_closed(graph, 0), SgResult::AlreadyClosed);
+        assert_eq!(sg_get_status(graph, 0), SgStatus::Unknown);
+        assert_eq!(
+            sg_not_reachable(graph, 1, 1),
+            SgResult::ContradictoryNotReachable
+        );
+        assert_eq!(sg_mark_live(graph, 1), SgResult::Ok);
+        assert_eq!(sg_mark_live(graph, 1), SgResult::AlreadyLive);
+        assert_eq!(sg_get_status(graph, 0), SgStatus::Live);
+        assert_eq!(sg_get_status_for(graph, 0, 1), SgStatus::Unknown);
+        assert_eq!(sg_mark_live_for(graph, 1, 1), SgResult::Ok);
+        assert_eq!(sg_mark_live_for(graph, 1, 1), SgResult::AlreadyLive);
+        assert_eq!(sg_get_status_for(graph, 0, 1), SgStatus::Live);
+        assert_eq!(sg_mark_closed(graph, 2), SgResult::Ok);
+        assert_eq!(sg_merge(graph, 2, 3), SgResult::ContradictoryMerge);
+        assert_eq!(sg_merge(graph, 3, 0), SgResult::Ok);
+        assert_eq!(sg_get_status(graph, 3), SgStatus::Live);
+        assert_eq!(sg_subsumed(graph, 0, 4), SgResult::Ok);
+        assert_eq!(sg_get_status(graph, 4), SgStatus::Live);
+        assert_eq!(sg_subsumed(graph, 3, 2), SgResult::ContradictorySubsumed);
+        sg_free(graph);
+
+        let graph = sg_new_markov(SgAlgorithm::Jump as u32);
+        assert_eq!(sg_add_transition(graph, 0, 1), SgResult::Ok);
+        assert_eq!(sg_mark_live(graph, 1), SgResult::Ok);
+        assert_eq!(sg_add_transition(graph, 0, 2), SgResult::Ok);
+        assert_eq!(sg_get_probability(graph, 0), SgProbability::Unknown);
+        assert_eq!(sg_mark_closed(graph, 0), SgResult::Ok);
+        assert_eq!(sg_get_probability(graph, 0), SgProbability::Positive);
+        assert_eq!(sg_mark_closed(graph, 2), SgResult::Ok);
+        assert_eq!(sg_get_probability(graph, 2), SgProbability::Zero);
+        assert_eq!(sg_merge(graph, 0, 3), SgResult::MergeUnsupported);
+        assert_eq!(sg_subsumed(graph, 0, 3), SgResult::SubsumedUnsupported);
+        sg_free(graph);
+
+        assert!(sg_new_buchi(SgAlgorithm::Jump as u32).is_null());
+        let graph = sg_new_buchi(SgAlgorithm::Tarjan as u32);
+        assert_eq!(sg_mark_accepting(graph, 0), SgResult::Ok);
+        assert_eq!(sg_mark_universal(graph, 0), SgResult::UniversalUnsupported);
+        sg_free(graph);
+
+        let null = std::ptr::null_mut();
+        assert_eq!(sg_mark_closed(null, 0), SgResult::NullHandle);
+        assert_eq!(sg_get_status(null, 0), SgStatus::Unseen);
+        sg_free(null);
+    }
+}
@@ -0,0 +1,80 @@
+/*
+    Run a short differential fuzzing session (see fuzz.rs), in every mode,
+    and test the generator and shrinker themselves.
+*/
+
+use state_graph::algorithm::NaiveStateGraph;
+use state_graph::fuzz::{self, Rng};
+use state_graph::interface::{StateGraph, Transaction};
+use std::collections::HashSet;
+use std::mem;
+
+#[test]
+fn test_fuzz() {
+    for &(seed, num_states) in &[(0, 4), (1, 8), (2, 12), (3, 20)] {
+        let mut rng = Rng::new(seed);
+        for _ in 0..100 {
+            let transactions = fuzz::gen_transactions(&mut rng, num_states);
+            if let Err(failure) = fuzz::check(&transactions) {
+                panic!("{}\n{:?}", failure, transactions);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_gen_valid() {
+    // No transaction should be rejected, except as redundant (states can
+    // become live before their own transactions are done)
+    let mut rng = Rng::new(42);
+    for _ in 0..100 {
+        let mut graph = NaiveStateGraph::new();
+        for t in fuzz::gen_transactions(&mut rng, 10) {
+            if let Err(err) = graph.try_process(t) {
+                assert!(err.is_redundant(), "{}", err);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_gen_kinds() {
+    // Every kind of transaction is generated, except for universal and
+    // accepting states (which need their own modes)
+    let mut rng = Rng::new(42);
+    let mut kinds = HashSet::new();
+    for _ in 0..100 {
+        for t in fuzz::gen_transactions(&mut rng, 10) {
+            kinds.insert(mem::discriminant(&t));
+        }
+    }
+    for t in &[
+        Transaction::Add(0, 1),
+        Transaction::AddLabeled(0, 1, 0),
+        Transaction::Close(0),
+        Transaction::Live(0),
+        Transaction::NotReachable(0, 1),
+        Transaction::Initial(0),
+        Transaction::LiveFor(0, 1),
+        Transaction::Merge(0, 1),
+        Transaction::Subsumed(0, 1),
+    ] {
+        assert!(kinds.contains(&mem::discriminant(t)), "{:?}", t);
+    }
+}
+
+#[test]
+fn test_shrink() {
+    // Fails whenever 3 -> 5 is added and 5 is later closed
+    let fails = |ts: &[Transaction]| {
+        let add = ts.iter().position(|&t| t == Transaction::Add(3, 5));
+        let close = ts.iter().rposition(|&t| t == Transaction::Close(5));
+        matches!((add, close), (Some(i), Some(j)) if i < j)
+    };
+    let mut rng = Rng::new(7);
+    let mut transactions = fuzz::gen_transactions(&mut rng, 10);
+    transactions.insert(0, Transaction::Add(3, 5));
+    transactions.push(Transaction::Close(5));
+    let shrunk = fuzz::shrink(&transactions, fails);
+    assert_eq!(shrunk, vec![Transaction::Add(3, 5), Transaction::Close(5)]);
+}
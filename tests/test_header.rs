@@ -0,0 +1,26 @@
+/*
+    Test that the committed C header include/state_graph.h is up to date
+    with the C API (ffi.rs): generate it again with cbindgen and compare.
+*/
+
+use std::env;
+use std::fs;
+
+#[test]
+fn test_header_up_to_date() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let bindings = cbindgen::generate(&crate_dir).unwrap();
+    let mut generated = Vec::new();
+    bindings.write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+    let committed =
+        fs::read_to_string(format!("{}/include/state_graph.h", crate_dir))
+            .unwrap();
+    assert!(
+        generated == committed,
+        "include/state_graph.h is out of date with src/ffi.rs; regenerate \
+         it with: cbindgen --config cbindgen.toml --output \
+         include/state_graph.h\n\ngenerated:\n{}",
+        generated
+    );
+}
@@ -0,0 +1,1719 @@
+/*
+    Test the StateGraph interface directly, on small graphs built in code
+    rather than loaded from the examples/ directory.
+*/
+
+use state_graph::algorithm::{
+    JumpStateGraph, NaiveStateGraph, SimpleStateGraph, TarjanStateGraph,
+};
+use state_graph::constants::*;
+use state_graph::driver;
+use state_graph::example::{Example, ExampleInput};
+use state_graph::fuzz::{self, Rng};
+use state_graph::interface::{
+    Kind, Probability, StateGraph, StateGraphError, Status, StatusChange,
+    Transaction,
+};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::str::FromStr;
+
+/*
+    Differential harness
+
+    Each feature below contributes a generator, which adds its transactions
+    to random plain sequences (see fuzz::gen_plain_transactions), and an
+    oracle, which checks Naive against the definition of the feature at
+    the end of a sequence. The harness checks every algorithm against Naive
+    on each sequence (see fuzz::check): after each transaction, after
+    popping push/pop scopes, and after each batch of process_batch.
+
+    The seed and the number of sequences per feature can be set with the
+    environment variables STATE_GRAPH_TEST_SEED and
+    STATE_GRAPH_TEST_ITERATIONS.
+*/
+
+struct Feature {
+    gen: fn(&[Transaction], &mut Rng) -> Vec<Transaction>,
+    oracle: fn(&[Transaction]),
+    markov: bool,
+}
+
+const NUM_STATES: usize = 8;
+
+fn env_or<T: FromStr>(var: &str, default: T) -> T {
+    match env::var(var) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid {}: {}", var, value)),
+        Err(_) => default,
+    }
+}
+
+fn check_sequence(transactions: &[Transaction], markov: bool) {
+    // On failure, report the failure on the shrunk sequence
+    let check = if markov { fuzz::check_markov } else { fuzz::check };
+    if check(transactions).is_err() {
+        let shrunk = fuzz::shrink(transactions, |ts| check(ts).is_err());
+        let failure = check(&shrunk).unwrap_err();
+        panic!("{}\n{:?}", failure, shrunk);
+    }
+}
+
+fn check_feature(feature: &Feature) {
+    let seed = env_or("STATE_GRAPH_TEST_SEED", 0);
+    let iterations = env_or("STATE_GRAPH_TEST_ITERATIONS", 100);
+    let mut rng = Rng::new(seed);
+    for _ in 0..iterations {
+        let transactions = fuzz::gen_plain_transactions(&mut rng, NUM_STATES);
+        let transactions = (feature.gen)(&transactions, &mut rng);
+        let transactions = fuzz::drop_invalid(transactions);
+        (feature.oracle)(&transactions);
+        check_sequence(&transactions, feature.markov);
+    }
+}
+
+fn check_examples(basenames: &[String], oracle: fn(&[Transaction])) {
+    for basename in basenames {
+        let transactions = Example::load_from(basename).input.0;
+        oracle(&transactions);
+        check_sequence(&transactions, false);
+    }
+}
+
+fn plain(transactions: &[Transaction], _rng: &mut Rng) -> Vec<Transaction> {
+    transactions.to_vec()
+}
+
+fn check_status_final(transactions: &[Transaction]) {
+    // Compare Naive against the definition at the end, on the graph where
+    // merged states are one state: Live states can reach a state marked
+    // live; Dead states are closed and can only reach closed states.
+    let mut naive = NaiveStateGraph::new();
+    let mut canon: HashMap<usize, usize> = HashMap::new();
+    let mut accepted = Vec::new();
+    for &t in transactions {
+        if naive.try_process(t).is_err() {
+            continue;
+        }
+        accepted.push(t);
+        if let Transaction::Merge(v1, v2) = t {
+            let find = |canon: &HashMap<usize, usize>, mut v| {
+                while let Some(&u) = canon.get(&v) {
+                    v = u;
+                }
+                v
+            };
+            let (c1, c2) = (find(&canon, v1), find(&canon, v2));
+            if c1 != c2 {
+                canon.insert(c1, c2);
+            }
+        }
+    }
+    let find = |mut v| {
+        while let Some(&u) = canon.get(&v) {
+            v = u;
+        }
+        v
+    };
+    let mut succs: HashMap<usize, Vec<usize>> = HashMap::new();
+    let (mut closed, mut marked) = (HashSet::new(), HashSet::new());
+    for t in accepted {
+        match t {
+            Transaction::Add(v1, v2) | Transaction::AddLabeled(v1, v2, _) => {
+                succs.entry(find(v1)).or_default().push(find(v2))
+            }
+            Transaction::Close(v) => {
+                closed.insert(find(v));
+            }
+            Transaction::Live(v) => {
+                marked.insert(find(v));
+            }
+            _ => (),
+        }
+    }
+    for v in ExampleInput(transactions.to_vec()).get_states() {
+        if !naive.is_seen(v) {
+            continue;
+        }
+        let mut reach = HashSet::from([find(v)]);
+        let mut stack = vec![find(v)];
+        while let Some(u) = stack.pop() {
+            for &w in succs.get(&u).into_iter().flatten() {
+                if reach.insert(w) {
+                    stack.push(w);
+                }
+            }
+        }
+        let expected = if reach.iter().any(|w| marked.contains(w)) {
+            Status::Live
+        } else if !closed.contains(&find(v)) {
+            Status::Open
+        } else if reach.iter().any(|w| !closed.contains(w)) {
+            Status::Unknown
+        } else {
+            Status::Dead
+        };
+        assert_eq!(naive.get_status(v), Some(expected), "{}", v);
+    }
+}
+
+/*
+    Custom state IDs
+*/
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Term {
+    Star,
+    Concat(u8, u8),
+    Empty,
+}
+
+fn check_term_ids<G: StateGraph<Term>>() {
+    let mut graph = G::new();
+    graph.process(Transaction::Add(Term::Star, Term::Concat(1, 2)));
+    graph.process(Transaction::Add(Term::Concat(1, 2), Term::Star));
+    graph.process(Transaction::Add(Term::Concat(1, 2), Term::Empty));
+    graph.process(Transaction::Close(Term::Star));
+    graph.process(Transaction::Close(Term::Concat(1, 2)));
+    assert_eq!(graph.get_status(Term::Star), Some(Status::Unknown));
+    assert_eq!(graph.get_status(Term::Concat(1, 2)), Some(Status::Unknown));
+    assert_eq!(graph.get_status(Term::Concat(2, 1)), None);
+    graph.process(Transaction::Close(Term::Empty));
+    assert_eq!(graph.get_status(Term::Star), Some(Status::Dead));
+    assert_eq!(graph.get_status(Term::Concat(1, 2)), Some(Status::Dead));
+    assert_eq!(graph.get_status(Term::Empty), Some(Status::Dead));
+}
+
+#[test]
+fn test_term_ids() {
+    check_term_ids::<NaiveStateGraph<Term>>();
+    check_term_ids::<SimpleStateGraph<Term>>();
+    check_term_ids::<TarjanStateGraph<Term>>();
+    check_term_ids::<JumpStateGraph<Term>>();
+}
+
+/*
+    Fallible (try_) API
+*/
+
+fn check_try_errors<G: StateGraph>() {
+    let mut graph = G::new();
+    assert_eq!(graph.try_add_transition(0, 1), Ok(()));
+    assert_eq!(
+        graph.try_add_transition(0, 0),
+        Err(StateGraphError::SelfLoop(0))
+    );
+    assert_eq!(graph.try_mark_closed(0), Ok(()));
+    assert_eq!(
+        graph.try_add_transition(0, 2),
+        Err(StateGraphError::AlreadyClosed(0))
+    );
+    assert_eq!(
+        graph.try_mark_closed(0),
+        Err(StateGraphError::AlreadyClosed(0))
+    );
+    // (A closed state can be marked live until it is found Dead)
+    assert_eq!(graph.try_mark_closed(3), Ok(()));
+    assert_eq!(graph.try_mark_live(3), Err(StateGraphError::AlreadyClosed(3)));
+    assert_eq!(
+        graph.try_not_reachable(1, 1),
+        Err(StateGraphError::ContradictoryNotReachable(1, 1))
+    );
+    // A hint contradicted by a transition, or within a cycle
+    graph.add_transition(4, 5);
+    assert_eq!(
+        graph.try_not_reachable(4, 5),
+        Err(StateGraphError::ContradictoryNotReachable(4, 5))
+    );
+    graph.add_transition(5, 6);
+    graph.add_transition(6, 4);
+    graph.add_transition(6, 7);
+    graph.mark_closed(4);
+    graph.mark_closed(5);
+    graph.mark_closed(6);
+    assert_eq!(
+        graph.try_not_reachable(5, 4),
+        Err(StateGraphError::ContradictoryNotReachable(5, 4))
+    );
+    assert_eq!(graph.try_not_reachable(7, 4), Ok(()));
+    assert_eq!(graph.try_mark_live(1), Ok(()));
+    assert_eq!(graph.try_mark_live(1), Err(StateGraphError::AlreadyLive(1)));
+    assert_eq!(
+        graph.try_process(Transaction::Close(1)),
+        Err(StateGraphError::AlreadyLive(1))
+    );
+    // Rejected inputs leave the graph unchanged
+    assert_eq!(graph.get_status(0), Some(Status::Live));
+    assert_eq!(graph.get_status(1), Some(Status::Live));
+    assert_eq!(graph.get_status(2), None);
+    assert_eq!(graph.get_status(3), Some(Status::Dead));
+}
+
+#[test]
+fn test_try_errors() {
+    check_try_errors::<NaiveStateGraph>();
+    check_try_errors::<SimpleStateGraph>();
+    check_try_errors::<TarjanStateGraph>();
+    check_try_errors::<JumpStateGraph>();
+}
+
+#[test]
+fn test_error_redundant() {
+    assert!(StateGraphError::SelfLoop(0).is_redundant());
+    assert!(StateGraphError::AlreadyLive(0).is_redundant());
+    assert!(!StateGraphError::AlreadyClosed(0).is_redundant());
+    assert!(!StateGraphError::ContradictoryNotReachable(0, 0).is_redundant());
+}
+
+/*
+    Status change notifications
+*/
+
+fn check_status_changes<G: StateGraph>(basename: &str) {
+    // After every transaction, the changes reported so far should be
+    // exactly the states that are currently Live or Dead.
+    let example = Example::load_from(basename);
+    let states = example.input.get_states();
+    let mut graph = G::new();
+    let mut reported = HashMap::new();
+    for &t in &example.input.0 {
+        for StatusChange { state, status } in graph.process_and_report(t) {
+            assert!(status == Status::Live || status == Status::Dead);
+            assert_eq!(reported.insert(state, status), None);
+        }
+        for &v in &states {
+            match graph.get_status(v) {
+                Some(Status::Live) => {
+                    assert_eq!(reported.get(&v), Some(&Status::Live))
+                }
+                Some(Status::Dead) => {
+                    assert_eq!(reported.get(&v), Some(&Status::Dead))
+                }
+                _ => assert_eq!(reported.get(&v), None),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_status_changes() {
+    let mut basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "unkloop_20"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "nestedloop2_sat"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "evil2_inter_unsat"));
+    for basename in &basenames {
+        check_status_changes::<NaiveStateGraph>(basename);
+        check_status_changes::<SimpleStateGraph>(basename);
+        check_status_changes::<TarjanStateGraph>(basename);
+        check_status_changes::<JumpStateGraph>(basename);
+    }
+}
+
+/*
+    Push/pop scopes
+*/
+
+#[test]
+fn test_push_pop() {
+    let mut basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "loop_20"));
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "unkloop_20"));
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "reverseunkloop_20"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "nestedloop2_sat"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "evil2_inter_unsat"));
+    check_examples(&basenames, check_status_final);
+}
+
+#[test]
+fn test_push_pop_changes() {
+    let mut graph = SimpleStateGraph::new();
+    graph.add_transition(0, 1);
+    graph.mark_closed(0);
+    graph.push();
+    graph.mark_live(1);
+    assert!(graph.is_live(0));
+    graph.pop();
+    assert!(graph.is_unknown(0));
+    assert!(graph.drain_status_changes().is_empty());
+    graph.mark_closed(1);
+    assert!(graph.is_dead(0));
+    assert_eq!(graph.drain_status_changes().len(), 2);
+}
+
+/*
+    Explanations
+*/
+
+fn check_explanations<G: StateGraph>(basename: &str) {
+    // Check explanations against the transactions themselves, both halfway
+    // through and at the end of the example
+    let example = Example::load_from(basename);
+    let transactions = &example.input.0;
+    let n = transactions.len();
+    for &end in &[n / 2, n] {
+        let mut graph = G::new();
+        let mut edges = HashSet::new();
+        let mut marked = HashSet::new();
+        for &t in &transactions[..end] {
+            graph.process(t);
+            match t {
+                Transaction::Add(v1, v2) => {
+                    edges.insert((v1, v2));
+                }
+                Transaction::Merge(v1, v2) => {
+                    edges.insert((v1, v2));
+                    edges.insert((v2, v1));
+                }
+                Transaction::Live(v) => {
+                    marked.insert(v);
+                }
+                _ => (),
+            }
+        }
+        for v in example.input.get_states() {
+            match graph.get_status(v) {
+                Some(Status::Live) => {
+                    let path = graph.explain_live(v).unwrap();
+                    assert_eq!(path.first(), Some(&v));
+                    assert!(marked.contains(path.last().unwrap()));
+                    assert!(path.iter().all(|&u| graph.is_live(u)));
+                    for pair in path.windows(2) {
+                        assert!(edges.contains(&(pair[0], pair[1])));
+                    }
+                    assert_eq!(graph.explain_dead(v), None);
+                }
+                Some(Status::Dead) => {
+                    let cert: HashSet<usize> =
+                        graph.explain_dead(v).unwrap().into_iter().collect();
+                    assert!(cert.contains(&v));
+                    assert!(cert.iter().all(|&u| graph.is_dead(u)));
+                    for &(v1, v2) in &edges {
+                        assert!(!cert.contains(&v1) || cert.contains(&v2));
+                    }
+                    assert_eq!(graph.explain_live(v), None);
+                }
+                _ => {
+                    assert_eq!(graph.explain_live(v), None);
+                    assert_eq!(graph.explain_dead(v), None);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_explanations() {
+    let mut basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "loop_20"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "nestedloop2_sat"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "evil2_inter_unsat"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_DATE, "date_minimal_sat"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_PASSW, "passw_sat1"));
+    basenames.push(format!("{}/{}", EX_DIR_RLIB_INTER1, "intersect_1_5"));
+    for basename in &basenames {
+        check_explanations::<NaiveStateGraph>(basename);
+        check_explanations::<SimpleStateGraph>(basename);
+        check_explanations::<TarjanStateGraph>(basename);
+        check_explanations::<JumpStateGraph>(basename);
+    }
+}
+
+/*
+    Batch processing
+*/
+
+#[test]
+fn test_process_batch() {
+    let mut basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "line_20"));
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "reverseloop_20"));
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "unkloop_20"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "nestedloop2_sat"));
+    check_examples(&basenames, check_status_final);
+    check_feature(&Feature {
+        gen: plain,
+        oracle: check_status_final,
+        markov: false,
+    });
+}
+
+/*
+    Reachability queries
+*/
+
+fn check_reachable<G: StateGraph>(transactions: &[Transaction], markov: bool) {
+    // After each transaction, compare is_reachable on every pair of states
+    // against Naive. Answers may be None where Naive knows better, and
+    // Some(false) where Naive doesn't know (from NotReachable hints), but
+    // must never be wrong. Dead states should always be answered.
+    // (A later merge can make any two states reachable, so the final check
+    // on Some(false) only applies without merges. In Markov mode, Live
+    // states can still get transitions until they are closed.)
+    let states: Vec<usize> =
+        ExampleInput(transactions.to_vec()).get_states().into_iter().collect();
+    let merges =
+        transactions.iter().any(|t| matches!(t, Transaction::Merge(_, _)));
+    let new = |markov| if markov { G::new_markov() } else { G::new() };
+    let new_naive = |markov| {
+        if markov {
+            NaiveStateGraph::new_markov()
+        } else {
+            NaiveStateGraph::new()
+        }
+    };
+    let mut end = new_naive(markov);
+    end.process_batch(transactions);
+    let mut graph = new(markov);
+    let mut naive = new_naive(markov);
+    for &t in transactions {
+        graph.process(t);
+        naive.process(t);
+        for &v in &states {
+            for &w in &states {
+                let actual = graph.is_reachable(v, w);
+                let expected = naive.is_reachable(v, w);
+                if actual == Some(true) || expected == Some(true) {
+                    assert_eq!(actual.or(expected), expected, "{} {}", v, w);
+                }
+                if (actual == Some(false) || expected == Some(false)) && !merges
+                {
+                    assert_ne!(end.is_reachable(v, w), Some(true));
+                }
+                if naive.is_dead(v) {
+                    assert_eq!(actual, expected, "{} {}", v, w);
+                }
+            }
+        }
+    }
+}
+
+fn check_reachable_all(transactions: &[Transaction]) {
+    check_reachable::<NaiveStateGraph>(transactions, false);
+    check_reachable::<SimpleStateGraph>(transactions, false);
+    check_reachable::<TarjanStateGraph>(transactions, false);
+    check_reachable::<JumpStateGraph>(transactions, false);
+}
+
+fn check_reachable_markov(transactions: &[Transaction]) {
+    check_reachable::<NaiveStateGraph>(transactions, true);
+    check_reachable::<SimpleStateGraph>(transactions, true);
+    check_reachable::<TarjanStateGraph>(transactions, true);
+    check_reachable::<JumpStateGraph>(transactions, true);
+}
+
+#[test]
+fn test_reachable() {
+    for basename in &driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN) {
+        check_reachable_all(&Example::load_from(basename).input.0);
+    }
+    let mut graph = SimpleStateGraph::new();
+    graph.add_transition(0, 1);
+    graph.add_transition(1, 2);
+    graph.add_transition(3, 1);
+    assert_eq!(graph.is_reachable(0, 2), Some(true));
+    assert_eq!(graph.is_reachable(4, 4), Some(true));
+    assert_eq!(graph.is_reachable(0, 3), None);
+    graph.mark_closed(0);
+    graph.mark_closed(1);
+    assert_eq!(graph.is_reachable(0, 3), None);
+    graph.mark_closed(2);
+    assert_eq!(graph.is_reachable(0, 3), Some(false));
+    assert_eq!(graph.is_reachable(1, 4), Some(false));
+    assert_eq!(graph.is_reachable(4, 1), None);
+    // In Markov mode, a Live state can still get transitions
+    let mut graph = NaiveStateGraph::new_markov();
+    graph.add_transition(0, 1);
+    graph.add_transition(1, 2);
+    graph.mark_closed(0);
+    graph.mark_live(1);
+    graph.mark_closed(2);
+    assert_eq!(graph.is_reachable(0, 3), None);
+    graph.add_transition(1, 3);
+    assert_eq!(graph.is_reachable(0, 3), Some(true));
+}
+
+#[test]
+fn test_reachable_random() {
+    // (Answers are only sound, not equal, so the oracle checks every
+    // algorithm against Naive)
+    check_feature(&Feature {
+        gen: plain,
+        oracle: check_reachable_all,
+        markov: false,
+    });
+    check_feature(&Feature {
+        gen: plain,
+        oracle: check_reachable_markov,
+        markov: true,
+    });
+}
+
+/*
+    Status indexes
+*/
+
+#[test]
+fn test_status_index() {
+    let mut basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "unkloop_20"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "nestedloop2_sat"));
+    // (The harness checks iter_status and count_status against get_status)
+    check_examples(&basenames, check_status_final);
+}
+
+/*
+    Witnesses
+*/
+
+fn check_witness_small<G: StateGraph>() {
+    let mut graph = G::new();
+    graph.add_labeled_transition(0, 1, 10);
+    graph.add_labeled_transition(1, 2, 11);
+    graph.add_labeled_transition(0, 2, 12);
+    graph.add_transition(2, 3);
+    // A cycle of closed states, merged into one
+    graph.add_labeled_transition(4, 5, 13);
+    graph.add_labeled_transition(5, 4, 14);
+    graph.add_labeled_transition(5, 0, 15);
+    graph.mark_closed(4);
+    graph.mark_closed(5);
+    assert_eq!(graph.witness_live(4), None);
+    graph.mark_live(3);
+    assert_eq!(graph.witness_live(3), Some(vec![]));
+    assert_eq!(graph.witness_live(2), Some(vec![]));
+    assert_eq!(graph.witness_live(0), Some(vec![12]));
+    assert_eq!(graph.witness_live(4), Some(vec![13, 15, 12]));
+    assert_eq!(graph.witness_live(5), Some(vec![15, 12]));
+    assert_eq!(graph.witness_live(6), None);
+}
+
+fn with_labels(
+    transactions: &[Transaction],
+    _rng: &mut Rng,
+) -> Vec<Transaction> {
+    // Label each transition by its position
+    transactions
+        .iter()
+        .enumerate()
+        .map(|(i, &t)| match t {
+            Transaction::Add(v1, v2) | Transaction::AddLabeled(v1, v2, _) => {
+                Transaction::AddLabeled(v1, v2, i)
+            }
+            t => t,
+        })
+        .collect()
+}
+
+fn check_witness<G: StateGraph>(transactions: &[Transaction]) {
+    // With transitions labeled by position, check that each witness spells
+    // out a path to a state marked live, as short as Naive's. The path may
+    // step between merged states without a label.
+    let labeled = with_labels(transactions, &mut Rng::new(0));
+    let merged = |v: usize, w: usize| {
+        let mut seen = HashSet::from([v]);
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            for &t in &labeled {
+                match t {
+                    Transaction::Merge(v1, v2)
+                        if v1 == u && seen.insert(v2) =>
+                    {
+                        stack.push(v2)
+                    }
+                    Transaction::Merge(v1, v2)
+                        if v2 == u && seen.insert(v1) =>
+                    {
+                        stack.push(v1)
+                    }
+                    _ => (),
+                }
+            }
+        }
+        seen.contains(&w)
+    };
+    let mut graph = G::new();
+    let mut naive = NaiveStateGraph::new();
+    graph.process_batch(&labeled);
+    naive.process_batch(&labeled);
+    for v in ExampleInput(labeled.clone()).get_states() {
+        let word = graph.witness_live(v);
+        assert_eq!(word.is_some(), graph.is_live(v));
+        if let Some(word) = word {
+            let mut u = v;
+            for &l in &word {
+                match labeled[l] {
+                    Transaction::AddLabeled(v1, v2, _) if merged(u, v1) => {
+                        u = v2
+                    }
+                    t => panic!("{:?} does not continue from {}", t, u),
+                }
+            }
+            assert!(labeled
+                .iter()
+                .any(|&t| matches!(t, Transaction::Live(w) if merged(u, w))));
+            assert_eq!(word.len(), naive.witness_live(v).unwrap().len());
+        }
+    }
+}
+
+fn check_witness_all(transactions: &[Transaction]) {
+    check_witness::<NaiveStateGraph>(transactions);
+    check_witness::<SimpleStateGraph>(transactions);
+    check_witness::<TarjanStateGraph>(transactions);
+    check_witness::<JumpStateGraph>(transactions);
+}
+
+#[test]
+fn test_witness() {
+    check_witness_small::<NaiveStateGraph>();
+    check_witness_small::<SimpleStateGraph>();
+    check_witness_small::<TarjanStateGraph>();
+    check_witness_small::<JumpStateGraph>();
+    let mut basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_DATE, "date_minimal_sat"));
+    let regexlib = driver::example_basenames_in_dir(EX_DIR_RLIB_M1);
+    basenames.extend(regexlib.into_iter().take(20));
+    for basename in &basenames {
+        check_witness_all(&Example::load_from(basename).input.0);
+    }
+    // (The harness checks that the witnesses are as long as Naive's; the
+    // oracle, that they spell out paths)
+    check_feature(&Feature {
+        gen: with_labels,
+        oracle: check_witness_all,
+        markov: false,
+    });
+}
+
+/*
+    Distance to live
+*/
+
+fn distances<G: StateGraph>(graph: &G, states: &[usize]) -> Vec<Option<usize>> {
+    // Check distances against explanations (shortest paths) on the way
+    states
+        .iter()
+        .map(|&v| {
+            let dist = graph.distance_to_live(v);
+            let path = graph.explain_live(v);
+            assert_eq!(dist, path.map(|path| path.len() - 1), "{}", v);
+            dist
+        })
+        .collect()
+}
+
+fn check_distances_final(transactions: &[Transaction]) {
+    // Compare Naive's distances against its explanations at the end
+    let mut naive = NaiveStateGraph::new();
+    for &t in transactions {
+        naive.process(t);
+    }
+    let states: Vec<usize> =
+        ExampleInput(transactions.to_vec()).get_states().into_iter().collect();
+    distances(&naive, &states);
+}
+
+#[test]
+fn test_distance_to_live() {
+    let mut graph = TarjanStateGraph::new();
+    for v in 0..5 {
+        graph.add_transition(v, v + 1);
+    }
+    graph.add_transition(1, 6);
+    graph.add_transition(0, 7);
+    graph.mark_live(5);
+    assert_eq!(graph.distance_to_live(0), Some(5));
+    assert_eq!(graph.distance_to_live(6), None);
+    // Distances decrease with new transitions and live marks
+    graph.add_transition(6, 4);
+    assert_eq!(graph.distance_to_live(6), Some(2));
+    assert_eq!(graph.distance_to_live(1), Some(3));
+    assert_eq!(graph.distance_to_live(0), Some(4));
+    graph.mark_live(7);
+    assert_eq!(graph.distance_to_live(7), Some(0));
+    assert_eq!(graph.distance_to_live(0), Some(1));
+    assert_eq!(graph.distance_to_live(1), Some(3));
+    assert_eq!(graph.distance_to_live(8), None);
+
+    let mut basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "nestedloop2_sat"));
+    check_examples(&basenames, check_distances_final);
+    // (Too long to check after each step in the harness)
+    let date = format!("{}/{}", EX_DIR_REGEX_DATE, "date_minimal_sat");
+    check_distances_final(&Example::load_from(&date).input.0);
+    check_feature(&Feature {
+        gen: plain,
+        oracle: check_distances_final,
+        markov: false,
+    });
+}
+
+/*
+    Live marks on closed states
+*/
+
+fn check_live_after_close<G: StateGraph>(markov: bool) {
+    // A cycle 1 -> 2 -> 3 -> 1 of closed states (merged by most
+    // algorithms), reached from 0 and with an open exit 3 -> 4
+    let mut graph = if markov { G::new_markov() } else { G::new() };
+    let states = [0, 1, 2, 3, 4];
+    for (v1, v2) in [(0, 1), (1, 2), (2, 3), (3, 1), (3, 4)] {
+        graph.add_transition(v1, v2);
+    }
+    for v in 0..4 {
+        graph.mark_closed(v);
+    }
+    assert_eq!(graph.get_status(1), Some(Status::Unknown));
+    graph.push();
+    graph.mark_live(2);
+    for v in 0..4 {
+        assert_eq!(graph.get_status(v), Some(Status::Live), "{}", v);
+    }
+    assert_eq!(
+        distances(&graph, &states),
+        vec![Some(2), Some(1), Some(0), Some(2), None]
+    );
+    if markov {
+        // Paths from 1 and 0 all go through 2; from 3 they may exit to 4
+        assert_eq!(graph.get_probability(0), Some(Probability::One));
+        assert_eq!(graph.get_probability(1), Some(Probability::One));
+        assert_eq!(graph.get_probability(3), Some(Probability::Positive));
+        return;
+    }
+    graph.pop();
+    assert_eq!(graph.get_status(2), Some(Status::Unknown));
+    graph.mark_closed(4);
+    assert_eq!(graph.get_status(2), Some(Status::Dead));
+    assert_eq!(graph.try_mark_live(2), Err(StateGraphError::AlreadyClosed(2)));
+}
+
+#[test]
+fn test_live_after_close() {
+    for markov in [false, true] {
+        check_live_after_close::<NaiveStateGraph>(markov);
+        check_live_after_close::<SimpleStateGraph>(markov);
+        check_live_after_close::<TarjanStateGraph>(markov);
+        check_live_after_close::<JumpStateGraph>(markov);
+    }
+}
+
+/*
+    Initial states
+*/
+
+fn with_initial(
+    transactions: &[Transaction],
+    rng: &mut Rng,
+) -> Vec<Transaction> {
+    let initial = [rng.below(NUM_STATES), rng.below(NUM_STATES)];
+    with_initial_states(transactions, &initial)
+}
+
+fn with_initial_states(
+    transactions: &[Transaction],
+    initial: &[usize],
+) -> Vec<Transaction> {
+    // Mark the given states initial, spread through the transactions
+    let mut result = transactions.to_vec();
+    for (i, &v) in initial.iter().enumerate() {
+        let pos = result.len() * i / initial.len();
+        result.insert(pos, Transaction::Initial(v));
+    }
+    result
+}
+
+fn check_initial_final(transactions: &[Transaction]) {
+    // Compare Naive against the forward closure of the initial states over
+    // the transitions accepted, at the end
+    let mut naive = NaiveStateGraph::new();
+    let mut accepted = Vec::new();
+    for &t in transactions {
+        if naive.try_process(t).is_ok() {
+            accepted.push(t);
+        }
+    }
+    let mut expected = HashSet::new();
+    let mut stack: Vec<usize> = naive.iter_initial().collect();
+    while let Some(v) = stack.pop() {
+        if expected.insert(v) {
+            for &t in &accepted {
+                match t {
+                    Transaction::Add(v1, v2)
+                    | Transaction::AddLabeled(v1, v2, _)
+                        if v1 == v =>
+                    {
+                        stack.push(v2)
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    for v in ExampleInput(transactions.to_vec()).get_states() {
+        assert_eq!(naive.is_reachable_from_initial(v), expected.contains(&v));
+    }
+}
+
+#[test]
+fn test_initial() {
+    let mut graph = JumpStateGraph::new();
+    graph.mark_initial(0);
+    assert!(!graph.is_initial_decided());
+    graph.add_transition(0, 1);
+    graph.add_transition(2, 3);
+    assert!(graph.is_reachable_from_initial(1));
+    assert!(!graph.is_reachable_from_initial(2));
+    graph.add_transition(1, 2);
+    assert!(graph.is_reachable_from_initial(3));
+    graph.mark_closed(0);
+    graph.mark_closed(1);
+    assert!(!graph.is_initial_decided());
+    graph.mark_live(3);
+    assert!(graph.is_initial_decided());
+    assert_eq!(graph.iter_initial().collect::<Vec<_>>(), vec![0]);
+
+    for basename in &driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN) {
+        let transactions = Example::load_from(basename).input.0;
+        let transactions = with_initial_states(&transactions, &[0]);
+        check_initial_final(&transactions);
+        check_sequence(&transactions, false);
+    }
+    check_feature(&Feature {
+        gen: with_initial,
+        oracle: check_initial_final,
+        markov: false,
+    });
+}
+
+/*
+    Universal states
+*/
+
+fn with_universal(
+    transactions: &[Transaction],
+    _rng: &mut Rng,
+) -> Vec<Transaction> {
+    // Mark states 0 to 3 universal, before anything else. (A closed
+    // universal state may then be found Dead before it is marked live.)
+    let ts = (0..4).map(Transaction::Universal);
+    fuzz::drop_invalid(ts.chain(transactions.iter().copied()).collect())
+}
+
+fn alternating_fixpoint(
+    succs: &HashMap<usize, Vec<usize>>,
+    universal: &HashSet<usize>,
+    closed: &HashSet<usize>,
+    mut result: HashSet<usize>,
+) -> HashSet<usize> {
+    // Least fixed point containing result: existential states with some
+    // successor in it, and closed universal states with all of them
+    loop {
+        let new: Vec<usize> = succs
+            .iter()
+            .filter(|&(v, ws)| {
+                !result.contains(v)
+                    && if universal.contains(v) {
+                        closed.contains(v)
+                            && ws.iter().all(|w| result.contains(w))
+                    } else {
+                        ws.iter().any(|w| result.contains(w))
+                    }
+            })
+            .map(|(&v, _)| v)
+            .collect();
+        if new.is_empty() {
+            return result;
+        }
+        result.extend(new);
+    }
+}
+
+fn check_alternating_final(transactions: &[Transaction]) {
+    // Compare Naive against the definition of liveness at the end
+    let mut naive = NaiveStateGraph::new();
+    let mut succs: HashMap<usize, Vec<usize>> = HashMap::new();
+    let (mut universal, mut closed, mut live) =
+        (HashSet::new(), HashSet::new(), HashSet::new());
+    for v in ExampleInput(transactions.to_vec()).get_states() {
+        succs.insert(v, Vec::new());
+    }
+    for &t in transactions {
+        if naive.try_process(t).is_err() {
+            continue;
+        }
+        match t {
+            Transaction::Add(v1, v2) => succs.get_mut(&v1).unwrap().push(v2),
+            Transaction::Close(v) => {
+                closed.insert(v);
+            }
+            Transaction::Live(v) => {
+                live.insert(v);
+            }
+            Transaction::Universal(v) => {
+                universal.insert(v);
+            }
+            _ => (),
+        }
+    }
+    let open =
+        succs.keys().filter(|v| !closed.contains(v) && !live.contains(v));
+    let maybe_live = open.copied().chain(live.iter().copied()).collect();
+    let maybe_live =
+        alternating_fixpoint(&succs, &universal, &closed, maybe_live);
+    let live = alternating_fixpoint(&succs, &universal, &closed, live);
+    for &v in succs.keys().filter(|&&v| naive.is_seen(v)) {
+        let expected = if live.contains(&v) {
+            Status::Live
+        } else if !closed.contains(&v) {
+            Status::Open
+        } else if maybe_live.contains(&v) {
+            Status::Unknown
+        } else {
+            Status::Dead
+        };
+        assert_eq!(naive.get_status(v), Some(expected), "{}", v);
+    }
+}
+
+#[test]
+fn test_universal() {
+    let mut graph = SimpleStateGraph::new();
+    graph.mark_universal(0);
+    assert_eq!(graph.get_kind(0), Kind::Universal);
+    assert_eq!(graph.get_kind(1), Kind::Existential);
+    graph.add_transition(0, 1);
+    graph.add_transition(0, 2);
+    graph.mark_live(1);
+    assert_eq!(graph.get_status(0), Some(Status::Open));
+    graph.mark_closed(0);
+    assert_eq!(graph.get_status(0), Some(Status::Unknown));
+    graph.mark_live(2);
+    assert_eq!(graph.get_status(0), Some(Status::Live));
+    assert_eq!(graph.distance_to_live(0), Some(1));
+    assert_eq!(
+        graph.try_mark_universal(0),
+        Err(StateGraphError::AlreadyLive(0))
+    );
+    let mut tarjan = TarjanStateGraph::new();
+    assert_eq!(
+        tarjan.try_mark_universal(0),
+        Err(StateGraphError::UniversalUnsupported(0))
+    );
+    let mut jump = JumpStateGraph::new();
+    assert_eq!(
+        jump.mark_universal_unchecked(0),
+        Err(StateGraphError::UniversalUnsupported(0))
+    );
+    assert_eq!(jump.get_status(0), None);
+
+    check_feature(&Feature {
+        gen: with_universal,
+        oracle: check_alternating_final,
+        markov: false,
+    });
+}
+
+#[test]
+#[should_panic(expected = "universal states not supported")]
+fn test_universal_unsupported() {
+    // Reported in release builds too, rather than dropped
+    let mut graph = TarjanStateGraph::new();
+    graph.process(Transaction::Universal(0));
+}
+
+/*
+    Büchi mode
+*/
+
+fn with_accepting(
+    transactions: &[Transaction],
+    rng: &mut Rng,
+) -> Vec<Transaction> {
+    // Mark a few states accepting, before anything else
+    let accepting = (0..3).map(|_| Transaction::Accepting(rng.below(8)));
+    accepting.chain(transactions.iter().copied()).collect()
+}
+
+fn check_buchi_final(transactions: &[Transaction]) {
+    // Compare Naive against the definition at the end: Live states can
+    // reach a state marked live, or an accepting state on a cycle of closed
+    // states; Dead states are closed and can only reach closed states.
+    let mut naive = NaiveStateGraph::new_buchi().unwrap();
+    let mut succs: HashMap<usize, Vec<usize>> = HashMap::new();
+    let (mut accepting, mut closed, mut live) =
+        (HashSet::new(), HashSet::new(), HashSet::new());
+    for &t in transactions {
+        if naive.try_process(t).is_err() {
+            continue;
+        }
+        match t {
+            Transaction::Add(v1, v2) => succs.entry(v1).or_default().push(v2),
+            Transaction::Close(v) => {
+                closed.insert(v);
+            }
+            Transaction::Live(v) => {
+                live.insert(v);
+            }
+            Transaction::Accepting(v) => {
+                accepting.insert(v);
+            }
+            _ => (),
+        }
+    }
+    let reachable = |v: usize, include: &dyn Fn(usize) -> bool| {
+        // States reachable from v in at least one step through 'include'
+        let mut result = HashSet::new();
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            for &w in succs.get(&u).into_iter().flatten() {
+                if include(w) && result.insert(w) {
+                    stack.push(w);
+                }
+            }
+        }
+        result
+    };
+    let targets: HashSet<usize> = accepting
+        .iter()
+        .copied()
+        .filter(|&a| {
+            closed.contains(&a)
+                && reachable(a, &|w| closed.contains(&w)).contains(&a)
+        })
+        .chain(live)
+        .collect();
+    for v in ExampleInput(transactions.to_vec()).get_states() {
+        if !naive.is_seen(v) {
+            continue;
+        }
+        let mut reach = reachable(v, &|_| true);
+        reach.insert(v);
+        let expected = if reach.iter().any(|w| targets.contains(w)) {
+            Status::Live
+        } else if !closed.contains(&v) {
+            Status::Open
+        } else if reach.iter().any(|w| !closed.contains(w)) {
+            Status::Unknown
+        } else {
+            Status::Dead
+        };
+        assert_eq!(naive.get_status(v), Some(expected), "{}", v);
+    }
+}
+
+fn check_buchi_small<G: StateGraph>() {
+    // Each accepting state on a cycle is Live as if marked live, even if
+    // found Live through another one first
+    use Transaction::*;
+    let mut graph = G::new_buchi().unwrap();
+    graph.mark_accepting(0);
+    graph.mark_accepting(1);
+    graph.add_transition(0, 1);
+    graph.add_transition(2, 0);
+    graph.add_transition(1, 2);
+    graph.mark_closed(2);
+    graph.mark_closed(0);
+    graph.mark_closed(1);
+    assert_eq!(graph.distance_to_live(0), Some(0));
+    assert_eq!(graph.distance_to_live(1), Some(0));
+    assert_eq!(graph.distance_to_live(2), Some(1));
+    // In a batch, closing a state already found Live on an accepting cycle
+    // is redundant, as when processed one at a time
+    let mut graph = G::new_buchi().unwrap();
+    graph.process_batch(&[
+        Accepting(0),
+        Add(0, 1),
+        Add(1, 0),
+        Add(2, 0),
+        Close(1),
+        Close(0),
+        Close(2),
+    ]);
+    assert!(graph.is_live(2));
+    assert!(!graph.is_marked_closed(2));
+    assert_eq!(graph.get_status_for(2, 1), Some(Status::Open));
+    // ... unless another class is in use, which it may still be Dead for
+    let mut graph = G::new_buchi().unwrap();
+    graph.process_batch(&[
+        LiveFor(5, 1),
+        Accepting(0),
+        Add(0, 1),
+        Add(1, 0),
+        Add(2, 0),
+        Close(1),
+        Close(0),
+        Close(2),
+    ]);
+    assert!(graph.is_live(2));
+    assert!(graph.is_marked_closed(2));
+    assert_eq!(graph.get_status_for(2, 1), Some(Status::Dead));
+    // A self-loop on an accepting state is a cycle, whichever comes first;
+    // on another state, it doesn't keep the state from being Dead
+    let mut graph = G::new_buchi().unwrap();
+    graph.mark_accepting(6);
+    assert_eq!(graph.try_add_transition(6, 6), Ok(()));
+    graph.add_transition(7, 7);
+    graph.mark_accepting(7);
+    graph.add_transition(8, 8);
+    graph.mark_closed(6);
+    graph.mark_closed(7);
+    graph.mark_closed(8);
+    assert_eq!(graph.get_status(6), Some(Status::Live));
+    assert_eq!(graph.distance_to_live(6), Some(0));
+    assert_eq!(graph.get_status(7), Some(Status::Live));
+    assert_eq!(graph.get_status(8), Some(Status::Dead));
+    let mut graph = G::new();
+    assert_eq!(
+        graph.try_add_transition(6, 6),
+        Err(StateGraphError::SelfLoop(6))
+    );
+}
+
+#[test]
+fn test_buchi() {
+    check_buchi_small::<NaiveStateGraph>();
+    check_buchi_small::<SimpleStateGraph>();
+    check_buchi_small::<TarjanStateGraph>();
+    let mut graph = TarjanStateGraph::new_buchi().unwrap();
+    graph.mark_accepting(2);
+    graph.add_transition(1, 2);
+    graph.add_transition(2, 3);
+    graph.add_transition(3, 2);
+    graph.add_transition(3, 4);
+    graph.mark_closed(1);
+    graph.mark_closed(2);
+    assert_eq!(graph.get_status(2), Some(Status::Unknown));
+    graph.mark_closed(3);
+    assert_eq!(graph.get_status(1), Some(Status::Live));
+    assert_eq!(graph.explain_live(1), Some(vec![1, 2]));
+    graph.mark_closed(4);
+    assert_eq!(graph.get_status(4), Some(Status::Dead));
+    let mut graph = SimpleStateGraph::new();
+    assert_eq!(graph.try_mark_accepting(0), Err(StateGraphError::NotBuchi(0)));
+
+    check_feature(&Feature {
+        gen: with_accepting,
+        oracle: check_buchi_final,
+        markov: false,
+    });
+}
+
+#[test]
+fn test_buchi_batch() {
+    // Found by fuzzing: Simple used to defer merging cycles to the end of
+    // the batch, and found state 7 Open rather than unseen
+    use Transaction::*;
+    let transactions = [
+        Accepting(0),
+        Accepting(1),
+        Accepting(2),
+        Add(3, 0),
+        Add(2, 0),
+        Add(2, 4),
+        Add(3, 5),
+        Add(2, 6),
+        Close(3),
+        NotReachable(3, 2),
+        Add(0, 1),
+        Add(5, 0),
+        Close(5),
+        Add(0, 5),
+        Add(4, 2),
+        Add(6, 0),
+        Close(0),
+        Add(6, 1),
+        NotReachable(5, 7),
+        Add(1, 5),
+        Add(4, 7),
+        Close(1),
+        Live(2),
+        Close(4),
+    ];
+    check_buchi_final(&transactions);
+    check_sequence(&transactions, false);
+}
+
+/*
+    Target classes
+*/
+
+const CLASSES: &[usize] = &[0, 1, 2];
+
+fn with_classes(
+    transactions: &[Transaction],
+    rng: &mut Rng,
+) -> Vec<Transaction> {
+    // Mark a few states live for classes 1 and 2, spread through the
+    // transactions
+    let mut result = transactions.to_vec();
+    for _ in 0..4 {
+        let pos = rng.below(result.len() + 1);
+        result
+            .insert(pos, Transaction::LiveFor(rng.below(8), 1 + rng.below(2)));
+    }
+    result
+}
+
+fn check_classes_final(transactions: &[Transaction]) {
+    // Compare Naive against the definition at the end, for classes other
+    // than the default: Live states can reach a state marked live for the
+    // class; Dead states are closed and can only reach closed states.
+    let mut naive = NaiveStateGraph::new();
+    let mut succs: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut closed = HashSet::new();
+    let mut marked = HashSet::new();
+    for &t in transactions {
+        if naive.try_process(t).is_err() {
+            continue;
+        }
+        match t {
+            Transaction::Add(v1, v2) => succs.entry(v1).or_default().push(v2),
+            Transaction::Close(v) => {
+                closed.insert(v);
+            }
+            Transaction::LiveFor(v, c) => {
+                marked.insert((v, c));
+            }
+            _ => (),
+        }
+    }
+    for v in ExampleInput(transactions.to_vec()).get_states() {
+        if !naive.is_seen(v) {
+            continue;
+        }
+        let mut reach = HashSet::from([v]);
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            for &w in succs.get(&u).into_iter().flatten() {
+                if reach.insert(w) {
+                    stack.push(w);
+                }
+            }
+        }
+        for &c in &CLASSES[1..] {
+            let expected = if reach.iter().any(|&w| marked.contains(&(w, c))) {
+                Status::Live
+            } else if !closed.contains(&v) {
+                Status::Open
+            } else if reach.iter().any(|w| !closed.contains(w)) {
+                Status::Unknown
+            } else {
+                Status::Dead
+            };
+            assert_eq!(naive.get_status_for(v, c), Some(expected), "{}", v);
+        }
+    }
+}
+
+#[test]
+fn test_classes() {
+    let mut graph = JumpStateGraph::new();
+    graph.add_transition(1, 2);
+    graph.add_transition(1, 3);
+    graph.mark_closed(1);
+    graph.mark_live(2);
+    graph.mark_live_for(3, 1);
+    graph.mark_closed(3);
+    assert_eq!(graph.get_status_for(1, 0), Some(Status::Live));
+    assert_eq!(graph.get_status_for(1, 1), Some(Status::Live));
+    assert_eq!(graph.get_status_for(1, 2), Some(Status::Unknown));
+    assert_eq!(graph.get_status_for(2, 2), Some(Status::Open));
+    assert_eq!(graph.get_status_for(3, 0), Some(Status::Dead));
+    assert_eq!(graph.get_status_for(3, 2), Some(Status::Dead));
+    assert_eq!(
+        graph.try_mark_live_for(3, 2),
+        Err(StateGraphError::AlreadyClosed(3))
+    );
+    graph.mark_live_for(2, 2);
+    assert_eq!(graph.get_status_for(1, 2), Some(Status::Live));
+    // A Live state still takes transitions and closes while it isn't Live
+    // for every class in use, so that it can be decided for the others
+    let mut graph = JumpStateGraph::new();
+    graph.mark_live_for(9, 1);
+    graph.mark_live(4);
+    assert_eq!(graph.try_add_transition(4, 5), Ok(()));
+    assert_eq!(graph.try_mark_closed(5), Ok(()));
+    assert_eq!(graph.try_mark_closed(4), Ok(()));
+    assert_eq!(graph.get_status_for(4, 0), Some(Status::Live));
+    assert_eq!(graph.get_status_for(4, 1), Some(Status::Dead));
+    // ... and once it is, closing it is redundant
+    graph.mark_live_for(9, 2);
+    graph.add_transition(9, 4);
+    assert_eq!(graph.try_mark_closed(9), Err(StateGraphError::AlreadyLive(9)));
+
+    check_feature(&Feature {
+        gen: with_classes,
+        oracle: check_classes_final,
+        markov: false,
+    });
+}
+
+/*
+    Markov mode
+*/
+
+fn check_markov_final(transactions: &[Transaction]) {
+    // Compare Naive against the definition at the end: a state reaches a
+    // state marked live with probability 1 if it is marked live, or it is
+    // Live, closed, and so is every state it can reach without going
+    // through a state marked live.
+    let mut naive = NaiveStateGraph::new_markov();
+    let mut succs: HashMap<usize, Vec<usize>> = HashMap::new();
+    let (mut closed, mut marked) = (HashSet::new(), HashSet::new());
+    for &t in transactions {
+        if naive.try_process(t).is_err() {
+            continue;
+        }
+        match t {
+            Transaction::Add(v1, v2) => succs.entry(v1).or_default().push(v2),
+            Transaction::Close(v) => {
+                closed.insert(v);
+            }
+            Transaction::Live(v) => {
+                marked.insert(v);
+            }
+            _ => (),
+        }
+    }
+    for v in ExampleInput(transactions.to_vec()).get_states() {
+        if !naive.is_seen(v) {
+            continue;
+        }
+        let mut reach = HashSet::from([v]);
+        let mut stack = vec![v];
+        while let Some(u) = stack.pop() {
+            if marked.contains(&u) {
+                continue;
+            }
+            for &w in succs.get(&u).into_iter().flatten() {
+                if reach.insert(w) {
+                    stack.push(w);
+                }
+            }
+        }
+        let one = marked.contains(&v)
+            || reach.iter().all(|&u| {
+                marked.contains(&u) || (closed.contains(&u) && naive.is_live(u))
+            });
+        let expected = match naive.get_status(v) {
+            _ if one => Some(Probability::One),
+            _ if !closed.contains(&v) => None,
+            Some(Status::Live) => Some(Probability::Positive),
+            Some(Status::Dead) => Some(Probability::Zero),
+            _ => None,
+        };
+        assert_eq!(naive.get_probability(v), expected, "{}", v);
+    }
+}
+
+#[test]
+fn test_markov() {
+    let mut graph = TarjanStateGraph::new_markov();
+    graph.mark_live(3);
+    graph.add_transition(1, 2);
+    graph.add_transition(2, 1);
+    graph.add_transition(2, 3);
+    assert_eq!(graph.get_probability(1), None);
+    graph.mark_closed(1);
+    assert_eq!(graph.get_probability(1), Some(Probability::Positive));
+    // 2 is Live, but transitions can still be added from it
+    graph.add_transition(2, 4);
+    graph.mark_closed(2);
+    assert_eq!(
+        graph.try_mark_closed(2),
+        Err(StateGraphError::AlreadyClosed(2))
+    );
+    assert_eq!(graph.get_probability(2), Some(Probability::Positive));
+    graph.mark_closed(4);
+    assert_eq!(graph.get_probability(4), Some(Probability::Zero));
+    assert_eq!(graph.get_probability(1), Some(Probability::Positive));
+
+    let mut graph = SimpleStateGraph::new_markov();
+    graph.mark_live(3);
+    graph.add_transition(1, 2);
+    graph.add_transition(2, 1);
+    graph.add_transition(2, 3);
+    graph.mark_closed(1);
+    graph.mark_closed(2);
+    assert_eq!(graph.get_probability(1), Some(Probability::One));
+    assert_eq!(graph.get_probability(3), Some(Probability::One));
+    let mut graph = SimpleStateGraph::new();
+    graph.mark_live(3);
+    assert!(!graph.is_almost_sure(3));
+
+    check_feature(&Feature {
+        gen: plain,
+        oracle: check_markov_final,
+        markov: true,
+    });
+}
+
+/*
+    Merging states
+*/
+
+fn with_merges(
+    transactions: &[Transaction],
+    rng: &mut Rng,
+) -> Vec<Transaction> {
+    // Declare a few pairs of states equal, spread through the transactions,
+    // and drop the NotReachable hints that no longer hold: merged states
+    // can reach whatever either could
+    let mut result = transactions.to_vec();
+    let mut canon: Vec<usize> = (0..8).collect();
+    for _ in 0..3 {
+        let (v1, v2) = (rng.below(8), rng.below(8));
+        let pos = rng.below(result.len() + 1);
+        result.insert(pos, Transaction::Merge(v1, v2));
+        let (c1, c2) = (canon[v1], canon[v2]);
+        for c in canon.iter_mut().filter(|c| **c == c1) {
+            *c = c2;
+        }
+    }
+    let mut succs = vec![Vec::new(); 8];
+    for &t in &result {
+        if let Transaction::Add(v1, v2) = t {
+            succs[canon[v1]].push(canon[v2]);
+        }
+    }
+    let reaches = |v1: usize, v2: usize| {
+        let mut seen = HashSet::from([canon[v1]]);
+        let mut stack = vec![canon[v1]];
+        while let Some(u) = stack.pop() {
+            for &w in &succs[u] {
+                if seen.insert(w) {
+                    stack.push(w);
+                }
+            }
+        }
+        seen.contains(&canon[v2])
+    };
+    result.retain(|&t| match t {
+        Transaction::NotReachable(v1, v2) => !reaches(v1, v2),
+        _ => true,
+    });
+    result
+}
+
+fn check_merge_small<G: StateGraph>() {
+    let mut graph = G::new();
+    graph.add_transition(1, 2);
+    graph.add_transition(3, 4);
+    graph.mark_closed(1);
+    graph.mark_closed(3);
+    assert_eq!(graph.get_status(1), Some(Status::Unknown));
+    // Merging 2 with a closed state closes it
+    graph.merge(2, 3);
+    assert_eq!(graph.get_status(2), Some(Status::Unknown));
+    assert_eq!(
+        graph.try_add_transition(2, 5),
+        Err(StateGraphError::AlreadyClosed(2))
+    );
+    graph.mark_closed(4);
+    assert_eq!(graph.get_status(1), Some(Status::Dead));
+    assert_eq!(graph.get_status(2), Some(Status::Dead));
+    assert_eq!(
+        graph.try_merge(1, 5),
+        Err(StateGraphError::ContradictoryMerge(1, 5))
+    );
+    // A cycle through merged states, then Live through either
+    graph.add_transition(5, 6);
+    graph.add_transition(7, 5);
+    graph.mark_closed(5);
+    graph.merge(6, 7);
+    graph.mark_closed(6);
+    assert_eq!(graph.get_status(5), Some(Status::Dead));
+    assert_eq!(graph.get_status(7), Some(Status::Dead));
+    graph.add_transition(8, 9);
+    graph.mark_closed(8);
+    graph.mark_live(10);
+    graph.merge(9, 10);
+    assert_eq!(graph.get_status(8), Some(Status::Live));
+    assert_eq!(graph.get_status(9), Some(Status::Live));
+    assert_eq!(graph.distance_to_live(8), Some(2));
+    // Closing a merged state closes every state merged with it, including
+    // when merged with a closed state later (which matters for the other
+    // classes, where Live states are only Dead if closed)
+    let mut graph = G::new();
+    graph.add_transition(1, 4);
+    graph.merge(1, 2);
+    graph.add_transition(3, 2);
+    graph.mark_closed(3);
+    graph.mark_closed(1);
+    assert!(graph.is_marked_closed(2));
+    graph.mark_live(1);
+    graph.mark_closed(4);
+    assert_eq!(graph.get_status_for(3, 1), Some(Status::Dead));
+    graph.merge(5, 6);
+    graph.add_transition(7, 8);
+    graph.mark_closed(7);
+    graph.merge(7, 5);
+    assert!(graph.is_marked_closed(6));
+    // Distances count the transitions both ways between merged states, even
+    // when the first of them forms a cycle
+    let mut graph = G::new();
+    graph.add_transition(10, 11);
+    graph.merge(10, 12);
+    graph.add_transition(13, 12);
+    graph.mark_closed(13);
+    graph.merge(10, 13);
+    graph.mark_live(11);
+    assert_eq!(graph.distance_to_live(13), Some(2));
+    // Including between states already found on a cycle
+    let mut graph = G::new();
+    graph.add_transition(20, 21);
+    graph.add_transition(21, 22);
+    graph.add_transition(22, 20);
+    graph.add_transition(22, 23);
+    graph.mark_closed(20);
+    graph.mark_closed(21);
+    graph.mark_closed(22);
+    graph.merge(20, 22);
+    graph.mark_live(23);
+    assert_eq!(graph.distance_to_live(20), Some(2));
+}
+
+#[test]
+fn test_merge() {
+    check_merge_small::<NaiveStateGraph>();
+    check_merge_small::<SimpleStateGraph>();
+    check_merge_small::<TarjanStateGraph>();
+    check_merge_small::<JumpStateGraph>();
+    let mut graph = NaiveStateGraph::new_buchi().unwrap();
+    assert_eq!(
+        graph.try_merge(1, 2),
+        Err(StateGraphError::MergeUnsupported(1, 2))
+    );
+
+    check_feature(&Feature {
+        gen: with_merges,
+        oracle: check_status_final,
+        markov: false,
+    });
+}
+
+/*
+    Subsumption hints
+*/
+
+fn with_subsumed(
+    transactions: &[Transaction],
+    rng: &mut Rng,
+) -> Vec<Transaction> {
+    // Add a few Subsumed hints, spread through the transactions, keeping
+    // only those that hold at the end: if the first state is live without
+    // hints then so is the second. (And only those not deciding a state
+    // Dead before it is marked live, which the hint would contradict.)
+    let mut naive = NaiveStateGraph::new();
+    for &t in transactions {
+        let _ = naive.try_process(t);
+    }
+    let live = |v| naive.is_seen(v) && naive.is_live(v);
+    let mut result = transactions.to_vec();
+    for _ in 0..4 {
+        let (v1, v2) = (rng.below(8), rng.below(8));
+        if !live(v1) || live(v2) {
+            let pos = rng.below(result.len() + 1);
+            let mut candidate = result.clone();
+            candidate.insert(pos, Transaction::Subsumed(v1, v2));
+            if fuzz::drop_invalid(candidate.clone()).len() == candidate.len() {
+                result = candidate;
+            }
+        }
+    }
+    result
+}
+
+fn check_subsumed_final(transactions: &[Transaction]) {
+    // Valid hints only decide states earlier: at the end, Live and Open
+    // states are as without hints, and Dead states are still Dead. (States
+    // only seen in one are skipped: with hints, a transition from a state
+    // already live is redundant.)
+    let mut with = NaiveStateGraph::new();
+    let mut without = NaiveStateGraph::new();
+    for &t in transactions {
+        let _ = with.try_process(t);
+        if !matches!(t, Transaction::Subsumed(_, _)) {
+            let _ = without.try_process(t);
+        }
+    }
+    for v in ExampleInput(transactions.to_vec()).get_states() {
+        if !without.is_seen(v) || !with.is_seen(v) {
+            continue;
+        }
+        let expected = without.get_status(v);
+        match with.get_status(v) {
+            Some(Status::Dead) => assert_ne!(expected, Some(Status::Live)),
+            status => assert_eq!(status, expected, "{}", v),
+        }
+        if expected == Some(Status::Dead) {
+            assert!(with.is_dead(v));
+        }
+    }
+}
+
+fn check_subsumed_small<G: StateGraph>() {
+    let mut graph = G::new();
+    // Live propagates to the superset, and back along edges
+    graph.add_transition(3, 2);
+    graph.mark_closed(3);
+    graph.subsumed(1, 2);
+    assert_eq!(graph.get_status(2), Some(Status::Open));
+    graph.mark_live(1);
+    assert_eq!(graph.get_status(2), Some(Status::Live));
+    assert_eq!(graph.get_status(3), Some(Status::Live));
+    // Dead propagates to the subset once closed, even with open successors
+    graph.add_transition(4, 5);
+    graph.mark_closed(4);
+    graph.mark_closed(5);
+    assert_eq!(graph.get_status(4), Some(Status::Dead));
+    graph.add_transition(6, 7);
+    graph.subsumed(6, 5);
+    assert_eq!(graph.get_status(6), Some(Status::Open));
+    graph.mark_closed(6);
+    assert_eq!(graph.get_status(6), Some(Status::Dead));
+    // States reachable from it are dead too, but stay open until closed
+    assert_eq!(graph.get_status(7), Some(Status::Open));
+    graph.add_transition(7, 8);
+    graph.mark_closed(7);
+    assert_eq!(graph.get_status(7), Some(Status::Dead));
+    assert_eq!(graph.get_status(8), Some(Status::Open));
+    assert_eq!(
+        graph.try_subsumed(1, 4),
+        Err(StateGraphError::ContradictorySubsumed(1, 4))
+    );
+    assert_eq!(graph.try_subsumed(9, 9), Ok(()));
+    assert_eq!(graph.get_status(9), None);
+    // Including through a merge with an open state
+    graph.add_transition(20, 21);
+    graph.add_transition(21, 20);
+    graph.add_transition(22, 23);
+    graph.mark_closed(21);
+    graph.mark_closed(24);
+    graph.subsumed(22, 24);
+    graph.mark_closed(22);
+    assert_eq!(graph.get_status(21), Some(Status::Unknown));
+    graph.merge(20, 23);
+    assert_eq!(graph.get_status(20), Some(Status::Open));
+    assert_eq!(graph.get_status(21), Some(Status::Dead));
+}
+
+#[test]
+fn test_subsumed() {
+    check_subsumed_small::<NaiveStateGraph>();
+    check_subsumed_small::<SimpleStateGraph>();
+    check_subsumed_small::<TarjanStateGraph>();
+    check_subsumed_small::<JumpStateGraph>();
+    let mut graph = NaiveStateGraph::new_markov();
+    assert_eq!(
+        graph.try_subsumed(1, 2),
+        Err(StateGraphError::SubsumedUnsupported(1, 2))
+    );
+
+    check_feature(&Feature {
+        gen: with_subsumed,
+        oracle: check_subsumed_final,
+        markov: false,
+    });
+}
@@ -0,0 +1,140 @@
+/*
+    Test the line protocol for driving a state graph (repl.rs).
+*/
+
+use state_graph::algorithm::{
+    JumpStateGraph, NaiveStateGraph, SimpleStateGraph, TarjanStateGraph,
+};
+use state_graph::constants::*;
+use state_graph::driver;
+use state_graph::example::{Example, ExampleOutput};
+use state_graph::interface::{StateGraph, Transaction};
+use state_graph::repl::{Command, Repl};
+use std::time::Duration;
+
+fn command_str(t: Transaction) -> String {
+    match t {
+        Transaction::Add(v1, v2) => format!("add {} {}", v1, v2),
+        Transaction::AddLabeled(v1, v2, l) => {
+            format!("add {} {} {}", v1, v2, l)
+        }
+        Transaction::Close(v) => format!("close {}", v),
+        Transaction::Live(v) => format!("live {}", v),
+        Transaction::Initial(v) => format!("init {}", v),
+        Transaction::Universal(v) => format!("univ {}", v),
+        Transaction::Accepting(v) => format!("accept {}", v),
+        Transaction::LiveFor(v, c) => format!("live {} {}", v, c),
+        Transaction::NotReachable(v1, v2) => format!("unreach {} {}", v1, v2),
+        Transaction::Merge(v1, v2) => format!("merge {} {}", v1, v2),
+        Transaction::Subsumed(v1, v2) => format!("subsumed {} {}", v1, v2),
+    }
+}
+
+fn check_repl<G: StateGraph>(basename: &str) {
+    // Send the example as commands through the protocol, and check that the
+    // final dump is the same as running the example directly
+    let example = Example::load_from(basename);
+    let mut input = String::new();
+    for &t in &example.input.0 {
+        input.push_str(&command_str(t));
+        input.push('\n');
+    }
+    input.push_str("dump\n");
+    let mut output = Vec::new();
+    Repl::<G>::new().run(input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), example.len() + 1);
+    assert!(lines[..example.len()].iter().all(|&line| line == "ok"));
+    let dump: ExampleOutput =
+        serde_json::from_str(lines[example.len()]).unwrap();
+    let timeout = Duration::from_secs(10);
+    let expected = example.run_with_timeout(&mut G::new(), timeout);
+    assert_eq!(&dump, expected.unwrap_output());
+}
+
+#[test]
+fn test_repl_examples() {
+    let mut basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "unkloop_20"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "nestedloop2_sat"));
+    for basename in &basenames {
+        check_repl::<NaiveStateGraph>(basename);
+        check_repl::<SimpleStateGraph>(basename);
+        check_repl::<TarjanStateGraph>(basename);
+        check_repl::<JumpStateGraph>(basename);
+    }
+}
+
+#[test]
+fn test_repl_session() {
+    let mut repl = Repl::<TarjanStateGraph>::new();
+    assert_eq!(repl.respond("  "), None);
+    assert_eq!(repl.respond("# comment"), None);
+    assert_eq!(repl.respond("add 1 2").unwrap(), "ok");
+    assert_eq!(repl.respond("add 2 1").unwrap(), "ok");
+    assert_eq!(repl.respond("close 1").unwrap(), "ok");
+    assert_eq!(repl.respond("status 1").unwrap(), "unknown");
+    assert_eq!(repl.respond("status 3").unwrap(), "open");
+    assert_eq!(
+        repl.respond("add 1 3").unwrap(),
+        "error: state already closed: 1"
+    );
+    assert_eq!(repl.respond("close 2").unwrap(), "ok");
+    assert_eq!(repl.respond("status 2").unwrap(), "dead");
+    assert_eq!(repl.respond("add 4 4").unwrap(), "ok");
+    assert_eq!(repl.respond("live 4").unwrap(), "ok");
+    assert_eq!(repl.respond("close 4").unwrap(), "ok");
+    assert_eq!(repl.respond("status 4").unwrap(), "live");
+    assert_eq!(
+        repl.respond("dump").unwrap(),
+        r#"{"live":[4],"dead":[1,2],"unknown":[],"open":[]}"#
+    );
+    assert_eq!(repl.respond("status 4 1").unwrap(), "open");
+    assert_eq!(
+        repl.respond("live 2 1").unwrap(),
+        "error: state already closed: 2"
+    );
+    assert_eq!(repl.respond("add 7 4").unwrap(), "ok");
+    assert_eq!(repl.respond("live 4 1").unwrap(), "ok");
+    assert_eq!(repl.respond("status 7 1").unwrap(), "live");
+    assert_eq!(repl.respond("add 5 6 97").unwrap(), "ok");
+    assert_eq!(
+        repl.respond("merge 1 6").unwrap(),
+        "error: contradictory merge: only one of 1 and 6 is dead"
+    );
+    assert_eq!(repl.respond("merge 6 7").unwrap(), "ok");
+    assert_eq!(repl.respond("status 5").unwrap(), "live");
+    assert!(repl.respond("close").unwrap().starts_with("error: "));
+    assert!(repl.respond("add 1").unwrap().starts_with("error: "));
+    assert!(repl.respond("frobnicate 1").unwrap().starts_with("error: "));
+    assert!(repl.respond("status -1").unwrap().starts_with("error: "));
+}
+
+#[test]
+fn test_parse_command() {
+    assert_eq!(
+        " unreach  3 4 ".parse(),
+        Ok(Command::Process(Transaction::NotReachable(3, 4)))
+    );
+    assert_eq!("status 5".parse(), Ok(Command::Status(5)));
+    assert_eq!("dump".parse(), Ok(Command::Dump));
+    assert!("add 1".parse::<Command>().is_err());
+    assert!("dump 1".parse::<Command>().is_err());
+    assert_eq!(
+        "live 1 2".parse(),
+        Ok(Command::Process(Transaction::LiveFor(1, 2)))
+    );
+    assert_eq!("status 1 2".parse(), Ok(Command::StatusFor(1, 2)));
+    assert!("live 1 2 3".parse::<Command>().is_err());
+    assert_eq!(
+        "merge 1 2".parse(),
+        Ok(Command::Process(Transaction::Merge(1, 2)))
+    );
+    assert!("merge 1".parse::<Command>().is_err());
+    assert_eq!(
+        "subsumed 1 2".parse(),
+        Ok(Command::Process(Transaction::Subsumed(1, 2)))
+    );
+    assert!("subsumed 1 2 3".parse::<Command>().is_err());
+}
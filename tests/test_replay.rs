@@ -0,0 +1,80 @@
+/*
+    Test streaming replay against running the fully loaded example.
+*/
+
+use state_graph::algorithm::{
+    JumpStateGraph, NaiveStateGraph, SimpleStateGraph, TarjanStateGraph,
+};
+use state_graph::constants::*;
+use state_graph::driver;
+use state_graph::example::{Example, ExampleResult, Replay};
+use state_graph::interface::StateGraph;
+use std::fs;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn replay_bytes<G: StateGraph>(
+    example: &Example,
+    bytes: &[u8],
+) -> ExampleResult {
+    let mut graph = G::new();
+    Replay::new(&mut graph, TIMEOUT)
+        .replay_from(bytes, example.expected.as_ref())
+        .unwrap()
+}
+
+fn check_replay<G: StateGraph>(basename: &str) {
+    let example = Example::load_from(basename);
+    let expected = example.run_with_timeout(&mut G::new(), TIMEOUT);
+    let json = fs::read(format!("{}{}", basename, EXAMPLE_IN_EXT)).unwrap();
+    let mut binary = Vec::new();
+    example.input.write_binary(&mut binary).unwrap();
+    for bytes in &[json, binary] {
+        let result = replay_bytes::<G>(&example, bytes);
+        assert!(result.is_correct());
+        assert_eq!(result.unwrap_output(), expected.unwrap_output());
+        if cfg!(debug_assertions) {
+            assert_eq!(result.summary(), expected.summary());
+        }
+    }
+}
+
+#[test]
+fn test_replay() {
+    let mut basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    basenames.push(format!("{}/{}", EX_DIR_GENERATED, "reverseunkloop_100"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_LOOP, "nestedloop2_sat"));
+    basenames.push(format!("{}/{}", EX_DIR_REGEX_DATE, "date_minimal_sat"));
+    for basename in &basenames {
+        check_replay::<NaiveStateGraph>(basename);
+        check_replay::<SimpleStateGraph>(basename);
+        check_replay::<TarjanStateGraph>(basename);
+        check_replay::<JumpStateGraph>(basename);
+    }
+}
+
+#[test]
+fn test_replay_timeout() {
+    let basename = format!("{}/{}", EX_DIR_GENERATED, "line_100");
+    let example = Example::load_from(&basename);
+    let json = fs::read(format!("{}{}", basename, EXAMPLE_IN_EXT)).unwrap();
+    let mut binary = Vec::new();
+    example.input.write_binary(&mut binary).unwrap();
+    for bytes in &[json, binary] {
+        let mut graph = SimpleStateGraph::new();
+        let result = Replay::new(&mut graph, Duration::from_secs(0))
+            .replay_from(bytes.as_slice(), None)
+            .unwrap();
+        assert!(matches!(result, ExampleResult::Timeout));
+    }
+}
+
+#[test]
+fn test_replay_malformed() {
+    for &bytes in &[&b"[{\"Add\": [0, 1]}, {\"Close\": }]"[..], b"[", b"{}"] {
+        let mut graph = SimpleStateGraph::new();
+        let result = Replay::new(&mut graph, TIMEOUT).replay_from(bytes, None);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,104 @@
+/*
+    Test the union-find structure with undo (union_find.rs) directly.
+*/
+
+use state_graph::union_find::UnionFind;
+
+fn new_with(n: usize) -> UnionFind {
+    let mut uf = UnionFind::new();
+    for i in 0..n {
+        assert_eq!(uf.alloc(), i);
+    }
+    uf
+}
+
+fn classes(uf: &UnionFind) -> Vec<usize> {
+    (0..uf.len()).map(|i| uf.find(i)).collect()
+}
+
+#[test]
+fn test_union_find() {
+    let mut uf = new_with(6);
+    assert_eq!(uf.len(), 6);
+    assert_eq!(classes(&uf), vec![0, 1, 2, 3, 4, 5]);
+    assert!(uf.union(0, 1).is_some());
+    assert!(uf.union(2, 3).is_some());
+    assert!(uf.union(1, 0).is_none());
+    assert_eq!(uf.find(0), uf.find(1));
+    assert_ne!(uf.find(0), uf.find(2));
+    assert!(uf.union(1, 3).is_some());
+    assert!(uf.union(0, 2).is_none());
+    let root = uf.find(0);
+    assert!((0..4).all(|i| uf.find(i) == root));
+    assert_eq!(uf.find(4), 4);
+    assert_eq!(uf.find(5), 5);
+}
+
+#[test]
+fn test_union_by_rank() {
+    // The root of the higher-rank set stays the root
+    let mut uf = new_with(5);
+    uf.union(0, 1);
+    uf.union(0, 2);
+    let root = uf.find(0);
+    uf.union(3, 0);
+    assert_eq!(uf.find(3), root);
+    uf.union(0, 4);
+    assert_eq!(uf.find(4), root);
+}
+
+#[test]
+fn test_undo() {
+    let mut uf = new_with(4);
+    let before = classes(&uf);
+    let u1 = uf.union(0, 1).unwrap();
+    let after_u1 = classes(&uf);
+    let u2 = uf.union(2, 3).unwrap();
+    let u3 = uf.union(0, 3).unwrap();
+    assert!((0..4).all(|i| uf.find(i) == uf.find(0)));
+    uf.undo_union(u3);
+    assert_eq!(uf.find(0), uf.find(1));
+    assert_eq!(uf.find(2), uf.find(3));
+    assert_ne!(uf.find(0), uf.find(2));
+    uf.undo_union(u2);
+    assert_eq!(classes(&uf), after_u1);
+    uf.undo_union(u1);
+    assert_eq!(classes(&uf), before);
+
+    assert_eq!(uf.alloc(), 4);
+    let u4 = uf.union(4, 0).unwrap();
+    uf.undo_union(u4);
+    uf.undo_alloc();
+    assert_eq!(uf.len(), 4);
+    assert_eq!(classes(&uf), before);
+}
+
+#[test]
+fn test_undo_rank_tie() {
+    // With equal ranks, the second argument becomes the root, and its rank
+    // increases; undoing the union must restore the rank, or the next tie
+    // is broken the other way
+    let mut uf = new_with(3);
+    let u = uf.union(0, 1).unwrap();
+    assert_eq!(uf.find(0), 1);
+    uf.undo_union(u);
+    assert_eq!(uf.find(0), 0);
+    uf.union(1, 2);
+    assert_eq!(uf.find(1), 2);
+
+    // Nested ties: two rank-1 sets joined (rank 2), then undone in order
+    let mut uf = new_with(4);
+    let u1 = uf.union(0, 1).unwrap();
+    let u2 = uf.union(2, 3).unwrap();
+    let u3 = uf.union(1, 3).unwrap();
+    assert_eq!(uf.find(0), 3);
+    uf.undo_union(u3);
+    uf.undo_union(u2);
+    uf.undo_union(u1);
+    assert_eq!(classes(&uf), vec![0, 1, 2, 3]);
+    // All ranks are back to 0, so ties are broken as for fresh sets
+    uf.union(3, 2);
+    uf.union(0, 2);
+    assert_eq!(uf.find(0), 2);
+    assert_eq!(uf.find(3), 2);
+}
@@ -0,0 +1,134 @@
+/*
+    Test the validation of NotReachable hints (validate.rs), on small
+    graphs and on random sequences with wrong hints added.
+*/
+
+use state_graph::algorithm::{
+    JumpStateGraph, NaiveStateGraph, SimpleStateGraph, TarjanStateGraph,
+};
+use state_graph::fuzz::{self, Rng};
+use state_graph::interface::{StateGraph, StateGraphError, Transaction};
+use state_graph::validate::{HintValidator, HintViolation, ValidationError};
+
+fn violation(hint: (usize, usize), path: &[usize]) -> ValidationError {
+    ValidationError::Hint(HintViolation { hint, path: path.to_vec() })
+}
+
+fn check_validate_small<G: StateGraph>() {
+    let mut validator = HintValidator::new(G::new());
+    // A hint contradicted later, reported once
+    assert_eq!(validator.try_process(Transaction::NotReachable(1, 3)), Ok(()));
+    assert_eq!(validator.try_process(Transaction::Add(1, 2)), Ok(()));
+    assert_eq!(
+        validator.try_process(Transaction::Add(2, 3)),
+        Err(violation((1, 3), &[1, 2, 3]))
+    );
+    assert!(validator.graph().is_seen(3));
+    assert_eq!(validator.try_process(Transaction::Add(3, 4)), Ok(()));
+    // A hint contradicted already isn't processed
+    assert_eq!(
+        validator.try_process(Transaction::NotReachable(2, 4)),
+        Err(violation((2, 4), &[2, 3, 4]))
+    );
+    assert_eq!(
+        validator.try_process(Transaction::Add(4, 4)),
+        Err(ValidationError::Graph(StateGraphError::SelfLoop(4)))
+    );
+    // Through a merge
+    assert_eq!(validator.try_process(Transaction::NotReachable(5, 7)), Ok(()));
+    assert_eq!(validator.try_process(Transaction::Add(5, 6)), Ok(()));
+    assert_eq!(
+        validator.try_process(Transaction::Merge(6, 7)),
+        Err(violation((5, 7), &[5, 6, 7]))
+    );
+    // Through a transition from a Live state (redundant for the graph)
+    assert_eq!(validator.try_process(Transaction::NotReachable(8, 9)), Ok(()));
+    assert_eq!(validator.try_process(Transaction::Live(8)), Ok(()));
+    assert_eq!(
+        validator.try_process(Transaction::Add(8, 9)),
+        Err(violation((8, 9), &[8, 9]))
+    );
+    let err = validator.try_process(Transaction::NotReachable(8, 8));
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        "NotReachable(8, 8) contradicted by path: [8]"
+    );
+}
+
+#[test]
+fn test_validate_small() {
+    check_validate_small::<NaiveStateGraph>();
+    check_validate_small::<SimpleStateGraph>();
+    check_validate_small::<TarjanStateGraph>();
+    check_validate_small::<JumpStateGraph>();
+}
+
+fn check_validate(transactions: &[Transaction], hint: (usize, usize)) {
+    // Every violation reported is a path of the transitions processed so
+    // far, the given hint is reported iff it doesn't hold at the end, and
+    // otherwise the results are the same as without validation.
+    // (On Naive, which ignores hints, so that a wrong one is harmless.)
+    let mut validator = HintValidator::new(NaiveStateGraph::new());
+    let mut graph = NaiveStateGraph::new();
+    let mut edges = Vec::new();
+    let mut reported = false;
+    for &t in transactions {
+        let result = validator.try_process(t);
+        match t {
+            Transaction::Add(v1, v2) => edges.push((v1, v2)),
+            Transaction::Merge(v1, v2) => {
+                edges.push((v1, v2));
+                edges.push((v2, v1));
+            }
+            _ => (),
+        }
+        match result {
+            Err(ValidationError::Hint(violation)) => {
+                let path = &violation.path;
+                assert_eq!(path.first(), Some(&violation.hint.0));
+                assert_eq!(path.last(), Some(&violation.hint.1));
+                for step in path.windows(2) {
+                    assert!(edges.contains(&(step[0], step[1])));
+                }
+                reported |= violation.hint == hint;
+                if !matches!(t, Transaction::NotReachable(_, _)) {
+                    graph.process(t);
+                }
+            }
+            result => {
+                assert_eq!(result, graph.try_process(t).map_err(Into::into))
+            }
+        }
+    }
+    let mut reachable = vec![hint.0];
+    let mut stack = vec![hint.0];
+    while let Some(u) = stack.pop() {
+        for &(v1, v2) in &edges {
+            if v1 == u && !reachable.contains(&v2) {
+                reachable.push(v2);
+                stack.push(v2);
+            }
+        }
+    }
+    assert_eq!(reported, reachable.contains(&hint.1), "{:?}", transactions);
+}
+
+#[test]
+fn test_validate_random() {
+    // Hints generated by fuzz hold; one added at random may not
+    let mut rng = Rng::new(24);
+    for _ in 0..200 {
+        let mut transactions = fuzz::gen_plain_transactions(&mut rng, 8);
+        let mut validator = HintValidator::new(JumpStateGraph::new());
+        for &t in &transactions {
+            assert!(!matches!(
+                validator.try_process(t),
+                Err(ValidationError::Hint(_))
+            ));
+        }
+        let hint = (rng.below(8), rng.below(8));
+        let pos = rng.below(transactions.len() + 1);
+        transactions.insert(pos, Transaction::NotReachable(hint.0, hint.1));
+        check_validate(&transactions, hint);
+    }
+}